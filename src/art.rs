@@ -1,7 +1,14 @@
 //! Drawing functions.
+//!
+//! The contract runs on wasm32, where `usize` and `isize` are only 32 bits wide.
+//! Arithmetic that can outgrow them, like squared distances, uses explicitly sized integers.
+//!
+//! A panic aborts the whole call, so drawing never panics on coordinates, which often come from a
+//! token's random traits. Pixels are read and written through checked accessors, like
+//! `Image::plot`, and anything outside the image is clipped rather than drawn.
 
-use crate::utils::{Color, FnvHasher, Pixels};
-use alloc::boxed::Box;
+use crate::utils::{self, Color, FnvHasher, Pixels, TraitSelector, GLYPH_HEIGHT, GLYPH_WIDTH};
+use alloc::{boxed::Box, format, vec, vec::Vec};
 use alloy_primitives::Address;
 use fastrand::Rng;
 use stylus_sdk::alloy_primitives::U256;
@@ -12,19 +19,225 @@ pub struct Image<const R: usize, const C: usize> {
 }
 
 /// Represents a cell on the grid.
-struct Cell {
-    x: usize,
-    y: usize,
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cell {
+    pub x: usize,
+    pub y: usize,
 }
 
 impl Cell {
-    fn new(x: usize, y: usize) -> Cell {
+    pub fn new(x: usize, y: usize) -> Cell {
         Cell { x, y }
     }
 }
 
-/// If true, never leaves a line connected by just a diagonal
-const THICK_LINES: bool = false;
+/// The direction along which a linear gradient blends.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(not(test), allow(dead_code))]
+pub enum GradientAngle {
+    /// Left to right.
+    Horizontal,
+    /// Top to bottom.
+    Vertical,
+    /// Top-left to bottom-right.
+    Diagonal,
+    /// Top-right to bottom-left.
+    AntiDiagonal,
+}
+
+/// Integer square root, rounded down.
+fn isqrt(value: u64) -> u64 {
+    if value < 2 {
+        return value;
+    }
+    let mut x = value;
+    let mut y = x.div_ceil(2);
+    while y < x {
+        x = y;
+        y = (x + value / x) / 2;
+    }
+    x
+}
+
+/// The largest radius an ellipse is drawn with; larger radii are clamped to it.
+/// Keeps [`trace_ellipse`]'s error terms well within `i64`, and is far bigger than any image.
+const MAX_RADIUS: usize = 1 << 19;
+
+/// Walks the first quadrant of an ellipse with radii `a` and `b`, clamped to [`MAX_RADIUS`],
+/// visiting each pixel's offset from the center, with `y` pointing up.
+///
+/// The error terms grow with the cube of the radii, so they're `i64` even where
+/// `isize` is 32 bits, as on wasm32.
+fn trace_ellipse(a: usize, b: usize, mut visit: impl FnMut(usize, usize)) {
+    let (a, b) = (a.min(MAX_RADIUS), b.min(MAX_RADIUS));
+    let (a2, b2) = ((a as i64).pow(2), (b as i64).pow(2));
+    let mut x = a; // IV. quadrant
+    let mut y = 0;
+    let mut dx = (1 - 2 * a as i64) * b2;
+    let mut dy = a2;
+    let mut error = dx + dy;
+    loop {
+        visit(x, y);
+        let error2 = error * 2;
+        if error2 >= dx {
+            if x == 0 {
+                break;
+            }
+            x -= 1;
+            dx += 2 * b2;
+            error += dx;
+        }
+        if error2 <= dy {
+            y += 1;
+            dy += 2 * a2;
+            error += dy;
+        }
+    }
+    // Handle very flat ellipses (a=1)
+    while y < b {
+        y += 1;
+        visit(0, y);
+    }
+}
+
+/// The counter-clockwise angle of (`dx`, `dy`) from the positive x axis, in hundredths of a degree.
+/// Uses the approximation atan(z) ≈ 45z + 15.64z(1 - z) degrees, which is within 0.3°.
+fn centidegrees(dx: isize, dy: isize) -> u32 {
+    let (ax, ay) = (dx.unsigned_abs(), dy.unsigned_abs());
+    if ax == 0 && ay == 0 {
+        return 0;
+    }
+    let (n, d) = (ax.min(ay) as u64, ax.max(ay) as u64);
+    let atan = ((4500 * n + 1564 * n * (d - n) / d) / d) as u32;
+    let first = if ay <= ax { atan } else { 9000 - atan };
+    match (dx >= 0, dy >= 0) {
+        (true, true) => first,
+        (false, true) => 18000 - first,
+        (false, false) => 18000 + first,
+        (true, false) => (36000 - first) % 36000,
+    }
+}
+
+/// How [`Image::blend_with`] combines each channel of two layers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(not(test), allow(dead_code))]
+pub enum BlendMode {
+    /// The top layer replaces the bottom.
+    Normal,
+    /// Darkens: white is the identity and black stays black.
+    Multiply,
+    /// Lightens: black is the identity and white stays white.
+    Screen,
+    /// Multiplies the dark parts of the bottom layer and screens the light parts.
+    Overlay,
+}
+
+impl BlendMode {
+    /// Blends channel `top` onto channel `bottom`.
+    fn apply(self, bottom: u8, top: u8) -> u8 {
+        let (a, b) = (bottom as u32, top as u32);
+        let multiply = |a: u32, b: u32| a * b / 255;
+        let screen = |a: u32, b: u32| 255 - (255 - a) * (255 - b) / 255;
+        (match self {
+            Self::Normal => b,
+            Self::Multiply => multiply(a, b),
+            Self::Screen => screen(a, b),
+            Self::Overlay if a < 128 => multiply(2 * a, b),
+            Self::Overlay => screen(2 * a - 255, b),
+        }) as u8
+    }
+}
+
+// Cohen–Sutherland region codes
+const INSIDE: u8 = 0b0000;
+const LEFT: u8 = 0b0001;
+const RIGHT: u8 = 0b0010;
+const TOP: u8 = 0b0100;
+const BOTTOM: u8 = 0b1000;
+
+/// Fixed-point scale of the coordinates used to split cubic Bézier curves.
+const BEZIER_SCALE: i128 = 1 << 16;
+
+/// How many times a cubic Bézier curve may be halved before it's drawn.
+const MAX_BEZIER_DEPTH: u32 = 8;
+
+/// Divides, rounding to the nearest integer.
+fn div_round(numerator: i128, denominator: i128) -> i128 {
+    let (n, d) = if denominator < 0 {
+        (-numerator, -denominator)
+    } else {
+        (numerator, denominator)
+    };
+    (2 * n + d).div_euclid(2 * d)
+}
+
+/// Clips the segment from `start` to `end` to a `width` by `height` rectangle
+/// using the Cohen–Sutherland algorithm. Returns `None` if no part is visible.
+fn clip_line(start: Cell, end: Cell, width: usize, height: usize) -> Option<(Cell, Cell)> {
+    let point = |cell: Cell| (cell.x as i128, cell.y as i128);
+    clip_segment(point(start), point(end), width, height)
+}
+
+/// Like [`clip_line`], but accepts endpoints with negative coordinates.
+fn clip_segment(
+    start: (i128, i128),
+    end: (i128, i128),
+    width: usize,
+    height: usize,
+) -> Option<(Cell, Cell)> {
+    if width == 0 || height == 0 {
+        return None;
+    }
+    let (x_max, y_max) = (width as i128 - 1, height as i128 - 1);
+    let region = |x: i128, y: i128| {
+        let mut code = INSIDE;
+        if x < 0 {
+            code |= LEFT;
+        } else if x > x_max {
+            code |= RIGHT;
+        }
+        if y < 0 {
+            code |= TOP;
+        } else if y > y_max {
+            code |= BOTTOM;
+        }
+        code
+    };
+
+    let ((mut x0, mut y0), (mut x1, mut y1)) = (start, end);
+    let mut code0 = region(x0, y0);
+    let mut code1 = region(x1, y1);
+    loop {
+        if code0 | code1 == INSIDE {
+            let cell = |x: i128, y: i128| Cell::new(x as usize, y as usize);
+            return Some((cell(x0, y0), cell(x1, y1)));
+        }
+        if code0 & code1 != INSIDE {
+            return None;
+        }
+
+        // Move the outside endpoint onto the edge it crosses
+        let outside = if code0 != INSIDE { code0 } else { code1 };
+        let (dx, dy) = (x1 - x0, y1 - y0);
+        let (x, y) = if outside & BOTTOM != 0 {
+            (x0 + div_round(dx * (y_max - y0), dy), y_max)
+        } else if outside & TOP != 0 {
+            (x0 + div_round(dx * -y0, dy), 0)
+        } else if outside & RIGHT != 0 {
+            (x_max, y0 + div_round(dy * (x_max - x0), dx))
+        } else {
+            (0, y0 + div_round(dy * -x0, dx))
+        };
+
+        if outside == code0 {
+            (x0, y0) = (x, y);
+            code0 = region(x0, y0);
+        } else {
+            (x1, y1) = (x, y);
+            code1 = region(x1, y1);
+        }
+    }
+}
 
 // Drawing algorithms are from http://members.chello.at/~easyfilter/Bresenham.pdf
 impl<const R: usize, const C: usize> Image<R, C> {
@@ -35,8 +248,71 @@ impl<const R: usize, const C: usize> Image<R, C> {
         }
     }
 
-    /// Draws a line from `start` to `end` with the given `color`
-    fn draw_line(&mut self, start: Cell, end: Cell, color: Color) {
+    /// Iterates over every pixel as `(x, y, color)`, row by row from the top left.
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn pixels_iter(&self) -> impl Iterator<Item = (usize, usize, Color)> + '_ {
+        self.pixels
+            .iter()
+            .enumerate()
+            .flat_map(|(y, row)| row.iter().enumerate().map(move |(x, &color)| (x, y, color)))
+    }
+
+    /// Like [`Image::pixels_iter`], but lets each pixel be changed in place.
+    pub fn pixels_iter_mut(&mut self) -> impl Iterator<Item = (usize, usize, &mut Color)> + '_ {
+        self.pixels.iter_mut().enumerate().flat_map(|(y, row)| {
+            row.iter_mut()
+                .enumerate()
+                .map(move |(x, color)| (x, y, color))
+        })
+    }
+
+    /// Gets the pixel at (`x`, `y`), or `None` outside the image.
+    fn pixel(&self, x: usize, y: usize) -> Option<Color> {
+        self.pixels.get(y)?.get(x).copied()
+    }
+
+    /// Sets the pixel at (`x`, `y`), skipping coordinates outside the image.
+    fn plot(&mut self, x: usize, y: usize, color: Color) {
+        if let Some(pixel) = self.pixels.get_mut(y).and_then(|row| row.get_mut(x)) {
+            *pixel = color;
+        }
+    }
+
+    /// Draws a line from `start` to `end` with the given `color`.
+    /// The line is clipped to the image, so endpoints may lie outside it.
+    pub fn draw_line(&mut self, start: Cell, end: Cell, color: Color) {
+        // Bresenham breaks ties differently in each direction, so always draw top to bottom
+        let (start, end) = match (start.y, start.x) <= (end.y, end.x) {
+            true => (start, end),
+            false => (end, start),
+        };
+        if let Some((start, end)) = clip_line(start, end, C, R) {
+            self.rasterize_line(start, end, color, false);
+        }
+    }
+
+    /// Draws a line `width` pixels wide, centered on the segment from `start` to `end`.
+    /// A `width` of 1 is equivalent to [`Image::draw_line`].
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn draw_line_width(&mut self, start: Cell, end: Cell, color: Color, width: usize) {
+        if width <= 1 {
+            return self.draw_line(start, end, color);
+        }
+        // Offset parallel copies of the line along the minor axis
+        let x_major = start.x.abs_diff(end.x) >= start.y.abs_diff(end.y);
+        let low = -((width as i128 - 1) / 2);
+        for offset in low..low + width as i128 {
+            let (ox, oy) = if x_major { (0, offset) } else { (offset, 0) };
+            let shift = |cell: Cell| (cell.x as i128 + ox, cell.y as i128 + oy);
+            if let Some((start, end)) = clip_segment(shift(start), shift(end), C, R) {
+                self.rasterize_line(start, end, color, true);
+            }
+        }
+    }
+
+    /// Draws a line between two in-bounds cells.
+    /// If `thick`, never leaves a line connected by just a diagonal.
+    fn rasterize_line(&mut self, start: Cell, end: Cell, color: Color, thick: bool) {
         let dx = end.x.abs_diff(start.x) as isize;
         let dy = -(end.y.abs_diff(start.y) as isize);
         let sx = if end.x > start.x { 1 } else { -1 };
@@ -44,29 +320,221 @@ impl<const R: usize, const C: usize> Image<R, C> {
         let mut error = dx + dy;
         let mut x = start.x;
         let mut y = start.y;
-        self.pixels[y][x] = color;
+        self.plot(x, y, color);
         while x != end.x || y != end.y {
             let error2 = error * 2;
             if error2 >= dy {
                 debug_assert!(x != end.x);
                 error += dy;
                 x = x.saturating_add_signed(sx);
-                if THICK_LINES {
-                    self.pixels[y][x] = color;
+                if thick {
+                    self.plot(x, y, color);
                 }
             }
             if error2 <= dx {
                 debug_assert!(y != end.y);
                 error += dx;
                 y = y.saturating_add_signed(sy);
-                if THICK_LINES {
-                    self.pixels[y][x] = color;
+                if thick {
+                    self.plot(x, y, color);
+                }
+            }
+            if !thick {
+                self.plot(x, y, color);
+            }
+        }
+    }
+
+    /// Like [`Image::plot`], but for coordinates that may be negative.
+    fn plot_signed(&mut self, x: i128, y: i128, color: Color) {
+        if let (Ok(x), Ok(y)) = (usize::try_from(x), usize::try_from(y)) {
+            self.plot(x, y, color);
+        }
+    }
+
+    /// Draws a quadratic Bézier curve from `p0` to `p2`, pulled toward the control point `p1`.
+    /// The curve is clipped to the image, so points may lie outside it.
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn draw_quadratic_bezier(&mut self, p0: Cell, p1: Cell, p2: Cell, color: Color) {
+        let point = |cell: Cell| (cell.x as i128, cell.y as i128);
+        let ((mut x0, mut y0), (mut x1, mut y1), (mut x2, mut y2)) =
+            (point(p0), point(p1), point(p2));
+
+        // Cut the curve where its gradient changes sign, leaving monotonic segments
+        let (x, y) = (x0 - x1, y0 - y1);
+        if x * (x2 - x1) > 0 {
+            // horizontal cut
+            let d = x0 - 2 * x1 + x2;
+            if y * (y2 - y1) > 0 && ((y0 - 2 * y1 + y2) * x).abs() > (y * d).abs() {
+                // the vertical cut comes first, so start from the other end
+                (x0, y0, x2, y2) = (x2, y2, x0, y0);
+            }
+            let n = x0 - x1;
+            let cut_x = x0 * x2 - x1 * x1; // over d
+            let x = div_round(cut_x, d);
+            let y = div_round(
+                (d - n) * (d - n) * y0 + 2 * n * (d - n) * y1 + n * n * y2,
+                d * d,
+            );
+            let control = div_round((y1 - y0) * (cut_x - x0 * d), d * (x1 - x0)) + y0;
+            self.rasterize_quadratic_bezier((x0, y0), (x, control), (x, y), color);
+            let control = div_round((y1 - y2) * (cut_x - x2 * d), d * (x1 - x2)) + y2;
+            (x0, y0, x1, y1) = (x, y, x, control);
+        }
+        if (y0 - y1) * (y2 - y1) > 0 {
+            // vertical cut
+            let d = y0 - 2 * y1 + y2;
+            let n = y0 - y1;
+            let cut_y = y0 * y2 - y1 * y1; // over d
+            let x = div_round(
+                (d - n) * (d - n) * x0 + 2 * n * (d - n) * x1 + n * n * x2,
+                d * d,
+            );
+            let y = div_round(cut_y, d);
+            let control = div_round((x1 - x0) * (cut_y - y0 * d), d * (y1 - y0)) + x0;
+            self.rasterize_quadratic_bezier((x0, y0), (control, y), (x, y), color);
+            let control = div_round((x1 - x2) * (cut_y - y2 * d), d * (y1 - y2)) + x2;
+            (x0, y0, x1, y1) = (x, y, control, y);
+        }
+        self.rasterize_quadratic_bezier((x0, y0), (x1, y1), (x2, y2), color);
+    }
+
+    /// Draws a quadratic Bézier segment whose gradient doesn't change sign.
+    fn rasterize_quadratic_bezier(
+        &mut self,
+        (mut x0, mut y0): (i128, i128),
+        (x1, y1): (i128, i128),
+        (mut x2, mut y2): (i128, i128),
+        color: Color,
+    ) {
+        let (mut sx, mut sy) = (x2 - x1, y2 - y1);
+        let (mut xx, mut yy) = (x0 - x1, y0 - y1);
+        let mut curvature = xx * sy - yy * sx;
+        if curvature != 0 {
+            if sx * sx + sy * sy > xx * xx + yy * yy {
+                // begin with the longer part
+                (x0, y0, x2, y2) = (x2, y2, x0, y0);
+                curvature = -curvature;
+            }
+            xx += sx;
+            sx = if x0 < x2 { 1 } else { -1 };
+            xx *= sx;
+            yy += sy;
+            sy = if y0 < y2 { 1 } else { -1 };
+            yy *= sy;
+            let mut xy = 2 * xx * yy;
+            xx *= xx;
+            yy *= yy;
+            if curvature * sx * sy < 0 {
+                (xx, yy, xy, curvature) = (-xx, -yy, -xy, -curvature);
+            }
+            let mut dx = 4 * sy * curvature * (x1 - x0) + xx - xy;
+            let mut dy = 4 * sx * curvature * (y0 - y1) + yy - xy;
+            xx += xx;
+            yy += yy;
+            let mut error = dx + dy + xy;
+            loop {
+                self.plot_signed(x0, y0, color);
+                if x0 == x2 && y0 == y2 {
+                    return;
+                }
+                let step_y = 2 * error < dx;
+                if 2 * error > dy {
+                    x0 += sx;
+                    dx -= xy;
+                    dy += yy;
+                    error += dy;
                 }
+                if step_y {
+                    y0 += sy;
+                    dy -= xy;
+                    dx += xx;
+                    error += dx;
+                }
+                // the curve is nearly straight, where the differences lose precision
+                if dy >= 0 || dx <= 0 {
+                    break;
+                }
+            }
+        }
+        // plot the rest as a line
+        if let Some((start, end)) = clip_segment((x0, y0), (x2, y2), C, R) {
+            self.rasterize_line(start, end, color, false);
+        }
+    }
+
+    /// Draws a cubic Bézier curve from `p0` to `p3`, shaped by the control points `p1` and `p2`.
+    /// The curve is clipped to the image, so points may lie outside it.
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn draw_cubic_bezier(&mut self, p0: Cell, p1: Cell, p2: Cell, p3: Cell, color: Color) {
+        let point = |cell: Cell| (cell.x as i128 * BEZIER_SCALE, cell.y as i128 * BEZIER_SCALE);
+        self.draw_cubic_bezier_scaled([point(p0), point(p1), point(p2), point(p3)], color, 0);
+    }
+
+    /// Draws a cubic Bézier curve with coordinates scaled by [`BEZIER_SCALE`].
+    /// The reference's cubic algorithm needs floating point, so instead the curve is
+    /// halved until each piece is within a pixel of a quadratic curve.
+    fn draw_cubic_bezier_scaled(&mut self, points: [(i128, i128); 4], color: Color, depth: u32) {
+        let [(x0, y0), (x1, y1), (x2, y2), (x3, y3)] = points;
+        let pixel = |x: i128, y: i128| (div_round(x, BEZIER_SCALE), div_round(y, BEZIER_SCALE));
+
+        // A straight curve covers its endpoints' segment if its controls lie within it
+        let straight = |(ax, ay): (i128, i128), (bx, by): (i128, i128)| {
+            (ax - x0) * (by - y0) == (ay - y0) * (bx - x0)
+        };
+        let within = |(x, y): (i128, i128)| {
+            (x0.min(x3)..=x0.max(x3)).contains(&x) && (y0.min(y3)..=y0.max(y3)).contains(&y)
+        };
+        if depth == 0
+            && straight((x1, y1), (x3, y3))
+            && straight((x2, y2), (x3, y3))
+            && within((x1, y1))
+            && within((x2, y2))
+        {
+            let ((x0, y0), (x3, y3)) = (pixel(x0, y0), pixel(x3, y3));
+            if let Some((start, end)) = clip_segment((x0, y0), (x3, y3), C, R) {
+                self.rasterize_line(start, end, color, false);
             }
-            if !THICK_LINES {
-                self.pixels[y][x] = color;
+            return;
+        }
+
+        // The quadratic's error is under 1/18 of this third difference
+        let error = (x3 - 3 * x2 + 3 * x1 - x0)
+            .abs()
+            .max((y3 - 3 * y2 + 3 * y1 - y0).abs());
+        if error <= 9 * BEZIER_SCALE || depth >= MAX_BEZIER_DEPTH {
+            let control = |a: i128, b: i128, c: i128, d: i128| div_round(3 * (b + c) - a - d, 4);
+            let start = pixel(x0, y0);
+            let control = pixel(control(x0, x1, x2, x3), control(y0, y1, y2, y3));
+            let end = pixel(x3, y3);
+            let cell = |(x, y): (i128, i128)| match (usize::try_from(x), usize::try_from(y)) {
+                (Ok(x), Ok(y)) => Some(Cell::new(x, y)),
+                _ => None,
+            };
+            match (cell(start), cell(control), cell(end)) {
+                (Some(start), Some(control), Some(end)) => {
+                    self.draw_quadratic_bezier(start, control, end, color)
+                }
+                // pieces reaching past the top or left edge are drawn straight
+                _ => {
+                    if let Some((start, end)) = clip_segment(start, end, C, R) {
+                        self.rasterize_line(start, end, color, false);
+                    }
+                }
             }
+            return;
         }
+
+        // Split at t = 1/2 with de Casteljau's algorithm
+        let mid = |(ax, ay): (i128, i128), (bx, by): (i128, i128)| {
+            (div_round(ax + bx, 2), div_round(ay + by, 2))
+        };
+        let (p0, p1, p2, p3) = ((x0, y0), (x1, y1), (x2, y2), (x3, y3));
+        let (p01, p12, p23) = (mid(p0, p1), mid(p1, p2), mid(p2, p3));
+        let (p012, p123) = (mid(p01, p12), mid(p12, p23));
+        let split = mid(p012, p123);
+        self.draw_cubic_bezier_scaled([p0, p01, p012, split], color, depth + 1);
+        self.draw_cubic_bezier_scaled([split, p123, p23, p3], color, depth + 1);
     }
 
     /// Draws an ellipse centered at `center` with width `a` and height `b`.
@@ -81,20 +549,13 @@ impl<const R: usize, const C: usize> Image<R, C> {
         draw_quadrants: [bool; 4],
         color: Color,
     ) {
-        let mut x = a; // IV. quadrant
-        let mut y = 0;
-        let mut dx = (1 - 2 * x as isize) * (b * b) as isize;
-        let mut dy = (x * x) as isize;
-        let mut error = dx + dy;
         // Draws coordinates if in-bound
         let mut draw = |x: Option<usize>, y: Option<usize>| {
             if let (Some(x), Some(y)) = (x, y) {
-                if x < C && y < R {
-                    self.pixels[y][x] = color;
-                }
+                self.plot(x, y, color);
             }
         };
-        loop {
+        trace_ellipse(a, b, |x, y| {
             if draw_quadrants[0] {
                 // I. Quadrant
                 draw(center.x.checked_add(x), center.y.checked_sub(y));
@@ -111,70 +572,1909 @@ impl<const R: usize, const C: usize> Image<R, C> {
                 // IV. Quadrant
                 draw(center.x.checked_add(x), center.y.checked_add(y));
             }
-            let error2 = error * 2;
-            if error2 >= dx {
-                if x == 0 {
-                    break;
+        });
+    }
+
+    /// Draws `count` circles around `center`, `spacing` pixels apart starting from the center,
+    /// cycling through `colors` from the innermost out. Rings running off the image are clipped,
+    /// and those past [`MAX_RADIUS`] aren't drawn.
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn draw_concentric(
+        &mut self,
+        center: Cell,
+        count: usize,
+        spacing: usize,
+        colors: &[Color],
+    ) {
+        if colors.is_empty() {
+            return;
+        }
+        for (ring, &color) in (1..=count).zip(colors.iter().cycle()) {
+            let radius = ring.saturating_mul(spacing);
+            if radius > MAX_RADIUS {
+                break;
+            }
+            self.draw_ellipse(center, radius, radius, [true; 4], color);
+        }
+    }
+
+    /// Draws the part of an ellipse running counter-clockwise from `start_deg` to `end_deg`,
+    /// where 0° points right and 90° points up. Angles past 360° wrap around,
+    /// and a span of 360° or more draws the whole ellipse.
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn draw_arc(
+        &mut self,
+        center: Cell,
+        a: usize,
+        b: usize,
+        start_deg: u32,
+        end_deg: u32,
+        color: Color,
+    ) {
+        let full = end_deg >= start_deg.saturating_add(360);
+        let (start, end) = (start_deg % 360 * 100, end_deg % 360 * 100);
+        let contains = |angle: u32| {
+            full || match start <= end {
+                true => (start..=end).contains(&angle),
+                false => angle >= start || angle <= end,
+            }
+        };
+        trace_ellipse(a, b, |x, y| {
+            for (sx, sy) in [(1, 1), (-1, 1), (-1, -1), (1, -1)] {
+                let (dx, dy) = (sx * x as isize, sy * y as isize);
+                if !contains(centidegrees(dx, dy)) {
+                    continue;
+                }
+                let px = center.x.checked_add_signed(dx);
+                let py = center.y.checked_add_signed(-dy);
+                if let (Some(px), Some(py)) = (px, py) {
+                    self.plot(px, py, color);
                 }
-                x -= 1;
-                dx += (2 * b * b) as isize;
-                error += dx;
             }
-            if error2 <= dy {
-                y += 1;
-                dy += (2 * a * a) as isize;
-                error += dy;
+        });
+    }
+
+    /// Replaces the 4-connected region of `start`'s color with `fill`.
+    /// Fills a scanline at a time from an explicit stack, so large regions can't overflow the call stack.
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn flood_fill(&mut self, start: Cell, fill: Color) {
+        let Some(target) = self.pixel(start.x, start.y) else {
+            return;
+        };
+        if target == fill {
+            return;
+        }
+
+        let mut stack = vec![start];
+        while let Some(Cell { x, y }) = stack.pop() {
+            if self.pixel(x, y) != Some(target) {
+                continue;
+            }
+            let Some(row) = self.pixels.get_mut(y) else {
+                continue;
+            };
+            // the check above keeps `x` in the row
+            let left = row[..x]
+                .iter()
+                .rposition(|&p| p != target)
+                .map_or(0, |i| i + 1);
+            let right = row[x..]
+                .iter()
+                .position(|&p| p != target)
+                .map_or(C, |i| x + i);
+            row[left..right].fill(fill);
+
+            // Seed each run of the target color above and below the span
+            let neighbors = [y.checked_sub(1), Some(y + 1).filter(|&y| y < R)];
+            for ny in neighbors.into_iter().flatten() {
+                let mut in_run = false;
+                let span = self.pixels.get(ny).and_then(|row| row.get(left..right));
+                for (nx, &pixel) in span.into_iter().flatten().enumerate() {
+                    let matches = pixel == target;
+                    if matches && !in_run {
+                        stack.push(Cell::new(left + nx, ny));
+                    }
+                    in_run = matches;
+                }
             }
         }
-        // Handle very flat ellipses (a=1)
-        while y < b {
-            y += 1;
-            if draw_quadrants[0] || draw_quadrants[1] {
-                draw(Some(center.x), center.y.checked_sub(y));
+    }
+
+    /// Renders `text` in a 3x5 pixel font with its top-left corner at `origin`.
+    /// Characters without a glyph are left blank, and text running off the image is clipped.
+    pub fn draw_text(&mut self, origin: Cell, text: &str, color: Color) {
+        for (i, c) in text.chars().enumerate() {
+            let Some(glyph) = utils::glyph(c) else {
+                continue;
+            };
+            let left = origin.x.saturating_add(i * (GLYPH_WIDTH + 1));
+            for (dy, row) in glyph.into_iter().enumerate() {
+                for dx in 0..GLYPH_WIDTH {
+                    if row & (1 << (GLYPH_WIDTH - 1 - dx)) != 0 {
+                        self.plot(left.saturating_add(dx), origin.y.saturating_add(dy), color);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Perturbs each channel of every pixel by a random amount in `-intensity..=intensity`.
+    /// Using a seeded `rng` makes the texture reproducible.
+    pub fn apply_dither(&mut self, intensity: u8, rng: &mut Rng) {
+        if intensity == 0 {
+            return;
+        }
+        let range = -(intensity as i16)..=intensity as i16;
+        for pixel in self.pixels.iter_mut().flatten() {
+            for channel in [&mut pixel.red, &mut pixel.green, &mut pixel.blue] {
+                let noise = rng.i16(range.clone());
+                *channel = (*channel as i16 + noise).clamp(0, 255) as u8;
+            }
+        }
+    }
+
+    /// Tints the image brownish, like an old photograph.
+    pub fn apply_sepia(&mut self) {
+        for pixel in self.pixels.iter_mut().flatten() {
+            let (r, g, b) = (pixel.red as u32, pixel.green as u32, pixel.blue as u32);
+            let tone =
+                |wr: u32, wg: u32, wb: u32| ((wr * r + wg * g + wb * b) / 1024).min(255) as u8;
+            *pixel = Color::new(
+                tone(402, 787, 194),
+                tone(357, 702, 172),
+                tone(279, 547, 134),
+            );
+        }
+    }
+
+    /// Maps each pixel's luminance onto the ramp from `dark` to `light`.
+    pub fn duotone(&mut self, dark: Color, light: Color) {
+        for pixel in self.pixels.iter_mut().flatten() {
+            *pixel = Color::lerp(dark, light, pixel.luminance());
+        }
+    }
+
+    /// Rotates the hue of every pixel by `degrees`, leaving grays as they are.
+    pub fn shift_hue(&mut self, degrees: u16) {
+        let degrees = degrees % 360;
+        if degrees == 0 {
+            return;
+        }
+        for pixel in self.pixels.iter_mut().flatten() {
+            let (hue, saturation, value) = pixel.to_hsv();
+            if saturation > 0 {
+                *pixel = Color::from_hsv(hue + degrees, saturation, value);
+            }
+        }
+    }
+
+    /// Composites `other` on top of this image.
+    pub fn blend_with(&mut self, other: &Image<R, C>, mode: BlendMode) {
+        let layers = self
+            .pixels
+            .iter_mut()
+            .flatten()
+            .zip(other.pixels.iter().flatten());
+        for (bottom, top) in layers {
+            *bottom = Color::new(
+                mode.apply(bottom.red, top.red),
+                mode.apply(bottom.green, top.green),
+                mode.apply(bottom.blue, top.blue),
+            );
+        }
+    }
+
+    /// Reflects the left half of the image onto the right half.
+    /// For odd widths the center column is left as is.
+    pub fn mirror_horizontal(&mut self) {
+        for row in self.pixels.iter_mut() {
+            let (left, right) = row.split_at_mut(C - C / 2);
+            for (target, source) in right.iter_mut().rev().zip(left.iter()) {
+                *target = *source;
             }
-            if draw_quadrants[2] || draw_quadrants[3] {
-                draw(Some(center.x), center.y.checked_add(y));
+        }
+    }
+
+    /// Reflects the top half of the image onto the bottom half.
+    /// For odd heights the center row is left as is.
+    pub fn mirror_vertical(&mut self) {
+        let (top, bottom) = self.pixels.split_at_mut(R - R / 2);
+        for (target, source) in bottom.iter_mut().rev().zip(top.iter()) {
+            *target = *source;
+        }
+    }
+
+    /// Reflects the top-left quadrant into the other three.
+    pub fn quadrant_symmetry(&mut self) {
+        self.mirror_horizontal();
+        self.mirror_vertical();
+    }
+
+    /// Resamples the image to a new size, taking each pixel from the nearest source pixel.
+    /// Scaling up by a whole factor turns every source pixel into a solid block.
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn scale<const R2: usize, const C2: usize>(&self) -> Image<R2, C2> {
+        let mut out = Image::new(Color::default());
+        for (x, y, pixel) in out.pixels_iter_mut() {
+            *pixel = self.pixel(x * C / C2, y * R / R2).unwrap_or_default();
+        }
+        out
+    }
+
+    /// Resamples the image to a new size, averaging the source pixels each output pixel covers.
+    /// Smoother than [`Image::scale`] when scaling down.
+    pub fn scale_average<const R2: usize, const C2: usize>(&self) -> Image<R2, C2> {
+        // the source pixels covering output pixel `i` along an axis of `from` pixels
+        let span = |i: usize, from: usize, to: usize| {
+            let start = i * from / to;
+            start..((i + 1) * from / to).max(start + 1)
+        };
+        let mut out = Image::new(Color::default());
+        for (x, y, pixel) in out.pixels_iter_mut() {
+            let (mut sum, mut count) = ([0_u32; 3], 0);
+            for source in &self.pixels[span(y, R, R2)] {
+                for color in &source[span(x, C, C2)] {
+                    sum[0] += color.red as u32;
+                    sum[1] += color.green as u32;
+                    sum[2] += color.blue as u32;
+                    count += 1;
+                }
             }
+            let [red, green, blue] = sum.map(|total| ((total + count / 2) / count) as u8);
+            *pixel = Color::new(red, green, blue);
         }
+        out
     }
 
-    /// Draws a line from `start` to `end` with the given `color`
+    /// Fills the image with a diagonal gradient, from `end` at the top left toward `start` at the bottom right
     pub fn draw_gradient(&mut self, start: Color, end: Color) {
-        for x in 0..C {
-            for y in 0..R {
-                let blend = 100 * (x + y) / (C + R);
-                let lerp = |x, y| ((x as usize * blend + y as usize * (100 - blend)) / 100) as u8;
-
-                let color = Color {
-                    red: lerp(start.red, end.red),
-                    green: lerp(start.green, end.green),
-                    blue: lerp(start.blue, end.blue),
-                };
-                self.pixels[y][x] = color;
+        for (x, y, pixel) in self.pixels_iter_mut() {
+            let blend = 100 * (x + y) / (C + R);
+            *pixel = Color::lerp_ratio(end, start, blend as u32, 100);
+        }
+    }
+
+    /// Fills the image with a gradient from `start` to `end` along the given direction.
+    pub fn draw_linear_gradient(&mut self, start: Color, end: Color, angle: GradientAngle) {
+        for (x, y, pixel) in self.pixels_iter_mut() {
+            let (step, span) = match angle {
+                GradientAngle::Horizontal => (x, C - 1),
+                GradientAngle::Vertical => (y, R - 1),
+                GradientAngle::Diagonal => (x + y, C + R - 2),
+                GradientAngle::AntiDiagonal => (C - 1 - x + y, C + R - 2),
+            };
+            let t = 255 * step / span.max(1);
+            *pixel = Color::lerp(start, end, t as u8);
+        }
+    }
+
+    /// Fills the image with a checkerboard of `size`-pixel squares, starting with `first` at the top-left.
+    pub fn draw_checkerboard(&mut self, size: usize, first: Color, second: Color) {
+        let size = size.max(1);
+        for (x, y, pixel) in self.pixels_iter_mut() {
+            *pixel = if (x / size + y / size).is_multiple_of(2) {
+                first
+            } else {
+                second
+            };
+        }
+    }
+
+    /// Fills the image with alternating stripes `width` pixels wide, changing color along `angle`.
+    pub fn draw_stripes(
+        &mut self,
+        width: usize,
+        angle: GradientAngle,
+        first: Color,
+        second: Color,
+    ) {
+        let width = width.max(1);
+        for (x, y, pixel) in self.pixels_iter_mut() {
+            let step = match angle {
+                GradientAngle::Horizontal => x,
+                GradientAngle::Vertical => y,
+                GradientAngle::Diagonal => x + y,
+                GradientAngle::AntiDiagonal => C - 1 - x + y,
+            };
+            *pixel = if (step / width).is_multiple_of(2) {
+                first
+            } else {
+                second
+            };
+        }
+    }
+
+    /// Draws a grid of filled dots, `spacing` pixels apart, over the image.
+    pub fn draw_dots(&mut self, spacing: usize, radius: usize, color: Color) {
+        let spacing = spacing.max(1);
+        for cy in (spacing / 2..R).step_by(spacing) {
+            for cx in (spacing / 2..C).step_by(spacing) {
+                for y in cy.saturating_sub(radius)..=cy.saturating_add(radius).min(R - 1) {
+                    for x in cx.saturating_sub(radius)..=cx.saturating_add(radius).min(C - 1) {
+                        let (dx, dy) = (x.abs_diff(cx) as u64, y.abs_diff(cy) as u64);
+                        if dx * dx + dy * dy <= (radius as u64).saturating_pow(2) {
+                            self.plot(x, y, color);
+                        }
+                    }
+                }
             }
         }
     }
+
+    /// Fills the outermost `thickness` rows and columns with `color`.
+    pub fn draw_border(&mut self, thickness: usize, color: Color) {
+        self.draw_inset_frame(0, thickness, color);
+    }
+
+    /// Draws a hollow rectangle `thickness` pixels wide, `offset` pixels in from each edge.
+    pub fn draw_inset_frame(&mut self, offset: usize, thickness: usize, color: Color) {
+        for (x, y, pixel) in self.pixels_iter_mut() {
+            let depth = x.min(y).min(C - 1 - x).min(R - 1 - y); // to the nearest edge
+            if depth >= offset && depth - offset < thickness {
+                *pixel = color;
+            }
+        }
+    }
+
+    /// Fills the image with a gradient radiating from `center`,
+    /// going from `inner` to `outer` at the farthest corner.
+    pub fn draw_radial_gradient(&mut self, center: Cell, inner: Color, outer: Color) {
+        // saturates for centers far off the image, which are then equally far from every pixel
+        let dist2 = |x: usize, y: usize| {
+            let dx = x.abs_diff(center.x) as u64;
+            let dy = y.abs_diff(center.y) as u64;
+            dx.saturating_mul(dx).saturating_add(dy.saturating_mul(dy))
+        };
+        let max_dist = [(0, 0), (C - 1, 0), (0, R - 1), (C - 1, R - 1)]
+            .map(|(x, y)| isqrt(dist2(x, y)))
+            .into_iter()
+            .max()
+            .unwrap_or_default()
+            .max(1);
+
+        for (x, y, pixel) in self.pixels_iter_mut() {
+            let t = 255 * isqrt(dist2(x, y)).min(max_dist) / max_dist;
+            *pixel = Color::lerp(inner, outer, t as u8);
+        }
+    }
+}
+
+/// Renders an image for ANSI truecolor terminals, drawing two rows per line with half-blocks.
+#[cfg(any(test, feature = "std"))]
+pub fn render_ascii<const R: usize, const C: usize>(image: &Image<R, C>) -> alloc::string::String {
+    use core::fmt::Write;
+
+    let mut out = alloc::string::String::new();
+    for rows in image.pixels.chunks(2) {
+        for col in 0..C {
+            let top = rows[0][col];
+            write!(out, "\x1b[38;2;{};{};{}m", top.red, top.green, top.blue).unwrap();
+            if let Some(bottom) = rows.get(1).map(|row| row[col]) {
+                write!(
+                    out,
+                    "\x1b[48;2;{};{};{}m",
+                    bottom.red, bottom.green, bottom.blue
+                )
+                .unwrap();
+            }
+            out.push('▀');
+        }
+        out.push_str("\x1b[39;49m\n"); // reset
+    }
+    out
+}
+
+/// An algorithm for drawing each token's art at an `R`x`C` resolution.
+///
+/// Implement this to give a deployment its own art without editing the workshop's.
+///
+/// Each token is drawn with the version of the art it was minted under, so tokens keep
+/// their look as the art evolves. To change the art, bump [`ArtStyle::VERSION`] and draw
+/// the new version alongside the old ones, leaving those untouched.
+pub trait ArtStyle<const R: usize, const C: usize> {
+    /// The version of the art newly minted tokens are drawn with.
+    const VERSION: u8 = 1;
+
+    /// Draws the given token of the NFT contract at `address`, as `version` of the art drew it.
+    fn render(version: u8, address: Address, token_id: U256) -> Image<R, C>;
+
+    /// The token's traits as (trait type, value) pairs, as listed in its metadata.
+    fn attributes(
+        _version: u8,
+        _address: Address,
+        _token_id: U256,
+    ) -> Vec<(&'static str, &'static str)> {
+        Vec::new()
+    }
+
+    /// The token's rarity score, higher for rarer tokens, for ranking the collection.
+    /// Defaults to zero, for art without traits to rank.
+    fn rarity_score(_version: u8, _address: Address, _token_id: U256) -> u32 {
+        0
+    }
+
+    /// The token's accent color, for theming interfaces around it.
+    /// Defaults to the average color of the rendered image.
+    fn accent_color(version: u8, address: Address, token_id: U256) -> Color {
+        let image = Self::render(version, address, token_id);
+        let average: Image<1, 1> = image.scale_average();
+        average.pixels[0][0]
+    }
+}
+
+/// The workshop's own art, as drawn by [`generate_nft`].
+///
+/// Version 1 drew every layer from one random stream, so any change to one layer reshuffled
+/// the rest. Version 2 gives each layer its own stream, as seeded by [`nft_layers`].
+pub struct DefaultStyle;
+
+impl ArtStyle<32, 32> for DefaultStyle {
+    const VERSION: u8 = 2;
+
+    fn render(version: u8, address: Address, token_id: U256) -> Image<32, 32> {
+        generate_nft_version(version, address, token_id)
+    }
+
+    fn attributes(
+        version: u8,
+        address: Address,
+        token_id: U256,
+    ) -> Vec<(&'static str, &'static str)> {
+        let traits = nft_layers(version, address, token_id).0;
+        vec![
+            ("Background", traits.background.name()),
+            ("Pattern", traits.pattern.name()),
+            ("Frame", traits.frame.name()),
+            ("Tone", traits.tone.name()),
+            ("Rarity", traits.rarity().name()),
+        ]
+    }
+
+    fn rarity_score(version: u8, address: Address, token_id: U256) -> u32 {
+        nft_layers(version, address, token_id).0.rarity_score()
+    }
+
+    fn accent_color(version: u8, address: Address, token_id: U256) -> Color {
+        nft_layers(version, address, token_id).0.foreground()
+    }
 }
 
-/// Generates the image for a given NFT token ID
+/// Generates the image for a given NFT token ID, in the current version of the art
+#[cfg_attr(not(test), allow(dead_code))]
 pub fn generate_nft(address: Address, token_id: U256) -> Image<32, 32> {
-    let mut hasher = FnvHasher::new();
-    hasher.update(token_id.as_le_slice());
-    hasher.update(address.as_slice());
-    let mut rng = Rng::with_seed(hasher.output());
+    generate_nft_version(DefaultStyle::VERSION, address, token_id)
+}
 
-    let bg_color = Color::from_hex(0xe3066e);
-    let fg_color = Color {
-        red: rng.u8(..),
-        green: rng.u8(..),
-        blue: rng.u8(..),
-    };
+/// Generates the image for a given NFT token ID, as the given version of the art drew it
+pub fn generate_nft_version(version: u8, address: Address, token_id: U256) -> Image<32, 32> {
+    let (traits, texture) = nft_layers(version, address, token_id);
+    draw_nft(traits, texture, token_id, 255)
+}
 
-    let mut image = Image::new(bg_color);
+/// Generates an NFT straight from the seed of its random stream, labeled as token 0.
+/// Lets tests pick seeds that reach specific traits, like the rarest backgrounds.
+#[cfg(test)]
+pub fn generate_nft_seeded(seed: u64) -> Image<32, 32> {
+    let mut selector = TraitSelector::new(seed);
+    let traits = NftTraits::generate(&mut selector);
+    draw_nft(traits, selector.rng().clone(), U256::ZERO, 255)
+}
 
-    image.draw_gradient(Color::from_hex(0xff0000), Color::from_hex(0x0000ff));
-    image.draw_line(Cell::new(4, 4), Cell::new(4, 6), fg_color);
-    image.draw_line(Cell::new(10, 4), Cell::new(10, 6), fg_color);
-    image.draw_ellipse(Cell::new(7, 9), 3, 3, [false, false, true, true], fg_color);
-    image
+/// Brightness of the foreground in each frame of [`generate_animated_nft`].
+const PULSE: [u8; 4] = [255, 210, 160, 210];
+
+/// Generates a short looping animation for a given NFT token ID, in which the face pulses.
+/// The first frame is the same as [`generate_nft`].
+#[cfg_attr(not(test), allow(dead_code))]
+pub fn generate_animated_nft(address: Address, token_id: U256) -> Vec<Image<32, 32>> {
+    PULSE
+        .iter()
+        .map(|&brightness| {
+            let (traits, texture) = nft_layers(DefaultStyle::VERSION, address, token_id);
+            draw_nft(traits, texture, token_id, brightness)
+        })
+        .collect()
+}
+
+/// How rare a trait is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Rarity {
+    Common,
+    Rare,
+    Epic,
+    Legendary,
+}
+
+impl Rarity {
+    /// The tier's name, as shown in metadata.
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Common => "Common",
+            Self::Rare => "Rare",
+            Self::Epic => "Epic",
+            Self::Legendary => "Legendary",
+        }
+    }
+}
+
+/// The backdrop an NFT is drawn on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Background {
+    Sunset,
+    Ocean,
+    Aurora,
+    Gold,
+}
+
+impl Background {
+    /// Every background, alongside its odds out of 100.
+    pub const WEIGHTED: [(Background, u32); 4] = [
+        (Self::Sunset, 70),
+        (Self::Ocean, 24),
+        (Self::Aurora, 5),
+        (Self::Gold, 1),
+    ];
+
+    /// The background's name, as shown in metadata.
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Sunset => "Sunset",
+            Self::Ocean => "Ocean",
+            Self::Aurora => "Aurora",
+            Self::Gold => "Gold",
+        }
+    }
+
+    pub const fn rarity(self) -> Rarity {
+        match self {
+            Self::Sunset => Rarity::Common,
+            Self::Ocean => Rarity::Rare,
+            Self::Aurora => Rarity::Epic,
+            Self::Gold => Rarity::Legendary,
+        }
+    }
+
+    fn draw(self, image: &mut Image<32, 32>) {
+        let center = Cell::new(16, 16);
+        match self {
+            Self::Sunset => {
+                image.draw_gradient(Color::from_hex(0xff0000), Color::from_hex(0x0000ff))
+            }
+            Self::Ocean => image.draw_linear_gradient(
+                Color::from_hex(0x00c0ff),
+                Color::from_hex(0x001040),
+                GradientAngle::Vertical,
+            ),
+            Self::Aurora => image.draw_radial_gradient(
+                center,
+                Color::from_hex(0x40ff90),
+                Color::from_hex(0x102040),
+            ),
+            Self::Gold => image.draw_radial_gradient(
+                center,
+                Color::from_hex(0xfff0a0),
+                Color::from_hex(0xb07800),
+            ),
+        }
+    }
+}
+
+/// A texture laid over an NFT's background.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pattern {
+    Plain,
+    Checkerboard,
+    Stripes,
+    Dots,
+}
+
+impl Pattern {
+    /// Every pattern, each as likely as the others.
+    pub const ALL: [Pattern; 4] = [Self::Plain, Self::Checkerboard, Self::Stripes, Self::Dots];
+
+    /// The pattern's name, as shown in metadata.
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Plain => "Plain",
+            Self::Checkerboard => "Checkerboard",
+            Self::Stripes => "Stripes",
+            Self::Dots => "Dots",
+        }
+    }
+
+    /// Shades the background with the pattern, keeping its hues.
+    fn draw(self, image: &mut Image<32, 32>) {
+        let light = Color::from_hex(0xffffff);
+        let shade = Color::from_hex(0xd0d0d0);
+        let mut layer = Image::new(light);
+        match self {
+            Self::Plain => return,
+            Self::Checkerboard => layer.draw_checkerboard(4, light, shade),
+            Self::Stripes => layer.draw_stripes(3, GradientAngle::Diagonal, light, shade),
+            Self::Dots => layer.draw_dots(8, 2, shade),
+        }
+        image.blend_with(&layer, BlendMode::Multiply);
+    }
+}
+
+/// A frame drawn around an NFT in its foreground color.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Frame {
+    None,
+    Border,
+    Inset,
+}
+
+impl Frame {
+    /// Every frame, alongside its odds out of 8.
+    pub const WEIGHTED: [(Frame, u32); 3] = [(Self::None, 6), (Self::Border, 1), (Self::Inset, 1)];
+
+    /// The frame's name, as shown in metadata.
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::None => "None",
+            Self::Border => "Border",
+            Self::Inset => "Inset",
+        }
+    }
+
+    fn draw(self, image: &mut Image<32, 32>, color: Color) {
+        match self {
+            Self::None => {}
+            Self::Border => image.draw_border(1, color),
+            Self::Inset => image.draw_inset_frame(2, 1, color),
+        }
+    }
+}
+
+/// How an NFT is mirrored onto itself.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Symmetry {
+    None,
+    Horizontal,
+    Vertical,
+    Quadrant,
+}
+
+impl Symmetry {
+    /// Every symmetry, each as likely as the others.
+    pub const ALL: [Symmetry; 4] = [Self::Horizontal, Self::Vertical, Self::Quadrant, Self::None];
+}
+
+/// A tone-mapping filter applied to the finished NFT.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToneFilter {
+    None,
+    Sepia,
+    Duotone,
+}
+
+impl ToneFilter {
+    /// Every filter, alongside its odds out of 8.
+    pub const WEIGHTED: [(ToneFilter, u32); 3] =
+        [(Self::Sepia, 1), (Self::Duotone, 1), (Self::None, 6)];
+
+    /// The filter's name, as shown in metadata.
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::None => "None",
+            Self::Sepia => "Sepia",
+            Self::Duotone => "Duotone",
+        }
+    }
+}
+
+/// The randomly chosen features of an NFT.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NftTraits {
+    pub background: Background,
+    pub pattern: Pattern,
+    /// Hue of the foreground, in degrees.
+    pub hue: u16,
+    pub symmetry: Symmetry,
+    pub tone: ToneFilter,
+    pub frame: Frame,
+}
+
+impl NftTraits {
+    /// Picks the traits as version 1 of the art did, consuming the start of the NFT's random stream.
+    fn generate(selector: &mut TraitSelector) -> Self {
+        let weights = Background::WEIGHTED.map(|(_, weight)| weight);
+        let background = Background::WEIGHTED[selector.weighted_pick(&weights)].0;
+        let hue = selector.rng().u16(..360);
+        let symmetry = *selector.pick(&Symmetry::ALL);
+        let weights = ToneFilter::WEIGHTED.map(|(_, weight)| weight);
+        let tone = ToneFilter::WEIGHTED[selector.weighted_pick(&weights)].0;
+        let pattern = *selector.pick(&Pattern::ALL);
+        let weights = Frame::WEIGHTED.map(|(_, weight)| weight);
+        let frame = Frame::WEIGHTED[selector.weighted_pick(&weights)].0;
+        Self {
+            background,
+            pattern,
+            hue,
+            symmetry,
+            tone,
+            frame,
+        }
+    }
+
+    /// Picks the traits from a stream for each layer: the palette from `colors`,
+    /// and the composition from `shapes`.
+    fn generate_layered(colors: &mut TraitSelector, shapes: &mut TraitSelector) -> Self {
+        let weights = Background::WEIGHTED.map(|(_, weight)| weight);
+        let background = Background::WEIGHTED[colors.weighted_pick(&weights)].0;
+        let hue = colors.rng().u16(..360);
+        let weights = ToneFilter::WEIGHTED.map(|(_, weight)| weight);
+        let tone = ToneFilter::WEIGHTED[colors.weighted_pick(&weights)].0;
+
+        let symmetry = *shapes.pick(&Symmetry::ALL);
+        let pattern = *shapes.pick(&Pattern::ALL);
+        let weights = Frame::WEIGHTED.map(|(_, weight)| weight);
+        let frame = Frame::WEIGHTED[shapes.weighted_pick(&weights)].0;
+        Self {
+            background,
+            pattern,
+            hue,
+            symmetry,
+            tone,
+            frame,
+        }
+    }
+
+    /// The rarity tier of the NFT, which is that of its rarest trait.
+    pub fn rarity(&self) -> Rarity {
+        self.background.rarity()
+    }
+
+    /// Sums the rarity scores of the NFT's traits, so the more unlikely its traits, the higher it scores.
+    /// Hue is left out, since every hue is equally likely.
+    pub fn rarity_score(&self) -> u32 {
+        inverse_frequency(&Background::WEIGHTED, self.background)
+            + inverse_frequency(&ToneFilter::WEIGHTED, self.tone)
+            + inverse_frequency(&Pattern::ALL.map(|pattern| (pattern, 1)), self.pattern)
+            + inverse_frequency(&Symmetry::ALL.map(|symmetry| (symmetry, 1)), self.symmetry)
+            + inverse_frequency(&Frame::WEIGHTED, self.frame)
+    }
+
+    /// The color the face is drawn in, before any tone filter.
+    pub fn foreground(&self) -> Color {
+        Color::from_hsv(self.hue, 230, 255)
+    }
+}
+
+/// What a trait every NFT has adds to its rarity score.
+const RARITY_SCALE: u32 = 100;
+
+/// The rarity score of `choice` among the `weighted` options it was drawn from,
+/// which is `RARITY_SCALE` divided by the odds of drawing it.
+fn inverse_frequency<T: PartialEq>(weighted: &[(T, u32)], choice: T) -> u32 {
+    let total: u32 = weighted.iter().map(|(_, weight)| weight).sum();
+    let weight = weighted
+        .iter()
+        .find(|(option, _)| *option == choice)
+        .map_or(total, |(_, weight)| *weight);
+    RARITY_SCALE * total / weight
+}
+
+/// Salts mixed into a token's seed, giving each layer of its art its own random stream.
+const COLOR_LAYER: u64 = 0x636f_6c6f_7273; // "colors"
+const SHAPE_LAYER: u64 = 0x7368_6170_6573; // "shapes"
+const TEXTURE_LAYER: u64 = 0x74_6578_7475_7265; // "texture"
+
+/// The seed from which an NFT is generated.
+fn nft_seed(address: Address, token_id: U256) -> u64 {
+    let mut hasher = FnvHasher::new();
+    hasher.update(token_id.as_le_slice());
+    hasher.update(address.as_slice());
+    hasher.output()
+}
+
+/// Picks an NFT's traits as the given version of the art did,
+/// along with the random stream that textures it.
+fn nft_layers(version: u8, address: Address, token_id: U256) -> (NftTraits, Rng) {
+    let seed = nft_seed(address, token_id);
+    match version {
+        1 => {
+            let mut selector = TraitSelector::new(seed);
+            let traits = NftTraits::generate(&mut selector);
+            (traits, selector.rng().clone())
+        }
+        _ => {
+            let mut colors = TraitSelector::new(seed ^ COLOR_LAYER);
+            let mut shapes = TraitSelector::new(seed ^ SHAPE_LAYER);
+            let traits = NftTraits::generate_layered(&mut colors, &mut shapes);
+            (traits, Rng::with_seed(seed ^ TEXTURE_LAYER))
+        }
+    }
+}
+
+/// Derives the traits of a given NFT token ID without drawing it, in the current version of the art.
+#[cfg_attr(not(test), allow(dead_code))]
+pub fn nft_traits(address: Address, token_id: U256) -> NftTraits {
+    nft_layers(DefaultStyle::VERSION, address, token_id).0
+}
+
+/// Draws the NFT with its foreground dimmed to `brightness` out of 255.
+fn draw_nft(traits: NftTraits, mut texture: Rng, token_id: U256, brightness: u8) -> Image<32, 32> {
+    let bg_color = Color::from_hex(0xe3066e);
+    let fg_color = traits.foreground().scale(brightness);
+
+    let mut image = Image::new(bg_color);
+
+    traits.background.draw(&mut image);
+    traits.pattern.draw(&mut image);
+    image.apply_dither(6, &mut texture);
+
+    // Darken the edges with a separately rendered vignette layer
+    let mut vignette = Image::new(bg_color);
+    vignette.draw_radial_gradient(
+        Cell::new(16, 16),
+        Color::from_hex(0xffffff),
+        Color::from_hex(0xa0a0a0),
+    );
+    image.blend_with(&vignette, BlendMode::Multiply);
+
+    image.draw_line(Cell::new(4, 4), Cell::new(4, 6), fg_color);
+    image.draw_line(Cell::new(10, 4), Cell::new(10, 6), fg_color);
+    image.draw_ellipse(Cell::new(7, 9), 3, 3, [false, false, true, true], fg_color);
+
+    match traits.symmetry {
+        Symmetry::None => {}
+        Symmetry::Horizontal => image.mirror_horizontal(),
+        Symmetry::Vertical => image.mirror_vertical(),
+        Symmetry::Quadrant => image.quadrant_symmetry(),
+    }
+    traits.frame.draw(&mut image, fg_color);
+
+    match traits.tone {
+        ToneFilter::None => {}
+        ToneFilter::Sepia => image.apply_sepia(),
+        ToneFilter::Duotone => image.duotone(Color::from_hex(0x1b1f3b), fg_color),
+    }
+
+    // Label the token with its id in the bottom-left corner
+    let label = format!("#{token_id}");
+    image.draw_text(
+        Cell::new(1, 32 - GLYPH_HEIGHT - 1),
+        &label,
+        Color::from_hex(0xffffff),
+    );
+    image
+}
+
+#[cfg(test)]
+mod tests {
+    use fastrand::Rng;
+
+    use alloy_primitives::{Address, U256};
+
+    use super::{
+        clip_line, generate_animated_nft, generate_nft, isqrt, BlendMode, Cell, GradientAngle,
+        Image,
+    };
+    use crate::utils::Color;
+
+    /// Paints every pixel a unique color so reflections are distinguishable.
+    fn unique_image<const R: usize, const C: usize>() -> Image<R, C> {
+        let mut image = Image::new(Color::default());
+        for (y, row) in image.pixels.iter_mut().enumerate() {
+            for (x, pixel) in row.iter_mut().enumerate() {
+                *pixel = Color::from_hex((y << 8 | x) as u32);
+            }
+        }
+        image
+    }
+
+    #[test]
+    fn test_mirror_horizontal() {
+        let original = unique_image::<4, 5>();
+        let mut image = unique_image::<4, 5>();
+        image.mirror_horizontal();
+        for y in 0..4 {
+            for x in 0..2 {
+                assert_eq!(image.pixels[y][x], original.pixels[y][x]);
+                assert_eq!(image.pixels[y][4 - x], original.pixels[y][x]);
+            }
+            // odd width: center column untouched
+            assert_eq!(image.pixels[y][2], original.pixels[y][2]);
+        }
+    }
+
+    #[test]
+    fn test_mirror_vertical() {
+        let original = unique_image::<5, 4>();
+        let mut image = unique_image::<5, 4>();
+        image.mirror_vertical();
+        for x in 0..4 {
+            for y in 0..2 {
+                assert_eq!(image.pixels[y][x], original.pixels[y][x]);
+                assert_eq!(image.pixels[4 - y][x], original.pixels[y][x]);
+            }
+            // odd height: center row untouched
+            assert_eq!(image.pixels[2][x], original.pixels[2][x]);
+        }
+    }
+
+    #[test]
+    fn test_quadrant_symmetry() {
+        let original = unique_image::<6, 6>();
+        let mut image = unique_image::<6, 6>();
+        image.quadrant_symmetry();
+        for y in 0..3 {
+            for x in 0..3 {
+                let source = original.pixels[y][x];
+                assert_eq!(image.pixels[y][x], source);
+                assert_eq!(image.pixels[y][5 - x], source);
+                assert_eq!(image.pixels[5 - y][x], source);
+                assert_eq!(image.pixels[5 - y][5 - x], source);
+            }
+        }
+    }
+
+    #[test]
+    fn test_scale() {
+        let original = unique_image::<32, 32>();
+        let large: Image<64, 64> = original.scale();
+        for y in 0..64 {
+            for x in 0..64 {
+                assert_eq!(large.pixels[y][x], original.pixels[y / 2][x / 2]);
+            }
+        }
+        let restored: Image<32, 32> = large.scale();
+        assert_eq!(restored.pixels, original.pixels);
+        let averaged: Image<32, 32> = large.scale_average();
+        assert_eq!(averaged.pixels, original.pixels);
+
+        let wide: Image<2, 6> = unique_image::<2, 3>().scale();
+        assert_eq!(wide.pixels[1][4], Color::from_hex(0x0102));
+        assert_eq!(wide.pixels[1][5], Color::from_hex(0x0102));
+    }
+
+    #[test]
+    fn test_scale_average() {
+        let mut image: Image<2, 2> = Image::new(Color::new(0, 0, 0));
+        image.pixels[0][0] = Color::new(255, 100, 10);
+        image.pixels[1][1] = Color::new(255, 0, 0);
+        let average: Image<1, 1> = image.scale_average();
+        assert_eq!(average.pixels[0][0], Color::new(128, 25, 3));
+        let nearest: Image<1, 1> = image.scale();
+        assert_eq!(nearest.pixels[0][0], image.pixels[0][0]);
+    }
+
+    #[test]
+    fn test_isqrt() {
+        for value in 0..10_000 {
+            let root = isqrt(value);
+            assert!(root * root <= value && (root + 1) * (root + 1) > value);
+        }
+    }
+
+    #[test]
+    fn test_wasm32_widths() {
+        use super::centidegrees;
+        let fg = Color::from_hex(0xffffff);
+        let max32 = u32::MAX as u64;
+
+        // squares past 32 bits
+        assert_eq!(isqrt(1 << 40), 1 << 20);
+        assert_eq!(isqrt(max32 + 1), 1 << 16);
+        assert_eq!(isqrt(u64::MAX), max32);
+
+        // the arctangent's intermediate product overflows 32 bits
+        assert!(1564 * 30_000 * 30_000 > max32);
+        let angle = centidegrees(60_000, 30_000); // atan(1/2) = 26.57°
+        assert!(angle.abs_diff(2657) <= 30, "{angle}");
+        assert!(centidegrees(-60_000, -30_000).abs_diff(18000 + 2657) <= 30);
+
+        // a dot whose squared radius overflows 32 bits covers everything
+        assert!(70_000 * 70_000 > max32);
+        let mut image: Image<8, 8> = Image::new(Color::default());
+        image.draw_dots(8, 70_000, fg);
+        assert!(image.pixels.iter().flatten().all(|&p| p == fg));
+        image.draw_dots(8, usize::MAX, Color::default());
+        assert!(image.pixels.iter().flatten().all(|&p| p != fg));
+    }
+
+    #[test]
+    fn test_radial_gradient() {
+        let inner = Color::from_hex(0xffffff);
+        let outer = Color::from_hex(0x000000);
+        let mut image: Image<33, 33> = Image::new(Color::default());
+        image.draw_radial_gradient(Cell::new(16, 16), inner, outer);
+
+        assert_eq!(image.pixels[16][16], inner);
+        for (x, y) in [(0, 0), (32, 0), (0, 32), (32, 32)] {
+            assert_eq!(image.pixels[y][x], outer);
+        }
+        // brightness falls off moving away from the center
+        for x in 16..32 {
+            assert!(image.pixels[16][x].red >= image.pixels[16][x + 1].red);
+        }
+    }
+
+    #[test]
+    fn test_radial_gradient_off_center() {
+        let inner = Color::from_hex(0xff00ff);
+        let outer = Color::from_hex(0x00ff00);
+        let mut image: Image<16, 24> = Image::new(Color::default());
+        image.draw_radial_gradient(Cell::new(3, 2), inner, outer);
+
+        assert_eq!(image.pixels[2][3], inner);
+        assert_eq!(image.pixels[15][23], outer);
+        for (x, y) in [(23, 0), (0, 15)] {
+            assert!(image.pixels[y][x].green > 0x80);
+        }
+    }
+
+    #[test]
+    fn test_linear_gradient() {
+        let start = Color::from_hex(0x000000);
+        let end = Color::from_hex(0xc8c8c8);
+        let mut image: Image<8, 11> = Image::new(Color::default());
+
+        image.draw_linear_gradient(start, end, GradientAngle::Horizontal);
+        assert_eq!(image.pixels[3][0], start);
+        assert_eq!(image.pixels[3][10], end);
+        assert_eq!(image.pixels[0][5], image.pixels[7][5]);
+
+        image.draw_linear_gradient(start, end, GradientAngle::Vertical);
+        assert_eq!(image.pixels[0][4], start);
+        assert_eq!(image.pixels[7][4], end);
+        assert_eq!(image.pixels[3][0], image.pixels[3][10]);
+
+        image.draw_linear_gradient(start, end, GradientAngle::Diagonal);
+        assert_eq!(image.pixels[0][0], start);
+        assert_eq!(image.pixels[7][10], end);
+
+        image.draw_linear_gradient(start, end, GradientAngle::AntiDiagonal);
+        assert_eq!(image.pixels[0][10], start);
+        assert_eq!(image.pixels[7][0], end);
+    }
+
+    #[test]
+    fn test_line_exits_canvas() {
+        let bg = Color::default();
+        let fg = Color::from_hex(0xffffff);
+
+        let mut image: Image<8, 8> = Image::new(bg);
+        image.draw_line(Cell::new(2, 3), Cell::new(40, 3), fg);
+        for x in 0..8 {
+            assert_eq!(image.pixels[3][x], if x >= 2 { fg } else { bg });
+        }
+
+        let mut image: Image<8, 8> = Image::new(bg);
+        image.draw_line(Cell::new(5, 5), Cell::new(20, 20), fg);
+        for y in 0..8 {
+            for x in 0..8 {
+                let on_line = x == y && x >= 5;
+                assert_eq!(image.pixels[y][x], if on_line { fg } else { bg });
+            }
+        }
+
+        // entirely outside
+        let mut image: Image<8, 8> = Image::new(bg);
+        image.draw_line(Cell::new(10, 0), Cell::new(10, 30), fg);
+        assert!(image.pixels.iter().flatten().all(|&p| p == bg));
+    }
+
+    #[test]
+    fn test_clip_line() {
+        let clip = |start: (usize, usize), end: (usize, usize)| {
+            clip_line(Cell::new(start.0, start.1), Cell::new(end.0, end.1), 8, 8)
+                .map(|(a, b)| ((a.x, a.y), (b.x, b.y)))
+        };
+
+        // fully inside
+        assert_eq!(clip((1, 2), (6, 5)), Some(((1, 2), (6, 5))));
+
+        // crosses the right edge
+        assert_eq!(clip((2, 3), (40, 3)), Some(((2, 3), (7, 3))));
+        assert_eq!(clip((3, 1), (13, 6)), Some(((3, 1), (7, 3))));
+
+        // crosses both the bottom and right edges
+        assert_eq!(clip((0, 12), (12, 0)), Some(((5, 7), (7, 5))));
+
+        // misses entirely, both trivially and after clipping
+        assert_eq!(clip((10, 0), (10, 30)), None);
+        assert_eq!(clip((6, 20), (20, 6)), None);
+    }
+
+    #[test]
+    fn test_clipped_line_pixels() {
+        let bg = Color::default();
+        let fg = Color::from_hex(0xffffff);
+        let mut image: Image<8, 8> = Image::new(bg);
+        image.draw_line(Cell::new(0, 12), Cell::new(12, 0), fg);
+        for y in 0..8 {
+            for x in 0..8 {
+                let on_line = x + y == 12;
+                assert_eq!(image.pixels[y][x], if on_line { fg } else { bg });
+            }
+        }
+    }
+
+    #[test]
+    fn test_line_symmetry() {
+        let bg = Color::default();
+        let fg = Color::from_hex(0xffffff);
+        let draw = |start: Cell, end: Cell| {
+            let mut image: Image<24, 24> = Image::new(bg);
+            image.draw_line(start, end, fg);
+            image.pixels
+        };
+        let check = |start: Cell, end: Cell| {
+            let forward = draw(start, end);
+            assert_eq!(forward, draw(end, start), "{start:?} to {end:?}");
+            assert_eq!(forward[start.y][start.x], fg);
+            assert_eq!(forward[end.y][end.x], fg);
+        };
+
+        // horizontal, vertical, single-pixel, and diagonal lines
+        check(Cell::new(3, 7), Cell::new(20, 7));
+        check(Cell::new(5, 0), Cell::new(5, 23));
+        check(Cell::new(9, 9), Cell::new(9, 9));
+        check(Cell::new(0, 0), Cell::new(23, 23));
+        check(Cell::new(2, 4), Cell::new(3, 5));
+        check(Cell::new(0, 0), Cell::new(23, 1));
+        check(Cell::new(0, 1), Cell::new(1, 23));
+
+        let mut rng = Rng::with_seed(0x11e);
+        for _ in 0..2000 {
+            let mut cell = || Cell::new(rng.usize(..24), rng.usize(..24));
+            check(cell(), cell());
+        }
+    }
+
+    #[test]
+    fn test_line_width() {
+        let bg = Color::default();
+        let fg = Color::from_hex(0xffffff);
+        let count =
+            |image: &Image<16, 16>| image.pixels.iter().flatten().filter(|&&p| p == fg).count();
+
+        let mut thin: Image<16, 16> = Image::new(bg);
+        thin.draw_line_width(Cell::new(2, 8), Cell::new(11, 8), fg, 1);
+        assert_eq!(count(&thin), 10);
+
+        let mut thick: Image<16, 16> = Image::new(bg);
+        thick.draw_line_width(Cell::new(2, 8), Cell::new(11, 8), fg, 3);
+        assert_eq!(count(&thick), 30);
+        for y in 7..=9 {
+            assert!((2..=11).all(|x| thick.pixels[y][x] == fg));
+        }
+
+        // bands at the edge are clipped rather than wrapping
+        let mut edge: Image<16, 16> = Image::new(bg);
+        edge.draw_line_width(Cell::new(0, 0), Cell::new(15, 0), fg, 3);
+        assert_eq!(count(&edge), 32);
+    }
+
+    /// Whether `end` can be reached from `start` through 8-connected pixels of `color`.
+    fn connected<const R: usize, const C: usize>(
+        image: &Image<R, C>,
+        color: Color,
+        start: Cell,
+        end: Cell,
+    ) -> bool {
+        let mut seen = [[false; C]; R];
+        let mut stack = vec![start];
+        while let Some(cell) = stack.pop() {
+            if cell == end {
+                return true;
+            }
+            for (dx, dy) in (-1..=1).flat_map(|dx| (-1..=1).map(move |dy| (dx, dy))) {
+                let (Some(x), Some(y)) = (
+                    cell.x.checked_add_signed(dx).filter(|&x| x < C),
+                    cell.y.checked_add_signed(dy).filter(|&y| y < R),
+                ) else {
+                    continue;
+                };
+                if image.pixels[y][x] == color && !seen[y][x] {
+                    seen[y][x] = true;
+                    stack.push(Cell::new(x, y));
+                }
+            }
+        }
+        false
+    }
+
+    #[test]
+    fn test_degenerate_bezier() {
+        let bg = Color::default();
+        let fg = Color::from_hex(0xffffff);
+        let (start, end) = (Cell::new(2, 3), Cell::new(28, 17));
+        let mut line: Image<32, 32> = Image::new(bg);
+        line.draw_line(start, end, fg);
+
+        for control in [Cell::new(15, 10), start, end] {
+            let mut quad: Image<32, 32> = Image::new(bg);
+            quad.draw_quadratic_bezier(start, control, end, fg);
+            assert!(quad.pixels == line.pixels, "control {control:?}");
+        }
+
+        let mut cubic: Image<32, 32> = Image::new(bg);
+        cubic.draw_cubic_bezier(start, Cell::new(15, 10), Cell::new(15, 10), end, fg);
+        assert!(cubic.pixels == line.pixels);
+
+        let mut cubic: Image<32, 32> = Image::new(bg);
+        cubic.draw_cubic_bezier(start, Cell::new(2, 3), Cell::new(28, 17), end, fg);
+        assert!(cubic.pixels == line.pixels);
+    }
+
+    #[test]
+    fn test_quadratic_bezier() {
+        let bg = Color::default();
+        let fg = Color::from_hex(0xffffff);
+        let (start, control, end) = (Cell::new(2, 29), Cell::new(16, 0), Cell::new(29, 29));
+        let mut image: Image<32, 32> = Image::new(bg);
+        image.draw_quadratic_bezier(start, control, end, fg);
+
+        assert_eq!(image.pixels[start.y][start.x], fg);
+        assert_eq!(image.pixels[end.y][end.x], fg);
+        assert!(connected(&image, fg, start, end));
+        // the apex is halfway to the control point, and nothing is drawn above it
+        assert!(image.pixels[14..=15].iter().any(|row| row.contains(&fg)));
+        assert!(image.pixels[..14].iter().flatten().all(|&p| p == bg));
+
+        // symmetric control points draw a symmetric curve
+        let mut mirrored: Image<32, 32> = Image::new(bg);
+        mirrored.draw_quadratic_bezier(Cell::new(1, 29), Cell::new(15, 0), Cell::new(29, 29), fg);
+        for row in mirrored.pixels.iter() {
+            assert!(row[..=30].iter().eq(row[..=30].iter().rev()));
+        }
+    }
+
+    #[test]
+    fn test_cubic_bezier() {
+        let bg = Color::default();
+        let fg = Color::from_hex(0xffffff);
+        let points = [
+            Cell::new(1, 16),
+            Cell::new(10, 0),
+            Cell::new(21, 31),
+            Cell::new(30, 16),
+        ];
+        let mut image: Image<32, 32> = Image::new(bg);
+        image.draw_cubic_bezier(points[0], points[1], points[2], points[3], fg);
+
+        assert_eq!(image.pixels[16][1], fg);
+        assert_eq!(image.pixels[16][30], fg);
+        assert!(connected(&image, fg, points[0], points[3]));
+        // an S-curve rises on the left and falls on the right
+        assert!(image.pixels[..12].iter().any(|row| row[..16].contains(&fg)));
+        assert!(image.pixels[20..].iter().any(|row| row[16..].contains(&fg)));
+        assert!(image.pixels[..12]
+            .iter()
+            .all(|row| !row[16..].contains(&fg)));
+    }
+
+    #[test]
+    fn test_bezier_clipped() {
+        let bg = Color::default();
+        let fg = Color::from_hex(0xffffff);
+        let mut image: Image<16, 16> = Image::new(bg);
+        image.draw_quadratic_bezier(Cell::new(0, 8), Cell::new(40, 8), Cell::new(0, 12), fg);
+        image.draw_cubic_bezier(
+            Cell::new(2, 2),
+            Cell::new(60, 0),
+            Cell::new(0, 60),
+            Cell::new(12, 2),
+            fg,
+        );
+        assert_eq!(image.pixels[8][0], fg);
+        assert_eq!(image.pixels[12][0], fg);
+        assert_eq!(image.pixels[2][2], fg);
+        assert_eq!(image.pixels[2][12], fg);
+    }
+
+    #[test]
+    fn test_centidegrees() {
+        use super::centidegrees;
+        assert_eq!(centidegrees(5, 0), 0);
+        assert_eq!(centidegrees(3, 3), 4500);
+        assert_eq!(centidegrees(0, 5), 9000);
+        assert_eq!(centidegrees(-5, 0), 18000);
+        assert_eq!(centidegrees(0, -5), 27000);
+        assert_eq!(centidegrees(-3, -3), 22500);
+        // tan(30°) = 1/√3
+        assert!(centidegrees(1732, 1000).abs_diff(3000) <= 30);
+    }
+
+    #[test]
+    fn test_large_ellipse() {
+        // on wasm32, these radii would overflow 32-bit error terms
+        let radius = 5000;
+        assert!((radius as i64).pow(3) > i32::MAX as i64);
+        let mut points = 0;
+        super::trace_ellipse(radius, radius, |x, y| {
+            let r2 = (x * x + y * y) as i64;
+            let error = r2 - (radius * radius) as i64;
+            assert!(error.abs() <= 2 * radius as i64, "({x}, {y})");
+            points += 1;
+        });
+        assert!(points >= radius);
+
+        let bg = Color::default();
+        let fg = Color::from_hex(0xffffff);
+        let mut image: Image<64, 64> = Image::new(bg);
+        image.draw_ellipse(Cell::new(32, 32), 31, 20, [true; 4], fg);
+        assert_eq!(image.pixels[32][1], fg);
+        assert_eq!(image.pixels[32][63], fg);
+        assert_eq!(image.pixels[12][32], fg);
+        assert_eq!(image.pixels[52][32], fg);
+        assert_eq!(image.pixels[32][32], bg);
+
+        // far larger than the image, so only the clipped edges are drawn
+        let mut image: Image<64, 64> = Image::new(bg);
+        image.draw_ellipse(Cell::new(32, 32), 40_000, 30_000, [true; 4], fg);
+        assert!(image.pixels.iter().flatten().all(|&p| p == bg));
+        image.draw_arc(Cell::new(0, 60_000), 60_000, 60_000, 0, 180, fg);
+        assert_eq!(image.pixels[0][0], fg);
+
+        // radii past MAX_RADIUS are clamped rather than overflowing
+        let mut image: Image<64, 64> = Image::new(bg);
+        image.draw_ellipse(Cell::new(32, 32), 3_000_000, 3_000_000, [true; 4], fg);
+        image.draw_arc(Cell::new(32, 32), usize::MAX, usize::MAX, 0, 360, fg);
+        image.draw_concentric(Cell::new(32, 32), usize::MAX, usize::MAX, &[fg]);
+        image.draw_concentric(Cell::new(32, 32), 3, super::MAX_RADIUS / 2, &[fg]);
+        assert!(image.pixels.iter().flatten().all(|&p| p == bg));
+        image.draw_arc(
+            Cell::new(0, super::MAX_RADIUS),
+            usize::MAX,
+            usize::MAX,
+            0,
+            180,
+            fg,
+        );
+        assert_eq!(image.pixels[0][0], fg);
+        // the widest, flattest ellipse runs right across the image, a row above and below its center
+        image.draw_ellipse(Cell::new(32, 32), usize::MAX, 1, [true; 4], fg);
+        assert!([31, 33]
+            .iter()
+            .all(|&y| image.pixels[y].iter().all(|&p| p == fg)));
+    }
+
+    #[test]
+    fn test_arc() {
+        let bg = Color::default();
+        let fg = Color::from_hex(0xffffff);
+        let center = Cell::new(16, 16);
+        let mut image: Image<32, 32> = Image::new(bg);
+        image.draw_arc(center, 10, 6, 0, 90, fg);
+        let mut count = 0;
+        for (x, y, pixel) in image.pixels_iter() {
+            if pixel == fg {
+                // quadrant I is to the right of and above the center
+                assert!(x >= center.x && y <= center.y, "({x}, {y})");
+                count += 1;
+            }
+        }
+        assert!(count > 0);
+        assert_eq!(image.pixels[16][26], fg);
+        assert_eq!(image.pixels[10][16], fg);
+
+        let draw = |start, end| {
+            let mut image: Image<32, 32> = Image::new(bg);
+            image.draw_arc(center, 10, 6, start, end, fg);
+            image.pixels
+        };
+        // wraps around past 360°
+        assert_eq!(draw(270, 450), draw(270, 90));
+        let right = draw(270, 450);
+        assert!(right.iter().all(|row| row[..16].iter().all(|&p| p == bg)));
+        assert_eq!(right[16][26], fg);
+
+        let mut ellipse: Image<32, 32> = Image::new(bg);
+        ellipse.draw_ellipse(center, 10, 6, [true; 4], fg);
+        assert_eq!(draw(0, 360), ellipse.pixels);
+        assert_eq!(draw(45, 405), ellipse.pixels);
+    }
+
+    #[test]
+    fn test_flood_fill() {
+        let bg = Color::default();
+        let outline = Color::from_hex(0xffffff);
+        let fill = Color::from_hex(0xe3066e);
+        let mut image: Image<16, 16> = Image::new(bg);
+        let corners = [(3, 4), (12, 4), (12, 10), (3, 10)].map(|(x, y)| Cell::new(x, y));
+        for i in 0..4 {
+            image.draw_line(corners[i], corners[(i + 1) % 4], outline);
+        }
+        let before = image.pixels.clone();
+
+        image.flood_fill(Cell::new(7, 7), fill);
+        for (x, y, pixel) in image.pixels_iter() {
+            let interior = (4..12).contains(&x) && (5..10).contains(&y);
+            let expected = if interior { fill } else { before[y][x] };
+            assert_eq!(pixel, expected, "({x}, {y})");
+        }
+
+        // refilling with the same color, or from outside the image, changes nothing
+        let filled = image.pixels.clone();
+        image.flood_fill(Cell::new(7, 7), fill);
+        image.flood_fill(Cell::new(16, 0), fill);
+        assert!(image.pixels == filled);
+
+        // the outside wraps around the rectangle
+        image.flood_fill(Cell::new(0, 0), fill);
+        assert!(image.pixels[4..=10]
+            .iter()
+            .all(|row| row[3] == outline && row[12] == outline));
+        assert!(image.pixels.iter().flatten().all(|&p| p != bg));
+    }
+
+    #[test]
+    fn test_concentric() {
+        let bg = Color::default();
+        let (red, blue) = (Color::from_hex(0xff0000), Color::from_hex(0x0000ff));
+        let center = Cell::new(16, 16);
+        let mut image: Image<32, 32> = Image::new(bg);
+        image.draw_concentric(center, 5, 4, &[red, blue]);
+
+        // the innermost ring is `spacing` out, and the colors alternate from there
+        for (radius, color) in [(4, red), (8, blue), (12, red), (16, blue)] {
+            assert_eq!(image.pixels[16 - radius][16], color, "{radius}");
+            assert_eq!(image.pixels[16][16 - radius], color, "{radius}");
+        }
+        for gap in [0, 2, 6, 10, 14] {
+            assert_eq!(image.pixels[16][16 - gap], bg, "{gap}");
+        }
+
+        // the outermost ring, of radius 20, only shows where it's on the image
+        let mut outer: Image<32, 32> = Image::new(bg);
+        outer.draw_ellipse(center, 20, 20, [true; 4], red);
+        assert_eq!(outer.pixels[0][4], red);
+        assert!(outer
+            .pixels_iter()
+            .all(|(x, y, p)| p != red || image.pixels[y][x] == red));
+
+        let mut untouched: Image<8, 8> = Image::new(bg);
+        untouched.draw_concentric(Cell::new(4, 4), 3, 1, &[]);
+        assert!(untouched.pixels_iter().all(|(_, _, p)| p == bg));
+    }
+
+    #[test]
+    fn test_shift_hue() {
+        let gray = Color::new(90, 90, 90);
+        let mut image: Image<2, 2> = Image::new(gray);
+        image.pixels[0][0] = Color::from_hex(0xff0000);
+        image.pixels[1][1] = Color::from_hex(0x00ff00);
+        image.shift_hue(120);
+        assert_eq!(image.pixels[0][0], Color::from_hex(0x00ff00));
+        assert_eq!(image.pixels[1][1], Color::from_hex(0x0000ff));
+        assert_eq!(image.pixels[0][1], gray);
+
+        // whole turns leave the image exactly as it was
+        image.shift_hue(360);
+        assert_eq!(image.pixels[0][0], Color::from_hex(0x00ff00));
+    }
+
+    #[test]
+    fn test_pixels_iter() {
+        let mut image: Image<3, 5> = Image::new(Color::default());
+        let cells: Vec<_> = image.pixels_iter().map(|(x, y, _)| (x, y)).collect();
+        assert_eq!(cells.len(), 3 * 5);
+        let row_major: Vec<_> = (0..3).flat_map(|y| (0..5).map(move |x| (x, y))).collect();
+        assert_eq!(cells, row_major);
+
+        for (x, y, pixel) in image.pixels_iter_mut() {
+            *pixel = Color::new(x as u8, y as u8, 0);
+        }
+        assert_eq!(image.pixels[2][4], Color::new(4, 2, 0));
+        assert!(image
+            .pixels_iter()
+            .all(|(x, y, color)| color == Color::new(x as u8, y as u8, 0)));
+    }
+
+    #[test]
+    fn test_checkerboard() {
+        let (a, b) = (Color::from_hex(0xffffff), Color::from_hex(0x000000));
+        let mut image: Image<16, 16> = Image::new(Color::default());
+        image.draw_checkerboard(4, a, b);
+        for (x, y, pixel) in image.pixels_iter() {
+            let expected = if (x / 4 + y / 4) % 2 == 0 { a } else { b };
+            assert_eq!(pixel, expected, "({x}, {y})");
+        }
+        // colors switch exactly at the cell boundaries
+        assert_eq!(image.pixels[0][3], a);
+        assert_eq!(image.pixels[0][4], b);
+        assert_eq!(image.pixels[3][0], a);
+        assert_eq!(image.pixels[4][0], b);
+        assert_eq!(image.pixels[4][4], a);
+        assert_eq!(image.pixels[15][15], a);
+    }
+
+    #[test]
+    fn test_stripes() {
+        let (a, b) = (Color::from_hex(0xffffff), Color::from_hex(0x000000));
+        let mut image: Image<8, 8> = Image::new(Color::default());
+        image.draw_stripes(2, GradientAngle::Vertical, a, b);
+        for (y, row) in image.pixels.iter().enumerate() {
+            let expected = if y % 4 < 2 { a } else { b };
+            assert!(row.iter().all(|&p| p == expected));
+        }
+
+        image.draw_stripes(1, GradientAngle::Diagonal, a, b);
+        assert_eq!(image.pixels[0][0], a);
+        assert_eq!(image.pixels[0][1], b);
+        assert_eq!(image.pixels[1][0], b);
+        assert_eq!(image.pixels[3][4], b);
+        assert_eq!(image.pixels[4][4], a);
+    }
+
+    #[test]
+    fn test_dots() {
+        let bg = Color::default();
+        let fg = Color::from_hex(0xffffff);
+        let mut image: Image<16, 16> = Image::new(bg);
+        image.draw_dots(8, 1, fg);
+        let centers = [(4, 4), (12, 4), (4, 12), (12, 12)];
+        for (x, y) in centers {
+            assert_eq!(image.pixels[y][x], fg);
+            assert_eq!(image.pixels[y - 1][x], fg);
+            assert_eq!(image.pixels[y][x + 1], fg);
+            assert_eq!(image.pixels[y + 1][x + 1], bg);
+        }
+        let count = image.pixels.iter().flatten().filter(|&&p| p == fg).count();
+        assert_eq!(count, 5 * centers.len());
+    }
+
+    #[test]
+    fn test_border() {
+        let bg = Color::default();
+        let fg = Color::from_hex(0xffffff);
+        let count =
+            |image: &Image<32, 32>| image.pixels.iter().flatten().filter(|&&p| p == fg).count();
+
+        let mut image: Image<32, 32> = Image::new(bg);
+        image.draw_border(2, fg);
+        assert_eq!(count(&image), 32 * 32 - 28 * 28);
+        assert_eq!(image.pixels[1][16], fg);
+        assert_eq!(image.pixels[2][16], bg);
+        assert_eq!(image.pixels[30][31], fg);
+
+        // too thick a border fills the image
+        let mut image: Image<32, 32> = Image::new(bg);
+        image.draw_border(20, fg);
+        assert_eq!(count(&image), 32 * 32);
+
+        // an inset frame leaves the edges alone
+        let mut image: Image<32, 32> = Image::new(bg);
+        image.draw_inset_frame(3, 1, fg);
+        assert_eq!(count(&image), 26 * 26 - 24 * 24);
+        assert_eq!(image.pixels[3][3], fg);
+        assert_eq!(image.pixels[2][3], bg);
+        assert_eq!(image.pixels[4][4], bg);
+        assert_eq!(image.pixels[28][10], fg);
+
+        // and works on images that aren't square
+        let mut image: Image<4, 6> = Image::new(bg);
+        image.draw_border(1, fg);
+        let edge = image.pixels.iter().flatten().filter(|&&p| p == fg).count();
+        assert_eq!(edge, 4 * 6 - 2 * 4);
+    }
+
+    #[test]
+    fn test_draw_text() {
+        let bg = Color::default();
+        let fg = Color::from_hex(0xffffff);
+        let mut image: Image<7, 16> = Image::new(bg);
+        image.draw_text(Cell::new(1, 1), "123", fg);
+
+        // the "2" glyph starts 4 columns after the "1"
+        let two = [
+            [true, true, true],
+            [false, false, true],
+            [true, true, true],
+            [true, false, false],
+            [true, true, true],
+        ];
+        for (dy, row) in two.iter().enumerate() {
+            for (dx, &set) in row.iter().enumerate() {
+                let pixel = image.pixels[1 + dy][5 + dx];
+                assert_eq!(pixel, if set { fg } else { bg }, "({dx}, {dy})");
+            }
+        }
+        // spacing columns stay empty
+        for y in 0..7 {
+            assert_eq!(image.pixels[y][4], bg);
+            assert_eq!(image.pixels[y][8], bg);
+        }
+    }
+
+    #[test]
+    fn test_draw_text_clipped() {
+        let fg = Color::from_hex(0xffffff);
+        let mut image: Image<4, 5> = Image::new(Color::default());
+        image.draw_text(Cell::new(3, 1), "#88", fg);
+        // only the left two columns and top three rows of "#" are visible
+        assert_eq!(image.pixels[1][3], fg);
+        assert_eq!(image.pixels[1][4], Color::default());
+        assert_eq!(image.pixels[2][4], fg);
+        assert_eq!(image.pixels[3][3], fg);
+        assert_eq!(image.pixels[3][4], Color::default());
+    }
+
+    #[test]
+    fn test_dither() {
+        let dithered = |intensity: u8, seed: u64| {
+            let mut image = unique_image::<8, 8>();
+            image.apply_dither(intensity, &mut Rng::with_seed(seed));
+            image
+        };
+        let original = unique_image::<8, 8>();
+
+        assert_eq!(dithered(0, 7).pixels, original.pixels);
+        assert_eq!(dithered(10, 7).pixels, dithered(10, 7).pixels);
+        assert_ne!(dithered(10, 7).pixels, dithered(10, 8).pixels);
+
+        let image = dithered(10, 7);
+        assert_ne!(image.pixels, original.pixels);
+        for (a, b) in image
+            .pixels
+            .iter()
+            .flatten()
+            .zip(original.pixels.iter().flatten())
+        {
+            assert!(a.red.abs_diff(b.red) <= 10);
+            assert!(a.green.abs_diff(b.green) <= 10);
+            assert!(a.blue.abs_diff(b.blue) <= 10);
+        }
+    }
+
+    #[test]
+    fn test_animated_nft() {
+        let address = Address::repeat_byte(0x42);
+        let token_id = U256::from(7);
+        let frames = generate_animated_nft(address, token_id);
+        assert_eq!(frames.len(), 4);
+        assert_eq!(frames[0].pixels, generate_nft(address, token_id).pixels);
+        assert_ne!(frames[0].pixels, frames[2].pixels);
+        assert_eq!(frames[1].pixels, frames[3].pixels);
+    }
+
+    #[test]
+    fn test_render_ascii() {
+        let image: Image<6, 5> = unique_image();
+        let rendered = super::render_ascii(&image);
+        let lines: Vec<&str> = rendered.lines().collect();
+        assert_eq!(lines.len(), 3);
+        for line in lines {
+            assert_eq!(line.matches('▀').count(), 5);
+            assert!(line.ends_with("\x1b[39;49m"));
+        }
+    }
+
+    #[test]
+    fn test_sepia() {
+        let mut image: Image<1, 3> = Image::new(Color::default());
+        image.pixels[0] = [
+            Color::new(100, 150, 200),
+            Color::from_hex(0xffffff),
+            Color::from_hex(0x000000),
+        ];
+        image.apply_sepia();
+        assert_eq!(image.pixels[0][0], Color::new(192, 171, 133));
+        assert_eq!(image.pixels[0][1], Color::new(255, 255, 239));
+        assert_eq!(image.pixels[0][2], Color::new(0, 0, 0));
+    }
+
+    #[test]
+    fn test_duotone() {
+        let dark = Color::from_hex(0x000040);
+        let light = Color::from_hex(0xffff40);
+        let mut image: Image<1, 3> = Image::new(Color::default());
+        image.pixels[0] = [
+            Color::new(100, 150, 200),
+            Color::from_hex(0xffffff),
+            Color::from_hex(0x000000),
+        ];
+        image.duotone(dark, light);
+        assert_eq!(image.pixels[0][0], Color::new(140, 140, 64));
+        assert_eq!(image.pixels[0][1], light);
+        assert_eq!(image.pixels[0][2], dark);
+    }
+
+    #[test]
+    fn test_traits_match_drawing() {
+        let address = Address::repeat_byte(0x42);
+        let mut checked = 0;
+        for id in 0..64 {
+            let traits = super::nft_traits(address, U256::from(id));
+            if traits.symmetry != super::Symmetry::Quadrant
+                || traits.tone != super::ToneFilter::None
+            {
+                continue;
+            }
+            // the corners are mirrored, away from the label
+            let image = generate_nft(address, U256::from(id));
+            assert_eq!(image.pixels[0][0], image.pixels[0][31]);
+            assert_eq!(image.pixels[3][2], image.pixels[28][29]);
+            checked += 1;
+        }
+        assert!(checked > 0);
+    }
+
+    #[test]
+    fn test_blend_with() {
+        let black: Image<4, 4> = Image::new(Color::from_hex(0x000000));
+        let white: Image<4, 4> = Image::new(Color::from_hex(0xffffff));
+        let blend = |mode: BlendMode, other: &Image<4, 4>| {
+            let mut image = unique_image::<4, 4>();
+            image.blend_with(other, mode);
+            image.pixels
+        };
+        let original = unique_image::<4, 4>().pixels;
+
+        assert_eq!(blend(BlendMode::Multiply, &white), original);
+        assert_eq!(blend(BlendMode::Multiply, &black), black.pixels);
+        assert_eq!(blend(BlendMode::Screen, &black), original);
+        assert_eq!(blend(BlendMode::Screen, &white), white.pixels);
+        assert_eq!(blend(BlendMode::Normal, &white), white.pixels);
+
+        let grey: Image<1, 1> = Image::new(Color::new(64, 128, 200));
+        let mut overlay: Image<1, 1> = Image::new(Color::new(100, 200, 255));
+        overlay.blend_with(&grey, BlendMode::Overlay);
+        assert_eq!(overlay.pixels[0][0], Color::new(50, 201, 255));
+    }
+
+    #[test]
+    fn test_generate_nft_seeded() {
+        use super::{generate_nft_seeded, Background, NftTraits};
+        use crate::utils::TraitSelector;
+
+        assert_eq!(generate_nft_seeded(7).pixels, generate_nft_seeded(7).pixels);
+        assert_ne!(generate_nft_seeded(7).pixels, generate_nft_seeded(8).pixels);
+
+        // find a legendary seed, which only 1 in 100 tokens get
+        let seed = (0..)
+            .find(|&seed| {
+                let traits = NftTraits::generate(&mut TraitSelector::new(seed));
+                traits.background == Background::Gold
+            })
+            .unwrap();
+        let image = generate_nft_seeded(seed);
+        assert_eq!(image.pixels, generate_nft_seeded(seed).pixels);
+        // the gold radial gradient is yellow at its center
+        let center = image.pixels[16][16];
+        assert!(center.red > center.blue && center.green > center.blue);
+    }
+
+    #[test]
+    fn test_independent_layers() {
+        use super::{NftTraits, COLOR_LAYER, SHAPE_LAYER};
+        use crate::utils::TraitSelector;
+
+        for seed in 0..64 {
+            let layered = |extra_shape_draws: usize| {
+                let mut colors = TraitSelector::new(seed ^ COLOR_LAYER);
+                let mut shapes = TraitSelector::new(seed ^ SHAPE_LAYER);
+                for _ in 0..extra_shape_draws {
+                    shapes.rng().u64(..);
+                }
+                NftTraits::generate_layered(&mut colors, &mut shapes)
+            };
+            // a new shape trait leaves every token's palette as it was
+            let (before, after) = (layered(0), layered(1));
+            assert_eq!(before.background, after.background);
+            assert_eq!(before.hue, after.hue);
+            assert_eq!(before.tone, after.tone);
+        }
+
+        // minted tokens keep the art of their version
+        let address = Address::repeat_byte(0x42);
+        let id = U256::from(7);
+        let v1 = NftTraits::generate(&mut TraitSelector::new(super::nft_seed(address, id)));
+        assert_eq!(super::nft_layers(1, address, id).0, v1);
+        assert_eq!(
+            generate_nft(address, id).pixels,
+            super::generate_nft_version(2, address, id).pixels
+        );
+        assert_ne!(
+            super::generate_nft_version(1, address, id).pixels,
+            super::generate_nft_version(2, address, id).pixels
+        );
+    }
+
+    #[test]
+    fn test_generate_never_panics() {
+        let mut rng = Rng::with_seed(0xf022);
+        let mut cases = vec![
+            (Address::ZERO, U256::ZERO),
+            (Address::repeat_byte(0xff), U256::MAX),
+            (Address::repeat_byte(0xff), U256::ZERO),
+            (Address::ZERO, U256::MAX),
+        ];
+        for _ in 0..64 {
+            let mut address = [0; 20];
+            rng.fill(&mut address);
+            let limbs = [rng.u64(..), rng.u64(..), rng.u64(..), rng.u64(..)];
+            cases.push((Address::new(address), U256::from_limbs(limbs)));
+        }
+        for (address, token_id) in cases {
+            let png = generate_nft(address, token_id).make_png();
+            assert!(png.starts_with(b"\x89PNG\r\n\x1a\n"));
+            // unknown versions, like those of tokens minted before versioning, draw too
+            for version in [0, 1, 2, rng.u8(3..)] {
+                super::generate_nft_version(version, address, token_id).make_png();
+            }
+        }
+
+        // the drawing primitives clip coordinates anywhere in `usize`
+        let (bg, fg) = (Color::default(), Color::from_hex(0xffffff));
+        for center in [Cell::new(usize::MAX, usize::MAX), Cell::new(0, usize::MAX)] {
+            let mut image: Image<32, 32> = Image::new(bg);
+            image.draw_radial_gradient(center, fg, bg);
+            assert!(image.pixels.iter().flatten().all(|&p| p == bg));
+        }
+    }
+
+    #[test]
+    fn test_rarity_score() {
+        use super::{Background, Frame, NftTraits, Pattern, Symmetry, ToneFilter};
+
+        let common = NftTraits {
+            background: Background::Sunset,
+            pattern: Pattern::Plain,
+            hue: 0,
+            symmetry: Symmetry::None,
+            tone: ToneFilter::None,
+            frame: Frame::None,
+        };
+        // 100/70 + 8/6 + 4 + 4 + 8/6 of the scale
+        assert_eq!(common.rarity_score(), 142 + 133 + 400 + 400 + 133);
+
+        // a rarer palette scores higher, and the rarest the highest
+        let sepia = NftTraits {
+            tone: ToneFilter::Sepia,
+            ..common
+        };
+        assert!(sepia.rarity_score() > common.rarity_score());
+        let scores = Background::WEIGHTED.map(|(background, _)| {
+            NftTraits {
+                background,
+                ..common
+            }
+            .rarity_score()
+        });
+        assert!(scores.windows(2).all(|pair| pair[0] < pair[1]));
+        assert_eq!(scores[3] - scores[0], 10_000 - 142);
+
+        // neither the hue nor equally likely traits change the score
+        let other = NftTraits {
+            hue: 200,
+            pattern: Pattern::Dots,
+            symmetry: Symmetry::Quadrant,
+            ..common
+        };
+        assert_eq!(other.rarity_score(), common.rarity_score());
+    }
+
+    #[test]
+    fn test_rarity_frequency() {
+        let address = Address::repeat_byte(0x42);
+        let sweep = 20_000;
+        let mut legendary = 0;
+        let mut common = 0;
+        for id in 0..sweep {
+            match super::nft_traits(address, U256::from(id)).rarity() {
+                super::Rarity::Legendary => legendary += 1,
+                super::Rarity::Common => common += 1,
+                _ => {}
+            }
+        }
+        // 1% and 70%, give or take
+        assert!(
+            (sweep / 200..sweep * 3 / 200).contains(&legendary),
+            "{legendary}"
+        );
+        assert!(
+            (sweep * 65 / 100..sweep * 75 / 100).contains(&common),
+            "{common}"
+        );
+
+        // deterministic per token
+        let traits = super::nft_traits(address, U256::from(1234));
+        assert_eq!(traits, super::nft_traits(address, U256::from(1234)));
+    }
 }
@@ -0,0 +1,115 @@
+//! Host-side benchmarks for the contract's hot paths.
+//!
+//! These time the work on the host rather than measuring gas, so compare numbers from the
+//! same machine before and after a change. Storage-heavy paths also report the slots read,
+//! which tracks gas more closely than time does. They're skipped by default, so run them
+//! in release mode:
+//!
+//! ```sh
+//! cargo test --release bench_ -- --ignored --nocapture
+//! ```
+
+use crate::{
+    art::{self, Image},
+    test_vm::TestVm,
+    utils::zlib_format,
+    StylusWorkshopNft, MINT_PRICE,
+};
+use alloy_primitives::{Address, U256};
+use std::{hint::black_box, io::Read, time::Instant};
+
+/// Runs `work` repeatedly, printing the average time per run.
+fn measure<T>(name: &str, runs: u32, mut work: impl FnMut() -> T) {
+    let start = Instant::now();
+    for _ in 0..runs {
+        black_box(work());
+    }
+    let average = start.elapsed() / runs;
+    println!("{name}: {average:?} per run ({runs} runs)");
+}
+
+fn inflate(data: &[u8]) -> Vec<u8> {
+    let mut reader = flate2::read::ZlibDecoder::new(data);
+    let mut inflated = Vec::new();
+    reader.read_to_end(&mut inflated).unwrap();
+    inflated
+}
+
+fn decode_png(data: &[u8]) -> Vec<u8> {
+    let mut reader = png::Decoder::new(data).read_info().unwrap();
+    let mut pixels = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut pixels).unwrap();
+    pixels.truncate(info.buffer_size());
+    pixels
+}
+
+fn rgb<const R: usize, const C: usize>(image: &Image<R, C>) -> Vec<u8> {
+    image
+        .pixels
+        .iter()
+        .flatten()
+        .flat_map(|p| [p.red, p.green, p.blue])
+        .collect()
+}
+
+#[test]
+#[ignore = "benchmark"]
+fn bench_make_png() {
+    let image = art::generate_nft(Address::new([0x42; 20]), U256::from(7));
+    measure("make_png 32x32", 1000, || image.make_png());
+
+    let large: Image<128, 128> = image.scale();
+    measure("make_png 128x128", 100, || large.make_png());
+
+    assert_eq!(decode_png(&image.make_png()), rgb(&image));
+    assert_eq!(decode_png(&large.make_png()), rgb(&large));
+}
+
+#[test]
+#[ignore = "benchmark"]
+fn bench_zlib_format() {
+    let mut rng = fastrand::Rng::with_seed(0xbe7c4);
+    for len in [3 * 1024, 64 * 1024, 256 * 1024] {
+        let data: Vec<u8> = (0..len).map(|_| rng.u8(..)).collect();
+        let name = format!("zlib_format {} KiB", len / 1024);
+        measure(&name, 100, || zlib_format(&data));
+        assert_eq!(inflate(&zlib_format(&data)), data);
+    }
+}
+
+#[test]
+#[ignore = "benchmark"]
+fn bench_transfer() {
+    const TOKENS: u32 = 100;
+    let vm = TestVm::new();
+    let mut nft: StylusWorkshopNft = vm.contract();
+    let alice = Address::new([0xa1; 20]);
+    let bob = Address::new([0xb0; 20]);
+
+    vm.set_sender(alice);
+    vm.set_value(MINT_PRICE);
+    vm.take_storage_loads();
+    let start = Instant::now();
+    for _ in 0..TOKENS {
+        nft.mint().ok().unwrap();
+    }
+    let elapsed = start.elapsed() / TOKENS;
+    let loads = vm.take_storage_loads() / TOKENS as usize;
+    println!("mint: {elapsed:?} and {loads} storage loads per run ({TOKENS} runs)");
+    vm.set_value(U256::ZERO);
+
+    let start = Instant::now();
+    for id in 0..TOKENS {
+        nft.erc712
+            .transfer_from(alice, bob, U256::from(id))
+            .ok()
+            .unwrap();
+    }
+    let elapsed = start.elapsed() / TOKENS;
+    let loads = vm.take_storage_loads() / TOKENS as usize;
+    println!("transfer_from: {elapsed:?} and {loads} storage loads per run ({TOKENS} runs)");
+
+    for id in 0..TOKENS {
+        assert_eq!(nft.erc712.owner_of(U256::from(id)).ok(), Some(bob));
+    }
+}
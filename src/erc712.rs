@@ -8,13 +8,21 @@
 //!
 //! Note that this code is unaudited and not fit for production use.
 
+use crate::msg;
 use alloc::{string::String, vec, vec::Vec};
-use alloy_primitives::{Address, U256};
-use alloy_sol_types::{sol, SolError};
+use alloy_primitives::{Address, FixedBytes, B256, U256};
+use alloy_sol_types::sol;
+#[cfg(test)]
+use alloy_sol_types::SolError;
 use core::{borrow::BorrowMut, marker::PhantomData};
-use stylus_sdk::{abi::Bytes, evm, msg, prelude::*};
+use stylus_sdk::{
+    abi::Bytes,
+    evm,
+    prelude::*,
+    storage::{StorageMap, StorageU256, StorageVec},
+};
 
-pub trait Erc712Params {
+pub trait Erc712Params: Sized {
     /// Immutable NFT name.
     const NAME: &'static str;
 
@@ -23,6 +31,31 @@ pub trait Erc712Params {
 
     /// The NFT's Uniform Resource Identifier.
     fn token_uri(token_id: U256) -> String;
+
+    /// Whether NFTs are bound to the account they're minted to, which can still burn them.
+    const SOULBOUND: bool = false;
+
+    /// Called before every transfer, including mints from and burns to the zero address,
+    /// once the NFT's own checks that the token can move have passed.
+    /// Returning an error, like [`TransferBlocked`], reverts the transfer.
+    fn before_token_transfer(
+        _erc712: &mut Erc712<Self>,
+        _from: Address,
+        _to: Address,
+        _token_id: U256,
+    ) -> Result<()> {
+        Ok(())
+    }
+
+    /// Called after every transfer, including mints from and burns to the zero address.
+    fn after_token_transfer(
+        _erc712: &mut Erc712<Self>,
+        _from: Address,
+        _to: Address,
+        _token_id: U256,
+    ) -> Result<()> {
+        Ok(())
+    }
 }
 
 sol_storage! {
@@ -32,7 +65,17 @@ sol_storage! {
         mapping(uint256 => address) approved;
         mapping(address => uint256) balance;
         mapping(address => mapping(address => bool)) approved_for_all;
-        uint256 total_supply;
+        mapping(address => bool) blocked_operators;
+        mapping(uint256 => bool) locked;
+        uint256 total_burned;
+        uint256 total_minted;
+        uint256 next_token_id;
+        mapping(address => address[]) operators;
+        mapping(address => mapping(address => bool)) operator_listed;
+        mapping(address => uint256[]) owned_tokens;
+        mapping(uint256 => uint256) owned_token_index;
+        uint256[] all_tokens;
+        mapping(uint256 => uint256) all_token_index;
         PhantomData<T> phantom;
     }
 }
@@ -42,53 +85,53 @@ sol! {
     event Transfer(address indexed from, address indexed to, uint256 indexed token_id);
     event Approval(address indexed owner, address indexed approved, uint256 indexed token_id);
     event ApprovalForAll(address indexed owner, address indexed operator, bool approved);
+    event Locked(uint256 token_id);
+    event Unlocked(uint256 token_id);
 
     error InvalidTokenId(uint256 token_id);
     error NotOwner(address from, uint256 token_id, address real_owner);
     error NotApproved(uint256 token_id, address owner, address spender);
     error TransferToZero(uint256 token_id);
     error ReceiverRefused(address receiver, uint256 token_id, bytes4 returned);
+    error OperatorBlocked(address operator);
+    error Soulbound(uint256 token_id);
+    error TokenStaked(uint256 token_id);
+    error TransferBlocked(uint256 token_id);
+    error InvalidOwner(address owner);
 }
 
-/// Represents the ways methods may fail.
-pub enum Erc712Error {
-    InvalidTokenId(InvalidTokenId),
-    NotOwner(NotOwner),
-    NotApproved(NotApproved),
-    TransferToZero(TransferToZero),
-    ReceiverRefused(ReceiverRefused),
-    ExternalCall(stylus_sdk::call::Error),
-}
-
-/// We will soon provide a `#[derive(SolidityError)]` to clean this up.
-impl From<stylus_sdk::call::Error> for Erc712Error {
-    fn from(err: stylus_sdk::call::Error) -> Self {
-        Self::ExternalCall(err)
-    }
-}
-
-/// We will soon provide a `#[derive(SolidityError)]` to clean this up.
-impl From<Erc712Error> for Vec<u8> {
-    fn from(val: Erc712Error) -> Self {
-        match val {
-            Erc712Error::InvalidTokenId(err) => err.encode(),
-            Erc712Error::NotOwner(err) => err.encode(),
-            Erc712Error::NotApproved(err) => err.encode(),
-            Erc712Error::TransferToZero(err) => err.encode(),
-            Erc712Error::ReceiverRefused(err) => err.encode(),
-            Erc712Error::ExternalCall(err) => err.into(),
-        }
+solidity_error! {
+    /// Represents the ways methods may fail.
+    pub enum Erc712Error {
+        InvalidTokenId(InvalidTokenId),
+        NotOwner(NotOwner),
+        NotApproved(NotApproved),
+        TransferToZero(TransferToZero),
+        ReceiverRefused(ReceiverRefused),
+        OperatorBlocked(OperatorBlocked),
+        Soulbound(Soulbound),
+        TokenStaked(TokenStaked),
+        TransferBlocked(TransferBlocked),
+        InvalidOwner(InvalidOwner),
+        #[nested]
+        ExternalCall(stylus_sdk::call::Error),
     }
 }
 
 /// Simplifies the result type for the contract's methods.
 type Result<T, E = Erc712Error> = core::result::Result<T, E>;
 
+/// Names the custom error with the given selector, to make reverts readable off-chain.
+#[cfg(any(test, feature = "std"))]
+pub fn error_name(selector: [u8; 4]) -> Option<&'static str> {
+    Erc712Error::error_name(selector)
+}
+
 // These methods aren't external, but are helpers used by external methods.
 // Methods marked as "pub" here are usable outside of the erc712 module (i.e. they're callable from main.rs).
 impl<T: Erc712Params> Erc712<T> {
     /// Requires that msg::sender() is authorized to spend a given token
-    fn require_authorized_to_spend(&self, from: Address, token_id: U256) -> Result<()> {
+    pub fn require_authorized_to_spend(&self, from: Address, token_id: U256) -> Result<()> {
         let owner = self.owner_of(token_id)?;
         if from != owner {
             return Err(Erc712Error::NotOwner(NotOwner {
@@ -98,28 +141,167 @@ impl<T: Erc712Params> Erc712<T> {
             }));
         }
 
-        if msg::sender() == owner {
+        // owners spending their own tokens is the common case, so check it before any more storage
+        let spender = msg::sender();
+        if spender == owner {
             return Ok(());
         }
-        if self.approved_for_all.getter(owner).get(msg::sender()) {
+        if self.approved_for_all.getter(owner).get(spender) {
             return Ok(());
         }
-        if msg::sender() == self.approved.get(token_id) {
+        if spender == self.approved.get(token_id) {
             return Ok(());
         }
         Err(Erc712Error::NotApproved(NotApproved {
             owner,
-            spender: msg::sender(),
+            spender,
             token_id,
         }))
     }
 
+    /// Requires that the sender own the NFT or be an allowed operator of its owner, returning the owner
+    fn require_authorized_to_approve(&self, token_id: U256) -> Result<Address> {
+        let owner = self.owner_of(token_id)?;
+        if msg::sender() == owner {
+            return Ok(owner);
+        }
+        if !self.approved_for_all.getter(owner).get(msg::sender()) {
+            return Err(Erc712Error::NotApproved(NotApproved {
+                owner,
+                spender: msg::sender(),
+                token_id,
+            }));
+        }
+        self.require_operator_allowed(msg::sender())?;
+        Ok(owner)
+    }
+
+    /// Approves `approved` to manage `owner`'s NFT, once the caller has been authorized
+    fn set_approval(&mut self, owner: Address, approved: Address, token_id: U256) {
+        self.approved.insert(token_id, approved);
+        evm::log(Approval {
+            approved,
+            owner,
+            token_id,
+        });
+    }
+
+    /// Requires that the NFT may move from `from` to `to`.
+    /// Soulbound NFTs can still be minted and burned, but staked ones can't even be burned,
+    /// which would leave the id staked for whoever mints it next.
+    fn require_transferable(&self, token_id: U256, from: Address, to: Address) -> Result<()> {
+        if T::SOULBOUND && !from.is_zero() && !to.is_zero() {
+            return Err(Erc712Error::Soulbound(Soulbound { token_id }));
+        }
+        if self.locked.get(token_id) {
+            return Err(Erc712Error::TokenStaked(TokenStaked { token_id }));
+        }
+        Ok(())
+    }
+
+    /// Runs before every transfer: requires the NFT be transferable, and that the caller,
+    /// if moving someone else's NFT, not be a blocked operator, then calls the params' hook.
+    fn before_token_transfer(&mut self, from: Address, to: Address, token_id: U256) -> Result<()> {
+        self.require_transferable(token_id, from, to)?;
+        let operator = msg::sender();
+        if !from.is_zero() && operator != from {
+            self.require_operator_allowed(operator)?;
+        }
+        T::before_token_transfer(self, from, to, token_id)
+    }
+
+    /// Grants or revokes `operator` the ability to manage all of `owner`'s NFTs.
+    /// Callers must first check `owner` authorized the change.
+    pub fn set_operator_approval(
+        &mut self,
+        owner: Address,
+        operator: Address,
+        approved: bool,
+    ) -> Result<()> {
+        if approved {
+            self.require_operator_allowed(operator)?;
+        }
+        self.approved_for_all
+            .setter(owner)
+            .insert(operator, approved);
+
+        // list each operator once, leaving revoked ones to be filtered out by `operators_of`
+        if approved && !self.operator_listed.getter(owner).get(operator) {
+            self.operator_listed.setter(owner).insert(operator, true);
+            self.operators.setter(owner).push(operator);
+        }
+
+        evm::log(ApprovalForAll {
+            owner,
+            operator,
+            approved,
+        });
+        Ok(())
+    }
+
+    /// Whether the NFT is currently owned, having been minted and not burned.
+    pub fn exists(&self, token_id: U256) -> bool {
+        !self.owners.get(token_id).is_zero()
+    }
+
+    /// Whether the NFT is staked, and so can't be transferred.
+    pub fn is_locked(&self, token_id: U256) -> bool {
+        self.locked.get(token_id)
+    }
+
+    /// Stakes or unstakes the NFT, which can't be transferred while staked.
+    /// Emits the ERC-5192 `Locked` or `Unlocked` event when this changes whether it's locked.
+    pub fn set_locked(&mut self, token_id: U256, locked: bool) {
+        if T::SOULBOUND || self.locked.get(token_id) == locked {
+            return;
+        }
+        self.locked.insert(token_id, locked);
+        match locked {
+            true => evm::log(Locked { token_id }),
+            false => evm::log(Unlocked { token_id }),
+        }
+    }
+
+    /// Requires that `operator` isn't blocked from managing other accounts' NFTs
+    fn require_operator_allowed(&self, operator: Address) -> Result<()> {
+        if self.blocked_operators.get(operator) {
+            return Err(Erc712Error::OperatorBlocked(OperatorBlocked { operator }));
+        }
+        Ok(())
+    }
+
+    /// Whether `operator` is blocked from managing other accounts' NFTs.
+    pub fn is_operator_blocked(&self, operator: Address) -> bool {
+        self.blocked_operators.get(operator)
+    }
+
+    /// Blocks or unblocks `operator` from managing other accounts' NFTs.
+    /// Owners can always move their own NFTs, and can revoke a blocked operator's approvals.
+    pub fn set_operator_blocked(&mut self, operator: Address, blocked: bool) {
+        self.blocked_operators.insert(operator, blocked);
+    }
+
+    /// Updates the lists enumerating NFTs as `token_id` moves from `from` to `to`.
+    fn enumerate_transfer(&mut self, token_id: U256, from: Address, to: Address) {
+        if from.is_zero() {
+            add_token(&mut self.all_tokens, &mut self.all_token_index, token_id);
+        } else {
+            let mut tokens = self.owned_tokens.setter(from);
+            remove_token(&mut tokens, &mut self.owned_token_index, token_id);
+        }
+        if to.is_zero() {
+            remove_token(&mut self.all_tokens, &mut self.all_token_index, token_id);
+        } else {
+            let mut tokens = self.owned_tokens.setter(to);
+            add_token(&mut tokens, &mut self.owned_token_index, token_id);
+        }
+    }
+
     /// Transfers `token_id` from `from` to `to`.
     /// This function does check that `from` is the owner of the token, but it does not check
     /// that `to` is not the zero address, as this function is usable for burning.
     pub fn transfer(&mut self, token_id: U256, from: Address, to: Address) -> Result<()> {
-        let mut owner = self.owners.setter(token_id);
-        let previous_owner = owner.get();
+        let previous_owner = self.owners.get(token_id);
         if previous_owner != from {
             return Err(Erc712Error::NotOwner(NotOwner {
                 from,
@@ -127,20 +309,39 @@ impl<T: Erc712Params> Erc712<T> {
                 real_owner: previous_owner,
             }));
         }
-        owner.set(to);
+        self.before_token_transfer(from, to, token_id)?;
+        self.owners.insert(token_id, to);
 
         // right now working with storage can be verbose, but this will change upcoming version of the Stylus SDK
-        let mut from_balance = self.balance.setter(from);
-        let balance = from_balance.get() - U256::from(1);
-        from_balance.set(balance);
+        // mints and burns leave the zero address, which owns nothing, without a balance
+        if !from.is_zero() {
+            let mut from_balance = self.balance.setter(from);
+            let balance = from_balance.get() - U256::from(1);
+            from_balance.set(balance);
+        }
+        if !to.is_zero() {
+            let mut to_balance = self.balance.setter(to);
+            let balance = to_balance.get() + U256::from(1);
+            to_balance.set(balance);
+        }
 
-        let mut to_balance = self.balance.setter(from);
-        let balance = to_balance.get() + U256::from(1);
-        to_balance.set(balance);
+        self.enumerate_transfer(token_id, from, to);
 
-        self.approved.delete(token_id);
+        // tell indexers the token's approval went with it, as ERC-721 recommends
+        if !self.approved.get(token_id).is_zero() {
+            self.approved.delete(token_id);
+            evm::log(Approval {
+                owner: from,
+                approved: Address::ZERO,
+                token_id,
+            });
+        }
         evm::log(Transfer { from, to, token_id });
-        Ok(())
+        if T::SOULBOUND && from.is_zero() {
+            // soulbound NFTs are locked from the moment they're minted
+            evm::log(Locked { token_id });
+        }
+        T::after_token_transfer(self, from, to, token_id)
     }
 
     fn call_receiver<S: TopLevelStorage>(
@@ -150,7 +351,7 @@ impl<T: Erc712Params> Erc712<T> {
         to: Address,
         data: Vec<u8>,
     ) -> Result<()> {
-        if to.has_code() {
+        if has_code(to) {
             let receiver = IERC721TokenReceiver::new(to);
             let received = receiver
                 .on_erc_721_received(&mut *storage, msg::sender(), from, token_id, data)?
@@ -178,31 +379,87 @@ impl<T: Erc712Params> Erc712<T> {
         Self::call_receiver(storage, token_id, from, to, data)
     }
 
-    pub fn mint(&mut self, to: Address) -> Result<()> {
-        let new_token_id = self.total_supply.get();
-        self.total_supply.set(new_token_id + U256::from(1u8));
-        self.transfer(new_token_id, Address::default(), to)?;
+    /// Claims the next token id, skipping any already minted by [`Erc712::mint_id`]
+    fn claim_token_id(&mut self) -> U256 {
+        let mut token_id = self.next_token_id.get();
+        while self.exists(token_id) {
+            token_id += U256::from(1u8);
+        }
+        self.next_token_id.set(token_id + U256::from(1u8));
+        token_id
+    }
+
+    /// Mints the next token id, returning it
+    pub fn mint(&mut self, to: Address) -> Result<U256> {
+        let new_token_id = self.claim_token_id();
+        self.mint_id(to, new_token_id)?;
+        Ok(new_token_id)
+    }
+
+    /// Mints a specific token id, which must not already be owned
+    pub fn mint_id(&mut self, to: Address, token_id: U256) -> Result<()> {
+        self.transfer(token_id, Address::default(), to)?;
+        let minted = self.total_minted.get();
+        self.total_minted.set(minted + U256::from(1u8));
         Ok(())
     }
 
+    /// Mints the next token id, returning it, after checking `to` can receive it
     pub fn safe_mint<S: TopLevelStorage + BorrowMut<Self>>(
         storage: &mut S,
         to: Address,
         data: Vec<u8>,
-    ) -> Result<()> {
-        let this = storage.borrow_mut();
-        let new_token_id = this.total_supply.get();
-        this.total_supply.set(new_token_id + U256::from(1u8));
-        Self::safe_transfer(storage, new_token_id, Address::default(), to, data)?;
-        Ok(())
+    ) -> Result<U256> {
+        let new_token_id = storage.borrow_mut().mint(to)?;
+        Self::call_receiver(storage, new_token_id, Address::default(), to, data)?;
+        Ok(new_token_id)
     }
 
+    /// Burns `token_id`, which `from` must own.
+    /// Soulbound NFTs can be burned, but staked ones can't until they're unstaked.
     pub fn burn(&mut self, from: Address, token_id: U256) -> Result<()> {
         self.transfer(token_id, from, Address::default())?;
+        let burned = self.total_burned.get();
+        self.total_burned.set(burned + U256::from(1u8));
         Ok(())
     }
 }
 
+/// Appends `token_id` to a list of NFTs, recording where it is in `indices`.
+fn add_token(
+    tokens: &mut StorageVec<StorageU256>,
+    indices: &mut StorageMap<U256, StorageU256>,
+    token_id: U256,
+) {
+    indices.insert(token_id, U256::from(tokens.len()));
+    tokens.push(token_id);
+}
+
+/// Removes `token_id` from a list of NFTs, moving the last one into its place.
+fn remove_token(
+    tokens: &mut StorageVec<StorageU256>,
+    indices: &mut StorageMap<U256, StorageU256>,
+    token_id: U256,
+) {
+    let index = indices.get(token_id);
+    let last = tokens.pop().unwrap_or_default();
+    if last != token_id {
+        tokens.setter(index).unwrap().set(last);
+        indices.insert(last, index);
+    }
+    indices.delete(token_id);
+}
+
+/// Up to `count` of the NFTs in a list, starting from the `start`th.
+fn token_page(tokens: &StorageVec<StorageU256>, start: U256, count: U256) -> Vec<U256> {
+    let len = U256::from(tokens.len());
+    let start = start.min(len);
+    let end = start.saturating_add(count).min(len);
+    (start.to::<usize>()..end.to::<usize>())
+        .filter_map(|index| tokens.get(index))
+        .collect()
+}
+
 sol_interface! {
     /// Allows calls to the `onERC721Received` method of other contracts implementing `IERC721TokenReceiver`.
     interface IERC721TokenReceiver {
@@ -211,7 +468,24 @@ sol_interface! {
 }
 
 /// Selector for `onERC721Received`, which is returned by contracts implementing `IERC721TokenReceiver`.
-const ERC721_TOKEN_RECEIVER_ID: u32 = 0x150b7a02;
+pub const ERC721_TOKEN_RECEIVER_ID: u32 = 0x150b7a02;
+
+/// ERC-165 interface ids, each the XOR of the interface's function selectors.
+pub const IERC165: u32 = 0x01ffc9a7;
+pub const IERC721: u32 = 0x80ac58cd;
+pub const IERC5192: u32 = 0xb45a3c0e;
+
+/// The codehash of an account with no code.
+const EMPTY_CODEHASH: B256 = B256::new(hex_literal::hex!(
+    "c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470"
+));
+
+/// Whether an account has code, and so must be asked before receiving NFTs.
+/// `Address::has_code` can't be used, as in this version of the SDK it answers the opposite.
+fn has_code(address: Address) -> bool {
+    let hash = address.codehash();
+    !hash.is_zero() && hash != EMPTY_CODEHASH
+}
 
 // these methods are external to other contracts
 #[external]
@@ -233,21 +507,44 @@ impl<T: Erc712Params> Erc712<T> {
     }
 
     /// Wether the NFT supports a given standard.
-    pub fn supports_interface(interface: [u8; 4]) -> Result<bool> {
+    pub fn supports_interface(interface: FixedBytes<4>) -> Result<bool> {
         if interface == [0xff; 4] {
             // special cased in the ERC165 standard
             return Ok(false);
         }
+        Ok(matches!(
+            u32::from_be_bytes(interface.0),
+            IERC165 | IERC721 | IERC5192
+        ))
+    }
 
-        const IERC165: u32 = 0x01ffc9a7;
-        const IERC721: u32 = 0x80ac58cd;
-        const _IERC721_ENUMERABLE: u32 = 0x780e9d63; // TODO: implement standard
+    /// Whether the NFT can't be transferred, being soulbound or staked, as in ERC-5192.
+    pub fn locked(&self, token_id: U256) -> Result<bool> {
+        self.owner_of(token_id)?; // require NFT exist
+        Ok(T::SOULBOUND || self.locked.get(token_id))
+    }
 
-        Ok(matches!(u32::from_be_bytes(interface), IERC165 | IERC721))
+    /// The number of NFTs ever minted.
+    pub fn total_minted(&self) -> Result<U256> {
+        Ok(self.total_minted.get())
+    }
+
+    /// The number of NFTs ever burned.
+    pub fn total_burned(&self) -> Result<U256> {
+        Ok(self.total_burned.get())
+    }
+
+    /// The number of NFTs in existence.
+    pub fn total_supply(&self) -> Result<U256> {
+        Ok(self.total_minted.get() - self.total_burned.get())
     }
 
     /// Gets the number of NFTs owned by an account.
+    /// Reverts for the zero address, which ERC-721 treats as owning nothing.
     pub fn balance_of(&self, owner: Address) -> Result<U256> {
+        if owner.is_zero() {
+            return Err(Erc712Error::InvalidOwner(InvalidOwner { owner }));
+        }
         Ok(U256::from(self.balance.get(owner)))
     }
 
@@ -292,6 +589,30 @@ impl<T: Erc712Params> Erc712<T> {
         Self::safe_transfer(storage, token_id, from, to, data.0)
     }
 
+    /// Safely transfers several NFTs from one account to another.
+    /// Every NFT is authorized before any moves, and the receiver is then called once per NFT,
+    /// as with [`safe_transfer_from_with_data`]. Any failure reverts the whole batch.
+    pub fn safe_transfer_batch<S: TopLevelStorage + BorrowMut<Self>>(
+        storage: &mut S,
+        from: Address,
+        to: Address,
+        token_ids: Vec<U256>,
+        data: Bytes,
+    ) -> Result<()> {
+        let this = storage.borrow_mut();
+        for &token_id in &token_ids {
+            if to.is_zero() {
+                return Err(Erc712Error::TransferToZero(TransferToZero { token_id }));
+            }
+            this.require_authorized_to_spend(from, token_id)?;
+        }
+        for token_id in token_ids {
+            storage.borrow_mut().transfer(token_id, from, to)?;
+            Self::call_receiver(storage, token_id, from, to, data.0.clone())?;
+        }
+        Ok(())
+    }
+
     /// Transfers the NFT.
     pub fn transfer_from(&mut self, from: Address, to: Address, token_id: U256) -> Result<()> {
         if to.is_zero() {
@@ -304,39 +625,29 @@ impl<T: Erc712Params> Erc712<T> {
 
     /// Grants an account the ability to manage the sender's NFT.
     pub fn approve(&mut self, approved: Address, token_id: U256) -> Result<()> {
-        let owner = self.owner_of(token_id)?;
+        let owner = self.require_authorized_to_approve(token_id)?;
+        self.require_operator_allowed(approved)?;
+        self.set_approval(owner, approved, token_id);
+        Ok(())
+    }
 
-        // require authorization
-        if msg::sender() != owner && !self.approved_for_all.getter(owner).get(msg::sender()) {
-            return Err(Erc712Error::NotApproved(NotApproved {
-                owner,
-                spender: msg::sender(),
-                token_id,
-            }));
+    /// Grants an account the ability to manage several of the sender's NFTs, as with [`approve`].
+    /// Every NFT is authorized before any are approved, so any failure reverts the whole batch.
+    pub fn approve_batch(&mut self, approved: Address, token_ids: Vec<U256>) -> Result<()> {
+        self.require_operator_allowed(approved)?;
+        let owners = token_ids
+            .iter()
+            .map(|&token_id| self.require_authorized_to_approve(token_id))
+            .collect::<Result<Vec<_>>>()?;
+        for (owner, token_id) in owners.into_iter().zip(token_ids) {
+            self.set_approval(owner, approved, token_id);
         }
-        self.approved.insert(token_id, approved);
-
-        evm::log(Approval {
-            approved,
-            owner,
-            token_id,
-        });
         Ok(())
     }
 
     /// Grants an account the ability to manage all of the sender's NFTs.
     pub fn set_approval_for_all(&mut self, operator: Address, approved: bool) -> Result<()> {
-        let owner = msg::sender();
-        self.approved_for_all
-            .setter(owner)
-            .insert(operator, approved);
-
-        evm::log(ApprovalForAll {
-            owner,
-            operator,
-            approved,
-        });
-        Ok(())
+        self.set_operator_approval(msg::sender(), operator, approved)
     }
 
     /// Gets the account managing an NFT, or zero if unmanaged.
@@ -348,4 +659,1138 @@ impl<T: Erc712Params> Erc712<T> {
     pub fn is_approved_for_all(&mut self, owner: Address, operator: Address) -> Result<bool> {
         Ok(self.approved_for_all.getter(owner).get(operator))
     }
+
+    /// Up to `count` of the NFTs `owner` holds, starting from the `start`th.
+    /// Paging keeps the response bounded for large holders: keep going until fewer than `count` come back.
+    /// The order changes as NFTs move, so page through a snapshot by calling at a fixed block.
+    pub fn tokens_of_owner_paginated(
+        &self,
+        owner: Address,
+        start: U256,
+        count: U256,
+    ) -> Result<Vec<U256>> {
+        Ok(token_page(&self.owned_tokens.getter(owner), start, count))
+    }
+
+    /// Up to `count` of the NFTs in existence, starting from the `start`th, for crawling the whole supply.
+    /// Pages starting past the supply come back empty.
+    /// The order changes as NFTs are burned, so page through a snapshot by calling at a fixed block.
+    pub fn tokens_by_range(&self, start: U256, count: U256) -> Result<Vec<U256>> {
+        Ok(token_page(&self.all_tokens, start, count))
+    }
+
+    /// The accounts currently authorized to manage all of a user's NFTs, in the order first approved.
+    pub fn operators_of(&self, owner: Address) -> Result<Vec<Address>> {
+        let operators = self.operators.getter(owner);
+        let approved = self.approved_for_all.getter(owner);
+        let operators = (0..operators.len())
+            .filter_map(|index| operators.get(index))
+            .filter(|operator| approved.get(*operator))
+            .collect();
+        Ok(operators)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_vm::TestVm;
+    use alloy_primitives::keccak256;
+    use alloy_sol_types::{sol_data, SolEvent, SolType};
+
+    struct TestParams;
+
+    impl Erc712Params for TestParams {
+        const NAME: &'static str = "Test";
+        const SYMBOL: &'static str = "TEST";
+
+        fn token_uri(_token_id: U256) -> String {
+            String::new()
+        }
+    }
+
+    struct SoulboundParams;
+
+    impl Erc712Params for SoulboundParams {
+        const NAME: &'static str = "Soulbound";
+        const SYMBOL: &'static str = "SOUL";
+        const SOULBOUND: bool = true;
+
+        fn token_uri(_token_id: U256) -> String {
+            String::new()
+        }
+    }
+
+    /// Blocks token 13 from moving, caps how many NFTs an account can hold by reading the NFT's
+    /// storage, and records every transfer made.
+    struct HookParams;
+
+    const BLOCKED_TOKEN: U256 = U256::from_limbs([13, 0, 0, 0]);
+    const MAX_HELD: U256 = U256::from_limbs([2, 0, 0, 0]);
+
+    thread_local! {
+        static HOOK_CALLS: core::cell::RefCell<Vec<(&'static str, Address, Address, U256)>> =
+            Default::default();
+    }
+
+    impl Erc712Params for HookParams {
+        const NAME: &'static str = "Hooked";
+        const SYMBOL: &'static str = "HOOK";
+
+        fn token_uri(_token_id: U256) -> String {
+            String::new()
+        }
+
+        fn before_token_transfer(
+            erc712: &mut Erc712<Self>,
+            from: Address,
+            to: Address,
+            token_id: U256,
+        ) -> Result<()> {
+            HOOK_CALLS.with(|calls| calls.borrow_mut().push(("before", from, to, token_id)));
+            if token_id == BLOCKED_TOKEN {
+                return Err(TransferBlocked { token_id }.into());
+            }
+            if !to.is_zero() && erc712.balance_of(to)? >= MAX_HELD {
+                return Err(TransferBlocked { token_id }.into());
+            }
+            Ok(())
+        }
+
+        fn after_token_transfer(
+            _erc712: &mut Erc712<Self>,
+            from: Address,
+            to: Address,
+            token_id: U256,
+        ) -> Result<()> {
+            HOOK_CALLS.with(|calls| calls.borrow_mut().push(("after", from, to, token_id)));
+            Ok(())
+        }
+    }
+
+    sol_storage! {
+        struct TestNft {
+            #[borrow]
+            Erc712<TestParams> erc712;
+        }
+
+        struct SoulboundNft {
+            #[borrow]
+            Erc712<SoulboundParams> erc712;
+        }
+
+        struct HookedNft {
+            #[borrow]
+            Erc712<HookParams> erc712;
+        }
+    }
+
+    unsafe impl TopLevelStorage for TestNft {}
+    unsafe impl TopLevelStorage for SoulboundNft {}
+    unsafe impl TopLevelStorage for HookedNft {}
+
+    const ALICE: Address = Address::new([0xa1; 20]);
+    const RECEIVER: Address = Address::new([0xbb; 20]);
+    const OPERATOR: Address = Address::new([0x0e; 20]);
+
+    /// Mints token 0 to Alice, then has her safely transfer it to a receiver returning `result`.
+    fn transfer_to_receiver(
+        vm: &TestVm,
+        result: Result<[u8; 4], Vec<u8>>,
+    ) -> (TestNft, Result<()>) {
+        let mut nft: TestNft = vm.contract();
+        vm.set_sender(ALICE);
+        nft.erc712.mint(ALICE).ok().unwrap();
+
+        vm.mock_contract(RECEIVER, move |_, _| {
+            result
+                .clone()
+                .map(|selector| sol_data::FixedBytes::<4>::encode_single(&selector))
+        });
+        let result = Erc712::safe_transfer_from(&mut nft, ALICE, RECEIVER, U256::ZERO);
+        (nft, result)
+    }
+
+    #[test]
+    fn test_receiver_accepts() {
+        let vm = TestVm::new();
+        let (nft, result) = transfer_to_receiver(&vm, Ok(ERC721_TOKEN_RECEIVER_ID.to_be_bytes()));
+        assert!(result.is_ok());
+        assert_eq!(nft.erc712.owner_of(U256::ZERO).ok(), Some(RECEIVER));
+    }
+
+    #[test]
+    fn test_receiver_returns_zero() {
+        let vm = TestVm::new();
+        let (_, result) = transfer_to_receiver(&vm, Ok([0; 4]));
+        let Err(Erc712Error::ReceiverRefused(err)) = result else {
+            panic!("receiver returning zero should be refused");
+        };
+        assert_eq!(err.receiver, RECEIVER);
+        assert_eq!(err.token_id, U256::ZERO);
+        assert_eq!(err.returned, [0; 4]);
+    }
+
+    #[test]
+    fn test_receiver_returns_wrong_selector() {
+        let mut selector = ERC721_TOKEN_RECEIVER_ID.to_be_bytes();
+        selector.reverse();
+        let vm = TestVm::new();
+        let (_, result) = transfer_to_receiver(&vm, Ok(selector));
+        assert!(matches!(result, Err(Erc712Error::ReceiverRefused(_))));
+    }
+
+    #[test]
+    fn test_receiver_reverts() {
+        let vm = TestVm::new();
+        let (_, result) = transfer_to_receiver(&vm, Err(b"nope".to_vec()));
+        assert!(matches!(
+            result,
+            Err(Erc712Error::ExternalCall(stylus_sdk::call::Error::Revert(data))) if data == b"nope"
+        ));
+    }
+
+    #[test]
+    fn test_safe_mint() {
+        let vm = TestVm::new();
+        let mut nft: TestNft = vm.contract();
+        vm.set_sender(ALICE);
+
+        // accounts without code aren't asked
+        let token_id = Erc712::safe_mint(&mut nft, ALICE, vec![]).ok().unwrap();
+        assert_eq!(token_id, U256::ZERO);
+        assert_eq!(nft.erc712.owner_of(token_id).ok(), Some(ALICE));
+        assert!(vm.take_calls().is_empty());
+
+        // receivers hear about the mint from the zero address, with its data
+        vm.mock_contract(RECEIVER, |_, _| {
+            let selector = ERC721_TOKEN_RECEIVER_ID.to_be_bytes();
+            Ok(sol_data::FixedBytes::<4>::encode_single(&selector))
+        });
+        let token_id = Erc712::safe_mint(&mut nft, RECEIVER, b"hi".to_vec())
+            .ok()
+            .unwrap();
+        assert_eq!(nft.erc712.owner_of(token_id).ok(), Some(RECEIVER));
+        assert_eq!(nft.erc712.total_minted().ok(), Some(U256::from(2)));
+        let calls = vm.take_calls();
+        assert_eq!(calls.len(), 1);
+        let calldata = &calls[0].calldata;
+        assert_eq!(calldata[..4], ERC721_TOKEN_RECEIVER_ID.to_be_bytes());
+        assert_eq!(calldata[4..36], ALICE.into_word());
+        assert_eq!(calldata[36..68], B256::ZERO);
+        assert_eq!(calldata[68..100], B256::from(token_id));
+    }
+
+    #[test]
+    fn test_safe_mint_refused() {
+        let vm = TestVm::new();
+        let mut nft: TestNft = vm.contract();
+        vm.set_sender(ALICE);
+        vm.mock_contract(RECEIVER, |_, _| Ok(vec![0; 32]));
+        let result = vm.call(|| Erc712::safe_mint(&mut nft, RECEIVER, vec![]));
+        assert!(matches!(result, Err(Erc712Error::ReceiverRefused(_))));
+
+        // storage fields cache what they've read, so reload them after the revert
+        let nft: TestNft = vm.contract();
+        assert!(nft.erc712.owner_of(U256::ZERO).is_err());
+        assert_eq!(nft.erc712.total_minted().ok(), Some(U256::ZERO));
+    }
+
+    #[test]
+    fn test_soulbound() {
+        let vm = TestVm::new();
+        let mut nft: SoulboundNft = vm.contract();
+        let bob = Address::new([0xb0; 20]);
+        vm.set_sender(ALICE);
+        nft.erc712.mint(ALICE).ok().unwrap();
+        nft.erc712.mint(ALICE).ok().unwrap();
+
+        assert!(matches!(
+            nft.erc712.transfer_from(ALICE, bob, U256::ZERO),
+            Err(Erc712Error::Soulbound(_))
+        ));
+        assert!(matches!(
+            Erc712::safe_transfer_from(&mut nft, ALICE, bob, U256::ZERO),
+            Err(Erc712Error::Soulbound(_))
+        ));
+        assert_eq!(nft.erc712.owner_of(U256::ZERO).ok(), Some(ALICE));
+
+        nft.erc712.burn(ALICE, U256::ZERO).ok().unwrap();
+        assert!(nft.erc712.owner_of(U256::ZERO).is_err());
+        assert_eq!(nft.erc712.owner_of(U256::from(1)).ok(), Some(ALICE));
+    }
+
+    #[test]
+    fn test_erc5192_locked() {
+        let vm = TestVm::new();
+        let mut nft: SoulboundNft = vm.contract();
+        vm.set_sender(ALICE);
+        nft.erc712.mint(ALICE).ok().unwrap();
+
+        // soulbound tokens are locked as they're minted, and stay that way
+        let logs = vm.take_logs();
+        assert_eq!(logs[0].0[0], Transfer::SIGNATURE_HASH);
+        assert_eq!(logs[1], (vec![Locked::SIGNATURE_HASH], vec![0; 32]));
+        assert_eq!(logs.len(), 2);
+        assert_eq!(nft.erc712.locked(U256::ZERO).ok(), Some(true));
+        nft.erc712.set_locked(U256::ZERO, false);
+        assert_eq!(nft.erc712.locked(U256::ZERO).ok(), Some(true));
+        assert!(vm.take_logs().is_empty());
+        assert!(matches!(
+            nft.erc712.locked(U256::from(1)),
+            Err(Erc712Error::InvalidTokenId(_))
+        ));
+
+        // otherwise tokens are only locked while staked
+        let mut nft: TestNft = vm.contract();
+        nft.erc712.mint(ALICE).ok().unwrap();
+        vm.take_logs();
+        assert_eq!(nft.erc712.locked(U256::ZERO).ok(), Some(false));
+        nft.erc712.set_locked(U256::ZERO, true);
+        nft.erc712.set_locked(U256::ZERO, true);
+        assert_eq!(nft.erc712.locked(U256::ZERO).ok(), Some(true));
+        nft.erc712.set_locked(U256::ZERO, false);
+        let logs = vm.take_logs();
+        assert_eq!(logs[0], (vec![Locked::SIGNATURE_HASH], vec![0; 32]));
+        assert_eq!(logs[1], (vec![Unlocked::SIGNATURE_HASH], vec![0; 32]));
+        assert_eq!(logs.len(), 2);
+        assert_eq!(nft.erc712.locked(U256::ZERO).ok(), Some(false));
+    }
+
+    #[test]
+    fn test_supply_counters() {
+        let vm = TestVm::new();
+        let mut nft: TestNft = vm.contract();
+        vm.set_sender(ALICE);
+        for _ in 0..3 {
+            nft.erc712.mint(ALICE).ok().unwrap();
+        }
+        nft.erc712.burn(ALICE, U256::from(1)).ok().unwrap();
+
+        assert_eq!(nft.erc712.total_minted().ok(), Some(U256::from(3)));
+        assert_eq!(nft.erc712.total_burned().ok(), Some(U256::from(1)));
+        assert_eq!(nft.erc712.total_supply().ok(), Some(U256::from(2)));
+
+        // burned ids aren't reused
+        nft.erc712.mint(ALICE).ok().unwrap();
+        assert_eq!(nft.erc712.owner_of(U256::from(3)).ok(), Some(ALICE));
+        assert_eq!(nft.erc712.total_supply().ok(), Some(U256::from(3)));
+    }
+
+    #[test]
+    fn test_owner_spends_without_approval_reads() {
+        let vm = TestVm::new();
+        let mut nft: TestNft = vm.contract();
+        nft.erc712.mint(ALICE).ok().unwrap();
+        nft.erc712.mint(ALICE).ok().unwrap();
+        vm.set_sender(ALICE);
+        nft.erc712.approve(OPERATOR, U256::from(1)).ok().unwrap();
+
+        // the owner only needs the token's owner read
+        vm.take_storage_loads();
+        nft.erc712
+            .require_authorized_to_spend(ALICE, U256::ZERO)
+            .ok()
+            .unwrap();
+        assert_eq!(vm.take_storage_loads(), 1);
+
+        // others also need both kinds of approval, leaving the blocklist to the transfer itself
+        vm.set_sender(OPERATOR);
+        nft.erc712
+            .require_authorized_to_spend(ALICE, U256::from(1))
+            .ok()
+            .unwrap();
+        assert_eq!(vm.take_storage_loads(), 3);
+        assert!(matches!(
+            nft.erc712.require_authorized_to_spend(ALICE, U256::ZERO),
+            Err(Erc712Error::NotApproved(_))
+        ));
+
+        // either way, transfers behave the same
+        nft.erc712
+            .transfer_from(ALICE, RECEIVER, U256::from(1))
+            .ok()
+            .unwrap();
+        vm.set_sender(ALICE);
+        nft.erc712
+            .transfer_from(ALICE, RECEIVER, U256::ZERO)
+            .ok()
+            .unwrap();
+        for id in [U256::ZERO, U256::from(1)] {
+            assert_eq!(nft.erc712.owner_of(id).ok(), Some(RECEIVER));
+        }
+    }
+
+    #[test]
+    fn test_mint_id() {
+        let vm = TestVm::new();
+        let mut nft: TestNft = vm.contract();
+        nft.erc712.mint_id(ALICE, U256::from(1)).ok().unwrap();
+        nft.erc712.mint_id(ALICE, U256::from(2)).ok().unwrap();
+        assert!(matches!(
+            nft.erc712.mint_id(ALICE, U256::from(1)),
+            Err(Erc712Error::NotOwner(_))
+        ));
+
+        // sequential mints skip the ids already taken
+        for expected in [0, 3, 4] {
+            nft.erc712.mint(ALICE).ok().unwrap();
+            let token_id = U256::from(expected);
+            assert_eq!(nft.erc712.owner_of(token_id).ok(), Some(ALICE));
+        }
+        assert_eq!(nft.erc712.total_minted().ok(), Some(U256::from(5)));
+    }
+
+    /// ERC-721's enumerable extension, which isn't advertised.
+    const IERC721_ENUMERABLE: u32 = 0x780e9d63;
+
+    /// Computes an ERC-165 interface id from the signatures of its functions.
+    fn interface_id(signatures: &[&str]) -> u32 {
+        signatures
+            .iter()
+            .map(|sig| u32::from_be_bytes(keccak256(sig)[..4].try_into().unwrap()))
+            .fold(0, |id, selector| id ^ selector)
+    }
+
+    #[test]
+    fn test_interface_ids() {
+        assert_eq!(IERC165, interface_id(&["supportsInterface(bytes4)"]));
+        assert_eq!(
+            IERC721,
+            interface_id(&[
+                "balanceOf(address)",
+                "ownerOf(uint256)",
+                "safeTransferFrom(address,address,uint256,bytes)",
+                "safeTransferFrom(address,address,uint256)",
+                "transferFrom(address,address,uint256)",
+                "approve(address,uint256)",
+                "setApprovalForAll(address,bool)",
+                "getApproved(uint256)",
+                "isApprovedForAll(address,address)",
+            ])
+        );
+        assert_eq!(
+            IERC721_ENUMERABLE,
+            interface_id(&[
+                "totalSupply()",
+                "tokenOfOwnerByIndex(address,uint256)",
+                "tokenByIndex(uint256)",
+            ])
+        );
+
+        assert_eq!(IERC5192, interface_id(&["locked(uint256)"]));
+
+        let supports = |id: u32| Erc712::<TestParams>::supports_interface(id.to_be_bytes().into());
+        assert_eq!(supports(IERC165).ok(), Some(true));
+        assert_eq!(supports(IERC721).ok(), Some(true));
+        assert_eq!(supports(IERC5192).ok(), Some(true));
+        assert_eq!(supports(IERC721_ENUMERABLE).ok(), Some(false));
+        assert_eq!(supports(0xffffffff).ok(), Some(false));
+    }
+
+    #[test]
+    fn test_error_encoding() {
+        let from = Address::new([0xaa; 20]);
+        let real_owner = Address::new([0xbb; 20]);
+        let token_id = U256::from(7);
+
+        // the selector, then each argument as a word
+        let err = Erc712Error::from(NotOwner {
+            from,
+            token_id,
+            real_owner,
+        });
+        let selector = &keccak256("NotOwner(address,uint256,address)")[..4];
+        let words = [from.into_word(), token_id.into(), real_owner.into_word()];
+        let expected = [selector, &words.concat()].concat();
+        assert_eq!(Vec::<u8>::from(err), expected);
+
+        let encodings = [
+            (
+                InvalidTokenId { token_id }.into(),
+                InvalidTokenId { token_id }.encode(),
+            ),
+            (
+                NotApproved {
+                    token_id,
+                    owner: real_owner,
+                    spender: from,
+                }
+                .into(),
+                NotApproved {
+                    token_id,
+                    owner: real_owner,
+                    spender: from,
+                }
+                .encode(),
+            ),
+            (
+                TransferToZero { token_id }.into(),
+                TransferToZero { token_id }.encode(),
+            ),
+            (
+                ReceiverRefused {
+                    receiver: from,
+                    token_id,
+                    returned: [0xde, 0xad, 0xbe, 0xef],
+                }
+                .into(),
+                ReceiverRefused {
+                    receiver: from,
+                    token_id,
+                    returned: [0xde, 0xad, 0xbe, 0xef],
+                }
+                .encode(),
+            ),
+            (
+                OperatorBlocked { operator: from }.into(),
+                OperatorBlocked { operator: from }.encode(),
+            ),
+            (
+                Soulbound { token_id }.into(),
+                Soulbound { token_id }.encode(),
+            ),
+            (
+                TokenStaked { token_id }.into(),
+                TokenStaked { token_id }.encode(),
+            ),
+            (
+                TransferBlocked { token_id }.into(),
+                TransferBlocked { token_id }.encode(),
+            ),
+            (
+                InvalidOwner { owner: from }.into(),
+                InvalidOwner { owner: from }.encode(),
+            ),
+        ];
+        for (err, expected) in encodings {
+            let err: Erc712Error = err;
+            assert_eq!(Vec::<u8>::from(err), expected);
+        }
+
+        // external call failures pass the callee's revert data through
+        let revert = stylus_sdk::call::Error::Revert(b"nope".to_vec());
+        assert_eq!(Vec::<u8>::from(Erc712Error::from(revert)), b"nope");
+    }
+
+    #[test]
+    fn test_transfer_hooks() {
+        let vm = TestVm::new();
+        let mut nft: HookedNft = vm.contract();
+        HOOK_CALLS.with(|calls| calls.borrow_mut().clear());
+        let take_calls = || HOOK_CALLS.with(|calls| calls.take());
+
+        // mints, transfers, and burns all run both hooks
+        vm.set_sender(ALICE);
+        nft.erc712.mint(ALICE).ok().unwrap();
+        nft.erc712
+            .transfer_from(ALICE, RECEIVER, U256::ZERO)
+            .ok()
+            .unwrap();
+        vm.set_sender(RECEIVER);
+        nft.erc712.burn(RECEIVER, U256::ZERO).ok().unwrap();
+        let zero = Address::ZERO;
+        assert_eq!(
+            take_calls(),
+            vec![
+                ("before", zero, ALICE, U256::ZERO),
+                ("after", zero, ALICE, U256::ZERO),
+                ("before", ALICE, RECEIVER, U256::ZERO),
+                ("after", ALICE, RECEIVER, U256::ZERO),
+                ("before", RECEIVER, zero, U256::ZERO),
+                ("after", RECEIVER, zero, U256::ZERO),
+            ]
+        );
+
+        // a reverting hook stops the mint before anything changes
+        vm.take_logs();
+        let result = nft.erc712.mint_id(ALICE, BLOCKED_TOKEN);
+        assert!(matches!(
+            result,
+            Err(Erc712Error::TransferBlocked(TransferBlocked { token_id })) if token_id == BLOCKED_TOKEN
+        ));
+        assert_eq!(take_calls(), vec![("before", zero, ALICE, BLOCKED_TOKEN)]);
+        assert!(nft.erc712.owner_of(BLOCKED_TOKEN).is_err());
+        assert!(vm.take_logs().is_empty());
+
+        // hooks can read the NFT's storage, here to cap what an account holds
+        nft.erc712.mint(ALICE).ok().unwrap();
+        nft.erc712.mint(ALICE).ok().unwrap();
+        assert!(matches!(
+            nft.erc712.mint(ALICE),
+            Err(Erc712Error::TransferBlocked(_))
+        ));
+        assert_eq!(nft.erc712.balance_of(ALICE).ok(), Some(MAX_HELD));
+        nft.erc712.mint(RECEIVER).ok().unwrap();
+    }
+
+    #[test]
+    fn test_operators_of() {
+        let vm = TestVm::new();
+        let mut nft: TestNft = vm.contract();
+        let other = Address::new([0x0f; 20]);
+        vm.set_sender(ALICE);
+        assert_eq!(nft.erc712.operators_of(ALICE).ok(), Some(vec![]));
+
+        nft.erc712
+            .set_approval_for_all(OPERATOR, true)
+            .ok()
+            .unwrap();
+        nft.erc712.set_approval_for_all(other, true).ok().unwrap();
+        let both = vec![OPERATOR, other];
+        assert_eq!(nft.erc712.operators_of(ALICE).ok(), Some(both.clone()));
+        assert_eq!(nft.erc712.operators_of(RECEIVER).ok(), Some(vec![]));
+
+        // revoked operators drop out, and aren't listed twice when approved again
+        nft.erc712
+            .set_approval_for_all(OPERATOR, false)
+            .ok()
+            .unwrap();
+        assert_eq!(nft.erc712.operators_of(ALICE).ok(), Some(vec![other]));
+        nft.erc712
+            .set_approval_for_all(OPERATOR, true)
+            .ok()
+            .unwrap();
+        nft.erc712
+            .set_approval_for_all(OPERATOR, true)
+            .ok()
+            .unwrap();
+        assert_eq!(nft.erc712.operators_of(ALICE).ok(), Some(both));
+    }
+
+    #[test]
+    fn test_tokens_of_owner_paginated() {
+        let vm = TestVm::new();
+        let mut nft: TestNft = vm.contract();
+        let page = |nft: &TestNft, owner, start: u32, count: u32| {
+            nft.erc712
+                .tokens_of_owner_paginated(owner, U256::from(start), U256::from(count))
+                .ok()
+                .unwrap()
+        };
+
+        vm.set_sender(ALICE);
+        for _ in 0..25 {
+            nft.erc712.mint(ALICE).ok().unwrap();
+        }
+        nft.erc712.mint(RECEIVER).ok().unwrap();
+
+        // moving NFTs out shuffles Alice's list, but never leaves a hole
+        for id in [3, 24, 10] {
+            nft.erc712
+                .transfer_from(ALICE, RECEIVER, U256::from(id))
+                .ok()
+                .unwrap();
+        }
+        nft.erc712.burn(ALICE, U256::from(0)).ok().unwrap();
+
+        let mut seen = Vec::new();
+        let mut start = 0;
+        loop {
+            let tokens = page(&nft, ALICE, start, 8);
+            seen.extend(tokens.iter().map(|id| id.to::<u32>()));
+            start += 8;
+            if tokens.len() < 8 {
+                break;
+            }
+        }
+        assert_eq!(seen.len(), 21);
+        seen.sort();
+        let expected: Vec<u32> = (1..24).filter(|id| ![3, 10].contains(id)).collect();
+        assert_eq!(seen, expected);
+
+        let received = page(&nft, RECEIVER, 0, 10);
+        let ids = [25, 3, 24, 10].map(U256::from);
+        assert_eq!(received, ids);
+        assert_eq!(page(&nft, RECEIVER, 1, 2), ids[1..3]);
+
+        // pages past the end come back short or empty, even when they'd overflow
+        assert_eq!(page(&nft, RECEIVER, 3, 10), ids[3..]);
+        assert!(page(&nft, RECEIVER, 4, 10).is_empty());
+        let past = nft
+            .erc712
+            .tokens_of_owner_paginated(RECEIVER, U256::MAX, U256::MAX)
+            .ok();
+        assert_eq!(past, Some(vec![]));
+        assert!(page(&nft, OPERATOR, 0, 10).is_empty());
+    }
+
+    #[test]
+    fn test_tokens_by_range() {
+        let vm = TestVm::new();
+        let mut nft: TestNft = vm.contract();
+        vm.set_sender(ALICE);
+        for _ in 0..30 {
+            nft.erc712.mint(ALICE).ok().unwrap();
+        }
+        nft.erc712
+            .mint_id(RECEIVER, U256::from(1_000))
+            .ok()
+            .unwrap();
+        for id in [0, 17, 29] {
+            nft.erc712.burn(ALICE, U256::from(id)).ok().unwrap();
+        }
+        let supply = nft.erc712.total_supply().ok().unwrap();
+        assert_eq!(supply, U256::from(28));
+
+        let mut crawled = Vec::new();
+        let mut start = U256::ZERO;
+        while start < supply {
+            let page = nft.erc712.tokens_by_range(start, U256::from(10)).ok();
+            crawled.extend(page.unwrap());
+            start += U256::from(10);
+        }
+        crawled.sort();
+        let mut expected: Vec<U256> = (1..29).filter(|id| *id != 17).map(U256::from).collect();
+        expected.push(U256::from(1_000));
+        assert_eq!(crawled, expected);
+
+        // pages are clamped to the supply
+        let last = nft.erc712.tokens_by_range(U256::from(25), U256::from(10));
+        assert_eq!(last.ok().map(|page| page.len()), Some(3));
+        let past = nft.erc712.tokens_by_range(supply, U256::MAX);
+        assert_eq!(past.ok(), Some(vec![]));
+    }
+
+    #[test]
+    fn test_safe_transfer_batch() {
+        let vm = TestVm::new();
+        let mut nft: TestNft = vm.contract();
+        vm.set_sender(ALICE);
+        for _ in 0..3 {
+            nft.erc712.mint(ALICE).ok().unwrap();
+        }
+        let ids = vec![U256::ZERO, U256::from(2)];
+
+        vm.mock_contract(RECEIVER, |_, _| {
+            let selector = ERC721_TOKEN_RECEIVER_ID.to_be_bytes();
+            Ok(sol_data::FixedBytes::<4>::encode_single(&selector))
+        });
+        let data = Bytes(b"batch".to_vec());
+        vm.take_calls();
+        Erc712::safe_transfer_batch(&mut nft, ALICE, RECEIVER, ids.clone(), data)
+            .ok()
+            .unwrap();
+        for &id in &ids {
+            assert_eq!(nft.erc712.owner_of(id).ok(), Some(RECEIVER));
+        }
+        assert_eq!(nft.erc712.owner_of(U256::from(1)).ok(), Some(ALICE));
+
+        // the receiver hears about each NFT
+        let calls = vm.take_calls();
+        assert_eq!(calls.len(), 2);
+        assert!(calls.iter().all(|call| call.to == RECEIVER));
+    }
+
+    #[test]
+    fn test_safe_transfer_batch_unauthorized() {
+        let vm = TestVm::new();
+        let mut nft: TestNft = vm.contract();
+        vm.set_sender(ALICE);
+        for _ in 0..3 {
+            nft.erc712.mint(ALICE).ok().unwrap();
+        }
+        nft.erc712.approve(OPERATOR, U256::ZERO).ok().unwrap();
+        nft.erc712.approve(OPERATOR, U256::from(1)).ok().unwrap();
+
+        // the operator wasn't approved for the last NFT, so none move
+        vm.set_sender(OPERATOR);
+        vm.take_logs();
+        let ids = vec![U256::ZERO, U256::from(1), U256::from(2)];
+        let result = Erc712::safe_transfer_batch(&mut nft, ALICE, RECEIVER, ids, Bytes(vec![]));
+        assert!(matches!(
+            result,
+            Err(Erc712Error::NotApproved(NotApproved { token_id, .. })) if token_id == U256::from(2)
+        ));
+        for id in 0..3 {
+            assert_eq!(nft.erc712.owner_of(U256::from(id)).ok(), Some(ALICE));
+        }
+        assert!(vm.take_logs().is_empty());
+    }
+
+    #[test]
+    fn test_approve_batch() {
+        let vm = TestVm::new();
+        let mut nft: TestNft = vm.contract();
+        let bob = Address::new([0xb0; 20]);
+        vm.set_sender(ALICE);
+        for _ in 0..3 {
+            nft.erc712.mint(ALICE).ok().unwrap();
+        }
+        nft.erc712.mint(bob).ok().unwrap();
+
+        vm.take_logs();
+        let ids = vec![U256::ZERO, U256::from(2)];
+        nft.erc712
+            .approve_batch(OPERATOR, ids.clone())
+            .ok()
+            .unwrap();
+        for &id in &ids {
+            assert_eq!(nft.erc712.get_approved(id).ok(), Some(OPERATOR));
+        }
+        assert_eq!(
+            nft.erc712.get_approved(U256::from(1)).ok(),
+            Some(Address::ZERO)
+        );
+
+        // each NFT logs its own approval
+        let logs = vm.take_logs();
+        assert_eq!(logs.len(), 2);
+        for (log, id) in logs.iter().zip(ids) {
+            let topics = vec![
+                Approval::SIGNATURE_HASH,
+                ALICE.into_word(),
+                OPERATOR.into_word(),
+                id.into(),
+            ];
+            assert_eq!(log, &(topics, vec![]));
+        }
+
+        // operators can approve for the owner too
+        nft.erc712
+            .set_approval_for_all(OPERATOR, true)
+            .ok()
+            .unwrap();
+        vm.set_sender(OPERATOR);
+        nft.erc712
+            .approve_batch(RECEIVER, vec![U256::from(1)])
+            .ok()
+            .unwrap();
+        assert_eq!(nft.erc712.get_approved(U256::from(1)).ok(), Some(RECEIVER));
+    }
+
+    #[test]
+    fn test_approve_batch_unauthorized() {
+        let vm = TestVm::new();
+        let mut nft: TestNft = vm.contract();
+        let bob = Address::new([0xb0; 20]);
+        nft.erc712.mint(ALICE).ok().unwrap();
+        nft.erc712.mint(bob).ok().unwrap();
+        nft.erc712.mint(ALICE).ok().unwrap();
+
+        // ALICE doesn't own the middle NFT, so none are approved
+        vm.set_sender(ALICE);
+        vm.take_logs();
+        let ids = vec![U256::ZERO, U256::from(1), U256::from(2)];
+        assert!(matches!(
+            nft.erc712.approve_batch(OPERATOR, ids),
+            Err(Erc712Error::NotApproved(NotApproved { token_id, owner, .. }))
+                if token_id == U256::from(1) && owner == bob
+        ));
+        for id in 0..3 {
+            let id = U256::from(id);
+            assert_eq!(nft.erc712.get_approved(id).ok(), Some(Address::ZERO));
+        }
+        assert!(vm.take_logs().is_empty());
+
+        // nor can a batch include NFTs that don't exist
+        assert!(matches!(
+            nft.erc712
+                .approve_batch(OPERATOR, vec![U256::ZERO, U256::from(9)]),
+            Err(Erc712Error::InvalidTokenId(_))
+        ));
+        assert_eq!(
+            nft.erc712.get_approved(U256::ZERO).ok(),
+            Some(Address::ZERO)
+        );
+    }
+
+    #[test]
+    fn test_transfer_clears_approval() {
+        let vm = TestVm::new();
+        let mut nft: TestNft = vm.contract();
+        vm.set_sender(ALICE);
+        nft.erc712.mint(ALICE).ok().unwrap();
+        nft.erc712.mint(ALICE).ok().unwrap();
+        nft.erc712.approve(OPERATOR, U256::ZERO).ok().unwrap();
+        assert_eq!(nft.erc712.get_approved(U256::ZERO).ok(), Some(OPERATOR));
+
+        vm.set_sender(OPERATOR);
+        vm.take_logs();
+        nft.erc712
+            .transfer_from(ALICE, RECEIVER, U256::ZERO)
+            .ok()
+            .unwrap();
+        assert_eq!(
+            nft.erc712.get_approved(U256::ZERO).ok(),
+            Some(Address::ZERO)
+        );
+
+        let logs = vm.take_logs();
+        let topics = vec![
+            Approval::SIGNATURE_HASH,
+            ALICE.into_word(),
+            B256::ZERO,
+            U256::ZERO.into(),
+        ];
+        assert_eq!(logs[0], (topics, vec![]));
+        assert_eq!(logs[1].0[0], Transfer::SIGNATURE_HASH);
+        assert_eq!(logs.len(), 2);
+
+        // without an approval to clear, a transfer only logs itself
+        vm.set_sender(ALICE);
+        nft.erc712
+            .transfer_from(ALICE, RECEIVER, U256::from(1))
+            .ok()
+            .unwrap();
+        let logs = vm.take_logs();
+        assert_eq!(logs.len(), 1);
+        assert_eq!(logs[0].0[0], Transfer::SIGNATURE_HASH);
+    }
+
+    /// Walks the ERC-721 transfer paths, checking the owner, balances, and approval each leaves.
+    mod conformance {
+        use super::*;
+
+        const BOB: Address = Address::new([0xb0; 20]);
+        const SPENDER: Address = Address::new([0x5e; 20]);
+        const TOKEN: U256 = U256::ZERO;
+
+        /// Mints Alice two NFTs, approving the spender for the first and the operator for all.
+        fn setup(vm: &TestVm) -> TestNft {
+            let mut nft: TestNft = vm.contract();
+            vm.set_sender(ALICE);
+            nft.erc712.mint(ALICE).ok().unwrap();
+            nft.erc712.mint(ALICE).ok().unwrap();
+            nft.erc712.approve(SPENDER, TOKEN).ok().unwrap();
+            nft.erc712
+                .set_approval_for_all(OPERATOR, true)
+                .ok()
+                .unwrap();
+            nft
+        }
+
+        /// Asserts who owns the first NFT, its approval, and the balances of everyone involved.
+        fn assert_state(nft: &mut TestNft, owner: Address, approved: Address) {
+            assert_eq!(nft.erc712.owner_of(TOKEN).ok(), Some(owner));
+            assert_eq!(nft.erc712.get_approved(TOKEN).ok(), Some(approved));
+            for account in [ALICE, BOB, RECEIVER] {
+                let expected = match account {
+                    ALICE if owner == ALICE => 2,
+                    ALICE => 1,
+                    account if account == owner => 1,
+                    _ => 0,
+                };
+                let balance = nft.erc712.balance_of(account).ok();
+                assert_eq!(balance, Some(U256::from(expected)), "{account}");
+            }
+
+            // the second NFT never moves
+            assert_eq!(nft.erc712.owner_of(U256::from(1)).ok(), Some(ALICE));
+        }
+
+        /// Transfers the first NFT from Alice to `to` as `sender`.
+        fn transfer_as(nft: &mut TestNft, vm: &TestVm, sender: Address, to: Address) -> Result<()> {
+            vm.set_sender(sender);
+            nft.erc712.transfer_from(ALICE, to, TOKEN)
+        }
+
+        #[test]
+        fn test_owner_transfer() {
+            let vm = TestVm::new();
+            let mut nft = setup(&vm);
+            assert_state(&mut nft, ALICE, SPENDER);
+
+            transfer_as(&mut nft, &vm, ALICE, BOB).ok().unwrap();
+            assert_state(&mut nft, BOB, Address::ZERO);
+
+            // the operator is only approved for Alice's NFTs
+            assert!(nft
+                .erc712
+                .is_approved_for_all(ALICE, OPERATOR)
+                .ok()
+                .unwrap());
+            assert!(matches!(
+                transfer_as(&mut nft, &vm, OPERATOR, BOB),
+                Err(Erc712Error::NotOwner(_))
+            ));
+        }
+
+        #[test]
+        fn test_approved_transfer() {
+            let vm = TestVm::new();
+            let mut nft = setup(&vm);
+            transfer_as(&mut nft, &vm, SPENDER, BOB).ok().unwrap();
+            assert_state(&mut nft, BOB, Address::ZERO);
+
+            // the approval went with the NFT, so the spender can't take it back
+            vm.set_sender(SPENDER);
+            assert!(matches!(
+                nft.erc712.transfer_from(BOB, ALICE, TOKEN),
+                Err(Erc712Error::NotApproved(_))
+            ));
+            assert_state(&mut nft, BOB, Address::ZERO);
+        }
+
+        #[test]
+        fn test_operator_transfer() {
+            let vm = TestVm::new();
+            let mut nft = setup(&vm);
+            transfer_as(&mut nft, &vm, OPERATOR, BOB).ok().unwrap();
+            assert_state(&mut nft, BOB, Address::ZERO);
+
+            // the operator stays approved for Alice's other NFTs
+            assert!(nft
+                .erc712
+                .is_approved_for_all(ALICE, OPERATOR)
+                .ok()
+                .unwrap());
+            nft.erc712
+                .transfer_from(ALICE, BOB, U256::from(1))
+                .ok()
+                .unwrap();
+            assert_eq!(nft.erc712.owner_of(U256::from(1)).ok(), Some(BOB));
+            assert_eq!(nft.erc712.balance_of(BOB).ok(), Some(U256::from(2)));
+        }
+
+        #[test]
+        fn test_unauthorized_transfer() {
+            let vm = TestVm::new();
+            let mut nft = setup(&vm);
+            assert!(matches!(
+                transfer_as(&mut nft, &vm, BOB, BOB),
+                Err(Erc712Error::NotApproved(NotApproved {
+                    owner: ALICE,
+                    spender: BOB,
+                    ..
+                }))
+            ));
+            assert_state(&mut nft, ALICE, SPENDER);
+
+            // nor can the spender move it from someone who doesn't own it
+            vm.set_sender(SPENDER);
+            assert!(matches!(
+                nft.erc712.transfer_from(BOB, RECEIVER, TOKEN),
+                Err(Erc712Error::NotOwner(NotOwner {
+                    real_owner: ALICE,
+                    ..
+                }))
+            ));
+            assert_state(&mut nft, ALICE, SPENDER);
+
+            // a revoked operator loses access
+            vm.set_sender(ALICE);
+            nft.erc712
+                .set_approval_for_all(OPERATOR, false)
+                .ok()
+                .unwrap();
+            assert!(matches!(
+                transfer_as(&mut nft, &vm, OPERATOR, BOB),
+                Err(Erc712Error::NotApproved(_))
+            ));
+            assert_state(&mut nft, ALICE, SPENDER);
+        }
+
+        #[test]
+        fn test_transfer_to_zero() {
+            let vm = TestVm::new();
+            let mut nft = setup(&vm);
+            for sender in [ALICE, SPENDER, OPERATOR] {
+                assert!(matches!(
+                    transfer_as(&mut nft, &vm, sender, Address::ZERO),
+                    Err(Erc712Error::TransferToZero(_))
+                ));
+                assert!(matches!(
+                    Erc712::safe_transfer_from(&mut nft, ALICE, Address::ZERO, TOKEN),
+                    Err(Erc712Error::TransferToZero(_))
+                ));
+                assert_state(&mut nft, ALICE, SPENDER);
+            }
+
+            // nor does the zero address get a balance from mints and burns
+            nft.erc712.burn(ALICE, U256::from(1)).ok().unwrap();
+            assert_eq!(nft.erc712.balance_of(ALICE).ok(), Some(U256::from(1)));
+            assert!(matches!(
+                nft.erc712.balance_of(Address::ZERO),
+                Err(Erc712Error::InvalidOwner(_))
+            ));
+        }
+
+        #[test]
+        fn test_safe_transfer_accepted() {
+            let vm = TestVm::new();
+            let mut nft = setup(&vm);
+            vm.mock_contract(RECEIVER, |_, _| {
+                let selector = ERC721_TOKEN_RECEIVER_ID.to_be_bytes();
+                Ok(sol_data::FixedBytes::<4>::encode_single(&selector))
+            });
+            vm.take_calls();
+            vm.set_sender(SPENDER);
+            Erc712::safe_transfer_from(&mut nft, ALICE, RECEIVER, TOKEN)
+                .ok()
+                .unwrap();
+            assert_state(&mut nft, RECEIVER, Address::ZERO);
+
+            // the receiver hears who sent the NFT, and from whom
+            let calls = vm.take_calls();
+            assert_eq!(calls.len(), 1);
+            let calldata = &calls[0].calldata;
+            assert_eq!(calldata[..4], ERC721_TOKEN_RECEIVER_ID.to_be_bytes());
+            assert_eq!(calldata[4..36], SPENDER.into_word());
+            assert_eq!(calldata[36..68], ALICE.into_word());
+            assert_eq!(calldata[68..100], B256::from(TOKEN));
+
+            // accounts without code need not be asked
+            vm.set_sender(RECEIVER);
+            Erc712::safe_transfer_from(&mut nft, RECEIVER, BOB, TOKEN)
+                .ok()
+                .unwrap();
+            assert!(vm.take_calls().is_empty());
+            assert_eq!(nft.erc712.owner_of(TOKEN).ok(), Some(BOB));
+            assert_eq!(nft.erc712.balance_of(RECEIVER).ok(), Some(U256::ZERO));
+            assert_eq!(nft.erc712.balance_of(BOB).ok(), Some(U256::from(1)));
+        }
+
+        #[test]
+        fn test_safe_mint() {
+            let vm = TestVm::new();
+            let mut nft = setup(&vm);
+            vm.mock_contract(RECEIVER, |_, _| {
+                let selector = ERC721_TOKEN_RECEIVER_ID.to_be_bytes();
+                Ok(sol_data::FixedBytes::<4>::encode_single(&selector))
+            });
+            vm.take_calls();
+            let token_id = Erc712::safe_mint(&mut nft, RECEIVER, vec![]).ok().unwrap();
+            assert_eq!(token_id, U256::from(2));
+            assert_eq!(nft.erc712.owner_of(token_id).ok(), Some(RECEIVER));
+            assert_eq!(nft.erc712.balance_of(RECEIVER).ok(), Some(U256::from(1)));
+            assert_eq!(nft.erc712.get_approved(token_id).ok(), Some(Address::ZERO));
+            assert_eq!(nft.erc712.balance_of(ALICE).ok(), Some(U256::from(2)));
+
+            // the receiver hears the mint came from the zero address
+            let calls = vm.take_calls();
+            assert_eq!(calls.len(), 1);
+            assert_eq!(calls[0].calldata[36..68], B256::ZERO);
+
+            // a receiver that refuses reverts the mint
+            vm.mock_contract(BOB, |_, _| Ok(vec![0; 32]));
+            let result = vm.call(|| Erc712::safe_mint(&mut nft, BOB, vec![]));
+            assert!(matches!(result, Err(Erc712Error::ReceiverRefused(_))));
+            let nft: TestNft = vm.contract(); // reload what the revert undid
+            assert!(nft.erc712.owner_of(U256::from(3)).is_err());
+            assert_eq!(nft.erc712.balance_of(BOB).ok(), Some(U256::ZERO));
+            assert_eq!(nft.erc712.total_minted().ok(), Some(U256::from(3)));
+        }
+
+        #[test]
+        fn test_safe_transfer_refused() {
+            for response in [Ok(vec![0; 32]), Err(b"nope".to_vec())] {
+                let vm = TestVm::new();
+                let mut nft = setup(&vm);
+                vm.mock_contract(RECEIVER, move |_, _| response.clone());
+                vm.set_sender(SPENDER);
+                vm.take_logs();
+                let result =
+                    vm.call(|| Erc712::safe_transfer_from(&mut nft, ALICE, RECEIVER, TOKEN));
+                assert!(matches!(
+                    result,
+                    Err(Erc712Error::ReceiverRefused(_) | Erc712Error::ExternalCall(_))
+                ));
+
+                // the receiver is asked after the NFT moves, so the revert is what undoes it
+                assert_state(&mut nft, ALICE, SPENDER);
+                assert!(vm.take_logs().is_empty());
+            }
+        }
+    }
 }
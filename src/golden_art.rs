@@ -0,0 +1,74 @@
+//! Snapshot tests pinning the look of generated NFTs.
+//!
+//! Each case renders a token in every version of the art and compares a hash of its pixels
+//! against the goldens in `golden_art.txt`, so any change to the drawing code that alters a
+//! token fails here. Old versions stay pinned, since minted tokens keep the version they got.
+//! When a change is intended, update the goldens and commit them alongside it:
+//!
+//! ```sh
+//! cargo test regenerate_golden_art -- --ignored
+//! ```
+
+use crate::art::{self, ArtStyle, DefaultStyle};
+use alloy_primitives::{keccak256, Address, B256, U256};
+use std::{fs, path::PathBuf};
+
+/// The tokens whose art is pinned.
+const CASES: [(Address, u64); 8] = [
+    (Address::new([0x00; 20]), 0),
+    (Address::new([0x00; 20]), 1),
+    (Address::new([0x42; 20]), 0),
+    (Address::new([0x42; 20]), 7),
+    (Address::new([0x42; 20]), 42),
+    (Address::new([0x42; 20]), 1234),
+    (Address::new([0xc0; 20]), 99),
+    (Address::new([0xff; 20]), 123_456_789),
+];
+
+fn golden_path() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("src/golden_art.txt")
+}
+
+/// Hashes the token's pixels, so changes to the image encoders don't count as drift.
+fn render_hash(version: u8, address: Address, token_id: u64) -> B256 {
+    let image = art::generate_nft_version(version, address, U256::from(token_id));
+    let rgb: Vec<u8> = image
+        .pixels
+        .iter()
+        .flatten()
+        .flat_map(|p| [p.red, p.green, p.blue])
+        .collect();
+    keccak256(rgb)
+}
+
+/// Renders every case in every version as a line of the golden file.
+fn render_goldens() -> String {
+    (1..=DefaultStyle::VERSION)
+        .flat_map(|version| CASES.iter().map(move |&case| (version, case)))
+        .map(|(version, (address, id))| {
+            let hash = render_hash(version, address, id);
+            let address = hex::encode(address);
+            format!("v{version} {address} {id} {}\n", hex::encode(hash))
+        })
+        .collect()
+}
+
+#[test]
+fn golden_art() {
+    let expected = fs::read_to_string(golden_path()).expect("missing golden_art.txt");
+    let actual = render_goldens();
+    for (expected, actual) in expected.lines().zip(actual.lines()) {
+        assert_eq!(
+            actual, expected,
+            "art drifted; if intended, run `cargo test regenerate_golden_art -- --ignored`"
+        );
+    }
+    let versions = usize::from(DefaultStyle::VERSION);
+    assert_eq!(expected.lines().count(), versions * CASES.len());
+}
+
+#[test]
+#[ignore = "overwrites the goldens"]
+fn regenerate_golden_art() {
+    fs::write(golden_path(), render_goldens()).expect("failed to write golden_art.txt");
+}
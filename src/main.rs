@@ -6,32 +6,93 @@
 
 // Only run this as a WASM if the export-abi feature is not set.
 #![cfg_attr(not(any(feature = "export-abi", test)), no_main)]
-#![cfg_attr(not(test), no_std)]
+#![cfg_attr(not(any(test, feature = "std")), no_std)]
 extern crate alloc;
 
+#[macro_use]
+mod solidity_error;
+
 mod art;
+#[cfg(test)]
+mod bench;
 pub mod erc712;
 #[cfg(test)]
+mod golden_art;
+#[cfg(test)]
 mod print_art;
+#[cfg(test)]
+mod test_vm;
 pub mod utils;
 
 use crate::erc712::Erc712;
-use alloc::{string::String, vec::Vec};
-use alloy_primitives::{uint, U256};
-use alloy_sol_types::{sol, SolError};
-use base64::Engine;
-use erc712::{Erc712Error, Erc712Params};
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+use alloy_primitives::{uint, Address, FixedBytes, B256, U16, U256, U8};
+use alloy_sol_types::sol;
+#[cfg(test)]
+use alloy_sol_types::SolError;
+use art::{ArtStyle, DefaultStyle, Image};
+use erc712::{Erc712Error, Erc712Params, NotOwner, ERC721_TOKEN_RECEIVER_ID};
 use stylus_sdk::{
-    abi::Bytes,
+    abi::{Bytes, Router},
     call::{self, Call},
-    msg,
+    contract, crypto, evm,
     prelude::*,
 };
+use utils::{json, ImageFormat};
+
+// Tests swap in a mock, since the SDK caches the call context for the whole process.
+#[cfg(not(test))]
+use stylus_sdk::{block, msg};
+#[cfg(test)]
+use test_vm::{block, msg};
 
 /// Initializes a custom, global allocator for Rust programs compiled to WASM.
 #[global_allocator]
 static ALLOC: wee_alloc::WeeAlloc = wee_alloc::WeeAlloc::INIT;
 
+/// The encoding of the image in each token's URI.
+const IMAGE_FORMAT: ImageFormat = ImageFormat::Png;
+
+/// How many blocks living art takes to shift its hue by one degree.
+/// Arbitrum makes about 4 blocks a second, so the hue comes full circle roughly every 15 hours.
+const BLOCKS_PER_HUE_DEGREE: u64 = 600;
+
+/// What `token_uri` returns for revealed tokens without a base URI, as set by the owner.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[repr(u8)]
+pub enum MetadataFormat {
+    /// JSON metadata listing the token's traits, with the image embedded in `IMAGE_FORMAT`.
+    #[default]
+    Json = 0,
+    /// Just the image, encoded in `IMAGE_FORMAT`.
+    Image = 1,
+    /// Just the image, as an SVG.
+    Svg = 2,
+    /// Like `Json`, plus an `animation_url` frame whose hue drifts with the block number.
+    /// The `image` stays the static art, for platforms that cache it.
+    Living = 3,
+}
+
+impl MetadataFormat {
+    pub const fn from_u8(format: u8) -> Option<Self> {
+        match format {
+            0 => Some(Self::Json),
+            1 => Some(Self::Image),
+            2 => Some(Self::Svg),
+            3 => Some(Self::Living),
+            _ => None,
+        }
+    }
+}
+
+/// The art drawn for each token. Swap in another [`ArtStyle`] to change it.
+type Style = DefaultStyle;
+
 /// Configures the NFT data.
 struct StylusWorkshopParams;
 
@@ -40,11 +101,72 @@ impl Erc712Params for StylusWorkshopParams {
     const SYMBOL: &'static str = "SNFT";
 
     fn token_uri(token_id: U256) -> String {
-        let image = art::generate_nft(stylus_sdk::contract::address(), token_id);
-        let png = image.make_png();
-        let mut out = String::from("data:image/png;base64,");
-        base64::engine::general_purpose::STANDARD.encode_string(&png, &mut out);
-        out
+        style_token_uri::<Style, 32, 32>(Style::VERSION, token_id, MetadataFormat::default())
+    }
+}
+
+/// Embeds `data` in a base64 data URI.
+fn data_uri(mime_type: &str, data: &[u8]) -> String {
+    let mut out = String::from("data:");
+    out.push_str(mime_type);
+    out.push_str(";base64,");
+    utils::base64_encode_into(data, &mut out);
+    out
+}
+
+/// Renders a token in the given version of a style, returning it as a data URI in `format`.
+fn style_token_uri<S: ArtStyle<R, C>, const R: usize, const C: usize>(
+    version: u8,
+    token_id: U256,
+    format: MetadataFormat,
+) -> String {
+    let image = S::render(version, contract::address(), token_id);
+    image_token_uri::<S, R, C>(version, token_id, token_id, &image, format)
+}
+
+/// Returns a token's `image` as a data URI in `format`,
+/// listing the traits its art id has in the given version of a style.
+fn image_token_uri<S: ArtStyle<R, C>, const R: usize, const C: usize>(
+    version: u8,
+    token_id: U256,
+    art_id: U256,
+    image: &Image<R, C>,
+    format: MetadataFormat,
+) -> String {
+    let address = contract::address();
+    let image_uri = |format: ImageFormat| data_uri(format.mime_type(), &image.encode(format));
+    match format {
+        MetadataFormat::Image => image_uri(IMAGE_FORMAT),
+        MetadataFormat::Svg => image_uri(ImageFormat::Svg),
+        MetadataFormat::Json | MetadataFormat::Living => {
+            let attributes =
+                S::attributes(version, address, art_id)
+                    .into_iter()
+                    .map(|(trait_type, value)| {
+                        json::Value::object([
+                            ("trait_type", trait_type.into()),
+                            ("value", value.into()),
+                        ])
+                    });
+            let name = format!("{} #{token_id}", StylusWorkshopParams::NAME);
+            let mut fields = vec![
+                ("name", json::Value::string(name)),
+                ("image", json::Value::string(image_uri(IMAGE_FORMAT))),
+            ];
+            if format == MetadataFormat::Living {
+                let mut frame = Image {
+                    pixels: image.pixels.clone(),
+                };
+                frame.shift_hue((block::number() / BLOCKS_PER_HUE_DEGREE % 360) as u16);
+                let frame_uri = data_uri(IMAGE_FORMAT.mime_type(), &frame.encode(IMAGE_FORMAT));
+                fields.push(("animation_url", json::Value::string(frame_uri)));
+            }
+            fields.push(("attributes", json::Value::array(attributes)));
+            data_uri(
+                "application/json",
+                json::Value::object(fields).to_string().as_bytes(),
+            )
+        }
     }
 }
 
@@ -54,48 +176,187 @@ sol_storage! {
     struct StylusWorkshopNft {
         #[borrow]
         Erc712<StylusWorkshopParams> erc712;
+        address owner;
+        bool revealed;
+        // `string` storage can only be read mutably, which would make `token_uri` non-view
+        bytes placeholder_uri;
+        bytes base_uri;
+        bytes32 provenance_hash;
+        bool metadata_frozen;
+        mapping(uint256 => uint256) staked_since;
+        uint256 referral_bps;
+        address royalty_receiver;
+        uint256 royalty_bps;
+        mapping(uint256 => address) token_royalty_receivers;
+        mapping(uint256 => uint256) token_royalty_bps;
+        address mint_signer;
+        mapping(bytes32 => bool) redeemed_mints;
+        mapping(address => uint256) pending_withdrawals;
+        uint8 metadata_format;
+        mapping(address => uint256) permit_nonces;
+        mapping(uint256 => bytes) token_uri_overrides;
+        mapping(uint256 => uint8) art_versions;
+        bool emit_token_rendered;
+        mapping(address => uint256) claim_deadlines;
+        bool pixel_place_open;
+        // each token's art once holders draw on it, packed by `Image::to_packed_bytes`
+        mapping(uint256 => bytes) canvases;
+        uint256 mint_cooldown;
+        mapping(address => uint256) last_mint_at;
+        // who mint proceeds are split between, and their shares in basis points
+        address[] payees;
+        uint16[] payee_bps;
+        // hand-drawn art for 1-of-1 tokens, packed by `Image::to_packed_bytes`
+        mapping(uint256 => bytes) special_art;
+        // the seed shuffling which art each token id gets, committed to as its keccak256 hash
+        bytes32 art_seed_commitment;
+        bytes32 art_seed;
+        bool art_seed_revealed;
+        // how much of each token's mint price the contract kept, refunded when it's burned
+        mapping(uint256 => uint256) refundable;
+        // the refundable amount of each account's token burned for its claim, passed on to the phase-2 token
+        mapping(address => uint256) claim_refundable;
     }
 }
 
+/// The denominator of basis-point fractions: 10000 bps is 100%
+const BPS_DENOMINATOR: U256 = uint!(10_000_U256);
+
+/// The ERC-165 interface id of ERC-4906, which has no functions to derive it from.
+const IERC4906: u32 = 0x49064906;
+
+/// The ERC-165 interface id of ERC-2981 royalties.
+const IERC2981: u32 = 0x2a55205a;
+
+/// The address of the `ecrecover` precompile
+const ECRECOVER: Address = Address::with_last_byte(1);
+
 /// The price of a mint, measured in wei: 0.0001 eth
 const MINT_PRICE: U256 = uint!(100_000_000_000_000_U256);
 
-// Declare Solidity error types
-sol! {
-    error IncorrectMintValue(uint256 paid, uint256 expected);
-}
+/// The most NFTs that can ever be minted, counting those since burned
+const MAX_SUPPLY: U256 = uint!(10_000_U256);
 
-/// Represents the ways methods may fail.
-pub enum StylusWorkshopNftError {
-    IncorrectMintValue(IncorrectMintValue),
-    Erc712Error(Erc712Error),
-    ExternalCallError(call::Error),
-}
+/// The most NFTs `mint_batch` mints at once, keeping each call well within the block gas limit
+const MAX_BATCH: U256 = uint!(20_U256);
 
-impl Into<Vec<u8>> for StylusWorkshopNftError {
-    fn into(self) -> Vec<u8> {
-        match self {
-            Self::IncorrectMintValue(err) => err.encode(),
-            Self::Erc712Error(err) => err.into(),
-            Self::ExternalCallError(err) => err.into(),
-        }
-    }
-}
+/// How long a claim from `burn_for_claim` can be redeemed for, in seconds: 7 days
+const CLAIM_WINDOW: u64 = 7 * 24 * 60 * 60;
 
-impl From<Erc712Error> for StylusWorkshopNftError {
-    fn from(err: Erc712Error) -> Self {
-        StylusWorkshopNftError::Erc712Error(err)
-    }
+// Declare events and Solidity error types
+sol! {
+    event ProvenanceHashSet(bytes32 provenance_hash);
+    event MetadataFrozen();
+    event Staked(address indexed owner, uint256 indexed token_id);
+    event Unstaked(address indexed owner, uint256 indexed token_id);
+    event Revealed();
+    event RoyaltyUpdated(address receiver, uint256 bps);
+    event TokenRoyaltyUpdated(uint256 indexed token_id, address receiver, uint256 bps);
+    event TokenRendered(uint256 indexed token_id, bytes32 image_hash);
+    event PixelSet(uint256 indexed token_id, uint8 x, uint8 y, uint32 color);
+    event ArtSeedCommitted(bytes32 commitment);
+    event ArtSeedRevealed(bytes32 seed);
+    event MintSignerUpdated(address signer);
+    event PayeesUpdated(address[] payees, uint16[] shares);
+    event MintCooldownUpdated(uint256 cooldown);
+    event ReferralBpsUpdated(uint256 bps);
+    event OperatorBlockedUpdated(address indexed operator, bool blocked);
+    event EmitTokenRenderedUpdated(bool enabled);
+    event PixelPlaceOpenUpdated(bool open);
+
+    // ERC-4906
+    event MetadataUpdate(uint256 token_id);
+    event BatchMetadataUpdate(uint256 from_token_id, uint256 to_token_id);
+
+    error IncorrectMintValue(uint256 paid, uint256 expected);
+    error AlreadyInitialized();
+    error NotContractOwner(address account);
+    error ProvenanceAlreadySet(bytes32 provenance_hash);
+    error MetadataIsFrozen();
+    error AlreadyStaked(uint256 token_id);
+    error NotStaked(uint256 token_id);
+    error InvalidReferrer(address referrer);
+    error InvalidBps(uint256 bps);
+    error InvalidSignature();
+    error SignatureUsed(bytes32 digest);
+    error CallFailed(uint256 index, bytes data);
+    error NothingToWithdraw(address account);
+    error InvalidMetadataFormat(uint8 format);
+    error InvalidRefundRecipient(address refund_to);
+    error SoldOut(uint256 max_supply);
+    error TokenAlreadyMinted(uint256 token_id);
+    error TokenIdOutOfRange(uint256 token_id, uint256 max_supply);
+    error PermitExpired(uint256 deadline);
+    error ClaimPending(address account, uint256 deadline);
+    error NoClaim(address account);
+    error ClaimExpired(address account, uint256 deadline);
+    error PixelPlaceClosed();
+    error OutOfBounds(uint8 x, uint8 y);
+    error MintCooldown(address account, uint256 available_at);
+    error InvalidBatchSize(uint256 quantity, uint256 max_batch);
+    error InvalidSplit(uint256 payees, uint256 total_bps);
+    error InvalidPackedImage();
+    error ArtSeedAlreadyCommitted(bytes32 commitment);
+    error ArtSeedAlreadyRevealed(bytes32 seed);
+    error ArtSeedMismatch(bytes32 seed, bytes32 commitment);
+    error ArtSeedTooLate(uint256 total_minted);
+    error ArtSeedUnrevealed(bytes32 commitment);
+    error TokenRenderedEnabled();
 }
 
-impl From<call::Error> for StylusWorkshopNftError {
-    fn from(err: call::Error) -> Self {
-        StylusWorkshopNftError::ExternalCallError(err)
+solidity_error! {
+    /// Represents the ways methods may fail.
+    pub enum StylusWorkshopNftError {
+        IncorrectMintValue(IncorrectMintValue),
+        AlreadyInitialized(AlreadyInitialized),
+        NotContractOwner(NotContractOwner),
+        ProvenanceAlreadySet(ProvenanceAlreadySet),
+        MetadataIsFrozen(MetadataIsFrozen),
+        AlreadyStaked(AlreadyStaked),
+        NotStaked(NotStaked),
+        InvalidReferrer(InvalidReferrer),
+        InvalidBps(InvalidBps),
+        InvalidSignature(InvalidSignature),
+        SignatureUsed(SignatureUsed),
+        CallFailed(CallFailed),
+        NothingToWithdraw(NothingToWithdraw),
+        InvalidMetadataFormat(InvalidMetadataFormat),
+        InvalidRefundRecipient(InvalidRefundRecipient),
+        SoldOut(SoldOut),
+        TokenAlreadyMinted(TokenAlreadyMinted),
+        TokenIdOutOfRange(TokenIdOutOfRange),
+        PermitExpired(PermitExpired),
+        ClaimPending(ClaimPending),
+        NoClaim(NoClaim),
+        ClaimExpired(ClaimExpired),
+        PixelPlaceClosed(PixelPlaceClosed),
+        OutOfBounds(OutOfBounds),
+        MintCooldown(MintCooldown),
+        InvalidBatchSize(InvalidBatchSize),
+        InvalidSplit(InvalidSplit),
+        InvalidPackedImage(InvalidPackedImage),
+        ArtSeedAlreadyCommitted(ArtSeedAlreadyCommitted),
+        ArtSeedAlreadyRevealed(ArtSeedAlreadyRevealed),
+        ArtSeedMismatch(ArtSeedMismatch),
+        ArtSeedTooLate(ArtSeedTooLate),
+        ArtSeedUnrevealed(ArtSeedUnrevealed),
+        TokenRenderedEnabled(TokenRenderedEnabled),
+        #[nested]
+        Erc712Error(Erc712Error),
+        #[nested]
+        ExternalCallError(call::Error),
     }
 }
 
 type Result<T, E = StylusWorkshopNftError> = core::result::Result<T, E>;
 
+/// Names the custom error with the given selector, including those of the ERC-721 implementation.
+/// Makes reverts readable in tests and simulation tooling.
+#[cfg(any(test, feature = "std"))]
+pub fn error_name(selector: [u8; 4]) -> Option<&'static str> {
+    StylusWorkshopNftError::error_name(selector).or_else(|| erc712::error_name(selector))
+}
+
 // These methods aren't external, but are helpers used by external methods.
 impl StylusWorkshopNft {
     fn check_mint_price(&self) -> Result<()> {
@@ -109,46 +370,3801 @@ impl StylusWorkshopNft {
         }
         Ok(())
     }
+
+    /// Requires the caller's last paid mint be at least `mint_cooldown` seconds ago, then records this one
+    /// A last mint at timestamp zero counts as none, which no real block has
+    fn enforce_mint_cooldown(&mut self) -> Result<()> {
+        let cooldown = self.mint_cooldown.get();
+        if cooldown == U256::ZERO {
+            return Ok(());
+        }
+        let account = msg::sender();
+        let now = U256::from(block::timestamp());
+        let last_mint_at = self.last_mint_at.get(account);
+        // saturates so a cooldown too long to ever end is one, instead of wrapping past now
+        let available_at = last_mint_at.saturating_add(cooldown);
+        if last_mint_at != U256::ZERO && now < available_at {
+            return Err(StylusWorkshopNftError::MintCooldown(MintCooldown {
+                account,
+                available_at,
+            }));
+        }
+        self.last_mint_at.insert(account, now);
+        Ok(())
+    }
+
+    /// Requires there be supply left to mint, which every mint path but `redeem_claim` checks
+    fn require_mintable(&self) -> Result<()> {
+        if self.is_sold_out()? {
+            return Err(StylusWorkshopNftError::SoldOut(SoldOut {
+                max_supply: MAX_SUPPLY,
+            }));
+        }
+        Ok(())
+    }
+
+    /// Tells indexers to refresh the metadata of every token
+    /// Covers the whole id space, since signed mints can use ids past the number minted
+    fn all_metadata_updated(&self) -> Result<()> {
+        evm::log(BatchMetadataUpdate {
+            from_token_id: U256::ZERO,
+            to_token_id: U256::MAX,
+        });
+        Ok(())
+    }
+
+    fn get_placeholder_uri(&self) -> String {
+        String::from_utf8_lossy(&self.placeholder_uri.get_bytes()).into()
+    }
+
+    fn get_base_uri(&self) -> String {
+        String::from_utf8_lossy(&self.base_uri.get_bytes()).into()
+    }
+
+    fn get_token_uri_override(&self, token_id: U256) -> String {
+        let uri = self.token_uri_overrides.getter(token_id);
+        String::from_utf8_lossy(&uri.get_bytes()).into()
+    }
+
+    /// The version of the art a token is drawn with, which for unminted tokens is the current one
+    fn get_art_version(&self, token_id: U256) -> u8 {
+        match self.art_versions.get(token_id).to::<u8>() {
+            0 => Style::VERSION,
+            version => version,
+        }
+    }
+
+    /// The id a token's art is drawn from: its own id, shuffled by the art seed once revealed
+    /// Ids past MAX_SUPPLY, which only signed mints and redeemed claims reach, are never shuffled
+    fn get_art_id(&self, token_id: U256) -> U256 {
+        if token_id >= MAX_SUPPLY || !self.art_seed_revealed.get() {
+            return token_id;
+        }
+        let seed = self.art_seed.get();
+        let art_id = utils::shuffle_index(token_id.to(), MAX_SUPPLY.to(), seed.as_slice());
+        U256::from(art_id)
+    }
+
+    /// Records that a newly minted token is drawn with the current version of the art,
+    /// and that burning it refunds `refundable`, what the contract kept of its mint price,
+    /// announcing the hash of its PNG if the owner opted in
+    fn minted(&mut self, token_id: U256, refundable: U256) {
+        self.art_versions.insert(token_id, U8::from(Style::VERSION));
+        self.refundable.insert(token_id, refundable);
+        self.canvases.delete(token_id); // left behind if a burned token id is minted again
+        if self.emit_token_rendered.get() {
+            let image_hash = crypto::keccak(self.token_art(token_id).make_png());
+            evm::log(TokenRendered {
+                token_id,
+                image_hash: image_hash.0,
+            });
+        }
+    }
+
+    /// The token's art: what holders drew on its canvas, or else its 1-of-1 art, or else its generated art
+    fn token_art(&self, token_id: U256) -> Image<32, 32> {
+        Image::load_packed(&self.canvases.getter(token_id))
+            .or_else(|| Image::load_packed(&self.special_art.getter(token_id)))
+            .unwrap_or_else(|| {
+                let version = self.get_art_version(token_id);
+                Style::render(version, contract::address(), self.get_art_id(token_id))
+            })
+    }
+
+    fn get_metadata_format(&self) -> MetadataFormat {
+        // only valid formats are ever stored
+        MetadataFormat::from_u8(self.metadata_format.get().to()).unwrap_or_default()
+    }
+
+    /// Requires the caller own the NFT
+    fn only_token_owner(&self, token_id: U256) -> Result<Address> {
+        let owner = self.erc712.owner_of(token_id)?;
+        if msg::sender() != owner {
+            let err = Erc712Error::from(NotOwner {
+                from: msg::sender(),
+                token_id,
+                real_owner: owner,
+            });
+            return Err(err.into());
+        }
+        Ok(owner)
+    }
+
+    /// Requires the caller be the contract owner, and that metadata can still change
+    fn only_owner_unfrozen(&self) -> Result<()> {
+        self.only_owner()?;
+        if self.metadata_frozen.get() {
+            return Err(StylusWorkshopNftError::MetadataIsFrozen(
+                MetadataIsFrozen {},
+            ));
+        }
+        Ok(())
+    }
+
+    /// Requires a fraction in basis points be at most 100%
+    fn check_bps(bps: U256) -> Result<()> {
+        if bps > BPS_DENOMINATOR {
+            return Err(StylusWorkshopNftError::InvalidBps(InvalidBps { bps }));
+        }
+        Ok(())
+    }
+
+    /// The `bps` basis-point fraction of `amount`, rounded down
+    /// Divides before multiplying so it can't overflow, given `bps` passed `check_bps`
+    fn bps_of(amount: U256, bps: U256) -> U256 {
+        let whole = amount / BPS_DENOMINATOR * bps;
+        whole + amount % BPS_DENOMINATOR * bps / BPS_DENOMINATOR
+    }
+
+    fn only_owner(&self) -> Result<()> {
+        let account = msg::sender();
+        if account != self.owner.get() {
+            return Err(StylusWorkshopNftError::NotContractOwner(NotContractOwner {
+                account,
+            }));
+        }
+        Ok(())
+    }
+
+    /// Credits a burned token's refund to `account`, to collect via `withdraw_refund`
+    /// Tokens that paid nothing into the contract, like signed mints, credit nothing
+    fn credit_refund(&mut self, account: Address, token_id: U256) {
+        let refund = self.refundable.get(token_id);
+        self.refundable.delete(token_id);
+        if refund != U256::ZERO {
+            self.credit_withdrawal(account, refund);
+        }
+    }
+
+    /// Credits `amount` to `account`, to collect via `withdraw_refund`
+    fn credit_withdrawal(&mut self, account: Address, amount: U256) {
+        let mut pending = self.pending_withdrawals.setter(account);
+        let credited = pending.get() + amount;
+        pending.set(credited);
+    }
+
+    /// How much of a paid mint's `proceeds` the contract keeps to refund when the token is burned:
+    /// all of them, unless a split is set to pay them to the payees
+    fn refundable_proceeds(&self, proceeds: U256) -> U256 {
+        match self.payees.len() {
+            0 => proceeds,
+            _ => U256::ZERO,
+        }
+    }
+
+    /// Credits `proceeds` of a mint to the payees by their shares, if a split is set
+    /// The last payee also gets the rounding dust. Payees withdraw rather than being sent eth,
+    /// so a payee can't block mints or spend the minter's gas
+    fn split_proceeds(&mut self, proceeds: U256) {
+        let payees = self.payees.len();
+        let mut remaining = proceeds;
+        for index in 0..payees {
+            let payee = self.payees.get(index).unwrap();
+            let share = match index + 1 == payees {
+                true => remaining,
+                false => {
+                    let bps = self.payee_bps.get(index).unwrap().to::<u16>();
+                    Self::bps_of(proceeds, U256::from(bps))
+                }
+            };
+            remaining -= share;
+            if share != U256::ZERO {
+                self.credit_withdrawal(payee, share);
+            }
+        }
+    }
+
+    /// The EIP-712 digest of a struct with the given hash, signed for this contract
+    fn eip712_digest(struct_hash: B256) -> B256 {
+        let mut domain = crypto::keccak(
+            b"EIP712Domain(string name,string version,uint256 chainId,address verifyingContract)",
+        )
+        .to_vec();
+        domain.extend(crypto::keccak(StylusWorkshopParams::NAME));
+        domain.extend(crypto::keccak(b"1"));
+        domain.extend(U256::from(block::chainid()).to_be_bytes::<32>());
+        domain.extend(contract::address().into_word());
+
+        let mut message = b"\x19\x01".to_vec();
+        message.extend(crypto::keccak(domain));
+        message.extend(struct_hash);
+        crypto::keccak(message)
+    }
+
+    /// Recovers who signed `digest` using the `ecrecover` precompile
+    /// Accepts 65-byte signatures, with `v` either 0/1 or 27/28
+    fn recover_signer(&self, digest: B256, signature: &[u8]) -> Option<Address> {
+        if signature.len() != 65 {
+            return None;
+        }
+        let (rs, v) = signature.split_at(64);
+        let v = match v[0] {
+            0 | 1 => v[0] + 27,
+            27 | 28 => v[0],
+            _ => return None,
+        };
+        let mut calldata = digest.to_vec();
+        calldata.extend(U256::from(v).to_be_bytes::<32>());
+        calldata.extend_from_slice(rs);
+
+        // the precompile returns nothing when the signature is invalid
+        let output = call::static_call(self, ECRECOVER, &calldata).ok()?;
+        if output.len() != 32 {
+            return None;
+        }
+        let signer = Address::from_word(B256::from_slice(&output));
+        (!signer.is_zero()).then_some(signer)
+    }
 }
 
 // these methods are external to other contracts
 #[external]
 #[inherit(Erc712<StylusWorkshopParams>)]
 impl StylusWorkshopNft {
-    /// Mints an NFT, but does not call onErc712Received
-    /// Requires the caller supply MINT_VALUE
-    #[payable]
-    pub fn mint(&mut self) -> Result<()> {
-        self.check_mint_price()?;
-        self.erc712.mint(msg::sender())?;
+    /// Makes the caller the owner of the contract
+    /// Must be called once, right after deployment. This SDK has no constructors, so whoever calls it
+    /// first wins: call it in the same transaction as the deployment, as a factory contract can,
+    /// or else check `owner` afterward and redeploy if someone front-ran the call
+    pub fn init(&mut self) -> Result<()> {
+        if !self.owner.get().is_zero() {
+            return Err(StylusWorkshopNftError::AlreadyInitialized(
+                AlreadyInitialized {},
+            ));
+        }
+        self.owner.set(msg::sender());
         Ok(())
     }
 
-    /// Mints an NFT and calls onErc712Received with empty data
-    /// Requires the caller supply MINT_VALUE
-    #[payable]
-    pub fn safe_mint(&mut self) -> Result<()> {
-        self.check_mint_price()?;
-        Erc712::safe_mint(self, msg::sender(), Vec::new())?;
+    /// The owner of the contract, who may reveal the art
+    pub fn owner(&self) -> Result<Address> {
+        Ok(self.owner.get())
+    }
+
+    /// Whether tokens show their generated art rather than the placeholder
+    pub fn revealed(&self) -> Result<bool> {
+        Ok(self.revealed.get())
+    }
+
+    /// Shows the generated art for every token
+    /// Requires the caller be the contract owner and the metadata not be frozen
+    pub fn reveal(&mut self) -> Result<()> {
+        self.only_owner_unfrozen()?;
+        self.revealed.set(true);
+        evm::log(Revealed {});
+        self.all_metadata_updated()?;
         Ok(())
     }
 
-    /// Mints an NFT and calls onErc712Received with the specified data
-    /// Requires the caller supply MINT_VALUE
-    #[payable]
-    #[selector(name = "safeMint")]
-    pub fn safe_mint_with_data(&mut self, data: Bytes) -> Result<()> {
-        self.check_mint_price()?;
-        Erc712::safe_mint(self, msg::sender(), data.0)?;
+    /// The URI every token reports until the art is revealed
+    pub fn placeholder_uri(&self) -> Result<String> {
+        Ok(self.get_placeholder_uri())
+    }
+
+    /// Sets the URI every token reports until the art is revealed
+    /// Requires the caller be the contract owner and the metadata not be frozen
+    pub fn set_placeholder_uri(&mut self, uri: String) -> Result<()> {
+        self.only_owner_unfrozen()?;
+        self.placeholder_uri.set_bytes(uri);
+        if !self.revealed.get() {
+            self.all_metadata_updated()?;
+        }
         Ok(())
     }
 
-    /// Burns an NFT and returns the MINT_VALUE to the caller
-    /// Requires the caller be able to receiver eth with no calldata
-    pub fn burn(&mut self, token_id: U256) -> Result<()> {
-        // This function checks that msg::sender() owns the specified token_id
-        self.erc712.burn(msg::sender(), token_id)?;
-        stylus_sdk::call::call(Call::new_in(self).value(MINT_PRICE), msg::sender(), &[])?;
+    /// The prefix of each token's off-chain metadata URI, or empty to generate the art on-chain
+    pub fn base_uri(&self) -> Result<String> {
+        Ok(self.get_base_uri())
+    }
+
+    /// Points token metadata off-chain, at the base URI followed by the token id
+    /// Setting it to empty switches back to the on-chain art
+    /// Requires the caller be the contract owner and the metadata not be frozen
+    pub fn set_base_uri(&mut self, uri: String) -> Result<()> {
+        self.only_owner_unfrozen()?;
+        self.base_uri.set_bytes(uri);
+        if self.revealed.get() {
+            self.all_metadata_updated()?;
+        }
+        Ok(())
+    }
+
+    /// The URI overriding a token's usual metadata, or empty if it has none
+    pub fn token_uri_override(&self, token_id: U256) -> Result<String> {
+        Ok(self.get_token_uri_override(token_id))
+    }
+
+    /// Points a special token, like a prize, at its own metadata once revealed
+    /// Setting it to empty switches the token back to the collection's metadata
+    /// Requires the caller be the contract owner and the metadata not be frozen
+    pub fn set_token_uri(&mut self, token_id: U256, uri: String) -> Result<()> {
+        self.only_owner_unfrozen()?;
+        self.token_uri_overrides.setter(token_id).set_bytes(uri);
+        if self.revealed.get() {
+            evm::log(MetadataUpdate { token_id });
+        }
+        Ok(())
+    }
+
+    /// A special token's 1-of-1 art, packed by `Image::to_packed_bytes`, or empty for generated art
+    pub fn special_art(&self, token_id: U256) -> Result<Bytes> {
+        Ok(self.special_art.getter(token_id).get_bytes().into())
+    }
+
+    /// Makes a token a 1-of-1, shown with hand-drawn art in place of its generated art
+    /// The art is a 32x32 image packed by `Image::to_packed_bytes`; empty makes the token generated again
+    /// For special metadata beyond the art, see `set_token_uri`
+    /// Requires the caller be the contract owner and the metadata not be frozen
+    pub fn set_special_art(&mut self, token_id: U256, art: Bytes) -> Result<()> {
+        self.only_owner_unfrozen()?;
+        if !art.is_empty() && Image::<32, 32>::from_packed_bytes(&art).is_none() {
+            return Err(StylusWorkshopNftError::InvalidPackedImage(
+                InvalidPackedImage {},
+            ));
+        }
+        self.special_art.setter(token_id).set_bytes(art.0);
+        if self.revealed.get() {
+            evm::log(MetadataUpdate { token_id });
+        }
+        Ok(())
+    }
+
+    /// How the on-chain art is presented: 0 for JSON metadata, 1 for the bare image, 2 for SVG,
+    /// 3 for JSON metadata with a frame that changes over time
+    pub fn metadata_format(&self) -> Result<u8> {
+        Ok(self.get_metadata_format() as u8)
+    }
+
+    /// Sets how the on-chain art is presented, as listed in `metadata_format`
+    /// Requires the caller be the contract owner and the metadata not be frozen
+    pub fn set_metadata_format(&mut self, format: u8) -> Result<()> {
+        self.only_owner_unfrozen()?;
+        if MetadataFormat::from_u8(format).is_none() {
+            return Err(StylusWorkshopNftError::InvalidMetadataFormat(
+                InvalidMetadataFormat { format },
+            ));
+        }
+        self.metadata_format.set(U8::from(format));
+        if self.revealed.get() {
+            self.all_metadata_updated()?;
+        }
+        Ok(())
+    }
+
+    /// The NFT's Uniform Resource Identifier, which is the placeholder until the art is revealed
+    /// Once revealed, this is the token's override if set, then the base URI and token id if a base URI is set, or the art otherwise
+    /// The art is JSON metadata by default, or the bare image as set by `set_metadata_format`
+    pub fn token_uri(&self, token_id: U256) -> Result<String> {
+        self.erc712.owner_of(token_id)?; // require NFT exist
+        if !self.revealed.get() {
+            return Ok(self.get_placeholder_uri());
+        }
+        let uri = self.get_token_uri_override(token_id);
+        if !uri.is_empty() {
+            return Ok(uri);
+        }
+
+        let mut uri = self.get_base_uri();
+        if uri.is_empty() {
+            let format = self.get_metadata_format();
+            let version = self.get_art_version(token_id);
+            let art_id = self.get_art_id(token_id);
+            let image = self.token_art(token_id);
+            return Ok(image_token_uri::<Style, 32, 32>(
+                version, token_id, art_id, &image, format,
+            ));
+        }
+        uri.push_str(&token_id.to_string());
+        Ok(uri)
+    }
+
+    /// The length in bytes of `token_uri`, for budgeting return data before fetching on-chain art
+    /// Renders the art to measure it, so costs as much gas as `token_uri` itself
+    pub fn token_uri_length(&self, token_id: U256) -> Result<U256> {
+        Ok(U256::from(self.token_uri(token_id)?.len()))
+    }
+
+    /// Whether token metadata is locked forever
+    pub fn metadata_frozen(&self) -> Result<bool> {
+        Ok(self.metadata_frozen.get())
+    }
+
+    /// Permanently locks token metadata, in the spirit of OpenSea's `PermanentURI` event
+    /// Requires the caller be the contract owner
+    pub fn freeze_metadata(&mut self) -> Result<()> {
+        self.only_owner_unfrozen()?;
+        self.metadata_frozen.set(true);
+        evm::log(MetadataFrozen {});
+        Ok(())
+    }
+
+    /// A hash committing to the whole collection's art, for verifying a fair mint
+    pub fn provenance_hash(&self) -> Result<B256> {
+        Ok(self.provenance_hash.get())
+    }
+
+    /// Records the collection's provenance hash, which can only be done once
+    /// Requires the caller be the contract owner
+    pub fn set_provenance_hash(&mut self, provenance_hash: B256) -> Result<()> {
+        self.only_owner()?;
+        let current = self.provenance_hash.get();
+        if !current.is_zero() {
+            return Err(StylusWorkshopNftError::ProvenanceAlreadySet(
+                ProvenanceAlreadySet {
+                    provenance_hash: current.0,
+                },
+            ));
+        }
+        self.provenance_hash.set(provenance_hash);
+        evm::log(ProvenanceHashSet {
+            provenance_hash: provenance_hash.0,
+        });
+        Ok(())
+    }
+
+    /// The keccak256 hash of the seed that will shuffle the art, or zero if none is committed
+    pub fn art_seed_commitment(&self) -> Result<B256> {
+        Ok(self.art_seed_commitment.get())
+    }
+
+    /// Commits to the seed that will shuffle which art each token id gets, as its keccak256 hash
+    /// Until the seed is revealed, nobody can tell which art an unminted id will get, so rare art can't be sniped
+    /// Must come before the first mint, so the seed can't be picked knowing who minted which ids,
+    /// and while `TokenRendered` is off, since the reveal would change the art of tokens it hashed
+    /// Can only be done once; requires the caller be the contract owner
+    pub fn commit_art_seed(&mut self, commitment: B256) -> Result<()> {
+        self.only_owner()?;
+        if self.emit_token_rendered.get() {
+            return Err(StylusWorkshopNftError::TokenRenderedEnabled(
+                TokenRenderedEnabled {},
+            ));
+        }
+        let total_minted = self.erc712.total_minted()?;
+        if total_minted != U256::ZERO {
+            return Err(StylusWorkshopNftError::ArtSeedTooLate(ArtSeedTooLate {
+                total_minted,
+            }));
+        }
+        let current = self.art_seed_commitment.get();
+        if !current.is_zero() {
+            return Err(StylusWorkshopNftError::ArtSeedAlreadyCommitted(
+                ArtSeedAlreadyCommitted {
+                    commitment: current.0,
+                },
+            ));
+        }
+        self.art_seed_commitment.set(commitment);
+        evm::log(ArtSeedCommitted {
+            commitment: commitment.0,
+        });
+        Ok(())
+    }
+
+    /// The seed shuffling the art, or zero until it's revealed
+    pub fn art_seed(&self) -> Result<B256> {
+        Ok(self.art_seed.get())
+    }
+
+    /// Reveals the committed seed, from then on drawing each token id below MAX_SUPPLY with the art of its shuffled id
+    /// Can only be done once; requires the caller be the contract owner, the seed match the commitment,
+    /// and the metadata not be frozen
+    pub fn reveal_art_seed(&mut self, seed: B256) -> Result<()> {
+        self.only_owner_unfrozen()?;
+        if self.art_seed_revealed.get() {
+            return Err(StylusWorkshopNftError::ArtSeedAlreadyRevealed(
+                ArtSeedAlreadyRevealed {
+                    seed: self.art_seed.get().0,
+                },
+            ));
+        }
+        let commitment = self.art_seed_commitment.get();
+        if commitment.is_zero() || crypto::keccak(seed) != commitment {
+            return Err(StylusWorkshopNftError::ArtSeedMismatch(ArtSeedMismatch {
+                seed: seed.0,
+                commitment: commitment.0,
+            }));
+        }
+        self.art_seed.set(seed);
+        self.art_seed_revealed.set(true);
+        evm::log(ArtSeedRevealed { seed: seed.0 });
+        self.all_metadata_updated()?;
+        Ok(())
+    }
+
+    /// The id whose art the token is drawn with, which differs from its own once the art seed is revealed
+    pub fn art_id(&self, token_id: U256) -> Result<U256> {
+        Ok(self.get_art_id(token_id))
+    }
+
+    /// Whether the keccak256 hash of the token's PNG matches the one given
+    /// Checks the same PNG as `token_image` and `TokenRendered`, so anyone can use this to check it hasn't changed
+    pub fn verify_token(&self, token_id: U256, expected_png_hash: B256) -> Result<bool> {
+        self.erc712.owner_of(token_id)?; // require NFT exist
+        let png = self.token_art(token_id).make_png();
+        Ok(crypto::keccak(png) == expected_png_hash)
+    }
+
+    /// Whether mints emit `TokenRendered` with the hash of the token's PNG
+    pub fn emit_token_rendered(&self) -> Result<bool> {
+        Ok(self.emit_token_rendered.get())
+    }
+
+    /// Sets whether mints emit `TokenRendered`, letting indexers check the art without rendering it
+    /// Rendering costs each mint a lot of gas, so this is off by default
+    /// Can't be turned on while a committed art seed awaits its reveal, which would reshuffle the hashed art
+    /// Requires the caller be the contract owner
+    pub fn set_emit_token_rendered(&mut self, enabled: bool) -> Result<()> {
+        self.only_owner()?;
+        let commitment = self.art_seed_commitment.get();
+        if enabled && !commitment.is_zero() && !self.art_seed_revealed.get() {
+            return Err(StylusWorkshopNftError::ArtSeedUnrevealed(
+                ArtSeedUnrevealed {
+                    commitment: commitment.0,
+                },
+            ));
+        }
+        self.emit_token_rendered.set(enabled);
+        evm::log(EmitTokenRenderedUpdated { enabled });
+        Ok(())
+    }
+
+    /// Whether holders can draw on their tokens with `set_pixel`
+    pub fn pixel_place_open(&self) -> Result<bool> {
+        Ok(self.pixel_place_open.get())
+    }
+
+    /// Opens or closes `set_pixel` to holders
+    /// Requires the caller be the contract owner
+    pub fn set_pixel_place_open(&mut self, open: bool) -> Result<()> {
+        self.only_owner()?;
+        self.pixel_place_open.set(open);
+        evm::log(PixelPlaceOpenUpdated { open });
+        Ok(())
+    }
+
+    /// Paints a pixel of the token's art, at (`x`, `y`) from the top left, with a `0xRRGGBB` color
+    /// The first pixel set starts the token's canvas from its generated art, which `token_uri` shows from then on
+    /// Requires pixel place be open, the caller own the NFT, and the metadata not be frozen
+    pub fn set_pixel(&mut self, token_id: U256, x: u8, y: u8, color: u32) -> Result<()> {
+        if !self.pixel_place_open.get() {
+            return Err(StylusWorkshopNftError::PixelPlaceClosed(
+                PixelPlaceClosed {},
+            ));
+        }
+        self.only_token_owner(token_id)?;
+        if self.metadata_frozen.get() {
+            return Err(StylusWorkshopNftError::MetadataIsFrozen(
+                MetadataIsFrozen {},
+            ));
+        }
+        let mut image = self.token_art(token_id);
+        let Some(pixel) = image
+            .pixels
+            .get_mut(y as usize)
+            .and_then(|row| row.get_mut(x as usize))
+        else {
+            return Err(StylusWorkshopNftError::OutOfBounds(OutOfBounds { x, y }));
+        };
+        let color = utils::Color::from_hex(color);
+        *pixel = color;
+        image.store_packed(&mut self.canvases.setter(token_id));
+
+        evm::log(PixelSet {
+            token_id,
+            x,
+            y,
+            color: color.to_hex(),
+        });
+        if self.revealed.get() {
+            evm::log(MetadataUpdate { token_id });
+        }
+        Ok(())
+    }
+
+    /// The token's art as raw PNG bytes, for integrators pinning or serving it themselves
+    pub fn token_image(&self, token_id: U256) -> Result<Bytes> {
+        self.erc712.owner_of(token_id)?; // require NFT exist
+        Ok(self.token_art(token_id).make_png().into())
+    }
+
+    /// The keccak256 hash of the given tokens' PNGs, concatenated in order
+    /// Lets deployers check the on-chain renderer matches an off-chain build, minted or not
+    /// Hashes the PNGs `token_image` serves, including 1-of-1 art and what holders drew
+    pub fn render_fingerprint(&self, token_ids: Vec<U256>) -> Result<B256> {
+        let mut pngs = Vec::new();
+        for token_id in token_ids {
+            pngs.extend(self.token_art(token_id).make_png());
+        }
+        Ok(crypto::keccak(pngs))
+    }
+
+    /// The token's accent color as 0xRRGGBB, so interfaces can theme around it without decoding the art
+    pub fn token_accent_color(&self, token_id: U256) -> Result<u32> {
+        self.erc712.owner_of(token_id)?; // require NFT exist
+        let version = self.get_art_version(token_id);
+        let art_id = self.get_art_id(token_id);
+        Ok(Style::accent_color(version, contract::address(), art_id).to_hex())
+    }
+
+    /// The token's rarity score, summing how unlikely each of its traits is; rarer tokens score higher
+    pub fn rarity_score(&self, token_id: U256) -> Result<U256> {
+        self.erc712.owner_of(token_id)?; // require NFT exist
+        let version = self.get_art_version(token_id);
+        let score = Style::rarity_score(version, contract::address(), self.get_art_id(token_id));
+        Ok(U256::from(score))
+    }
+
+    /// The token's traits as (trait type, value) pairs, as listed in its JSON metadata
+    /// Lets other contracts, like games, branch on a token's traits without parsing JSON
+    pub fn traits_of(&self, token_id: U256) -> Result<Vec<(String, String)>> {
+        self.erc712.owner_of(token_id)?; // require NFT exist
+        let version = self.get_art_version(token_id);
+        let traits = Style::attributes(version, contract::address(), self.get_art_id(token_id))
+            .into_iter()
+            .map(|(trait_type, value)| (trait_type.into(), value.into()))
+            .collect();
+        Ok(traits)
+    }
+
+    /// The version of the art the token was minted with, which it keeps as the art evolves
+    pub fn art_version(&self, token_id: U256) -> Result<u8> {
+        self.erc712.owner_of(token_id)?; // require NFT exist
+        Ok(self.get_art_version(token_id))
+    }
+
+    /// Whether the NFT supports a given standard, including ERC-4906, ERC-2981, and ERC-5192.
+    pub fn supports_interface(interface: FixedBytes<4>) -> Result<bool> {
+        if matches!(u32::from_be_bytes(interface.0), IERC4906 | IERC2981) {
+            return Ok(true);
+        }
+        Ok(Erc712::<StylusWorkshopParams>::supports_interface(
+            interface,
+        )?)
+    }
+
+    /// Whether an operator is blocked from managing other accounts' NFTs
+    pub fn is_operator_blocked(&self, operator: Address) -> Result<bool> {
+        Ok(self.erc712.is_operator_blocked(operator))
+    }
+
+    /// Blocks or unblocks an operator, such as a marketplace that doesn't honor creator fees
+    /// Owners can still move their own NFTs
+    /// Requires the caller be the contract owner
+    pub fn set_operator_blocked(&mut self, operator: Address, blocked: bool) -> Result<()> {
+        self.only_owner()?;
+        self.erc712.set_operator_blocked(operator, blocked);
+        evm::log(OperatorBlockedUpdated { operator, blocked });
         Ok(())
     }
+
+    /// When the NFT was staked, or zero if it isn't
+    pub fn staked_since(&self, token_id: U256) -> Result<U256> {
+        Ok(self.staked_since.get(token_id))
+    }
+
+    /// Stakes an NFT, locking it in place until it's unstaked
+    /// Requires the caller own the NFT
+    pub fn stake(&mut self, token_id: U256) -> Result<()> {
+        let owner = self.only_token_owner(token_id)?;
+        if self.erc712.is_locked(token_id) {
+            return Err(StylusWorkshopNftError::AlreadyStaked(AlreadyStaked {
+                token_id,
+            }));
+        }
+        self.erc712.set_locked(token_id, true);
+        self.staked_since
+            .insert(token_id, U256::from(block::timestamp()));
+        evm::log(Staked { owner, token_id });
+        Ok(())
+    }
+
+    /// Unstakes an NFT, allowing it to be transferred again
+    /// Requires the caller own the NFT
+    pub fn unstake(&mut self, token_id: U256) -> Result<()> {
+        let owner = self.only_token_owner(token_id)?;
+        if !self.erc712.is_locked(token_id) {
+            return Err(StylusWorkshopNftError::NotStaked(NotStaked { token_id }));
+        }
+        self.erc712.set_locked(token_id, false);
+        self.staked_since.delete(token_id);
+        evm::log(Unstaked { owner, token_id });
+        Ok(())
+    }
+
+    /// Runs each ABI-encoded call against this contract in turn, returning their results
+    /// Lets frontends batch reads into a single `eth_call`, though calls may also write
+    /// Reverts with the index and revert data of the first call to fail, or that has no method
+    pub fn multicall(&mut self, calls: Vec<Bytes>) -> Result<Vec<Bytes>> {
+        let mut results = Vec::with_capacity(calls.len());
+        for (index, calldata) in calls.iter().enumerate() {
+            let result = match calldata.get(..4) {
+                Some(selector) => {
+                    let selector = u32::from_be_bytes(selector.try_into().unwrap());
+                    <Self as Router<Self>>::route(self, selector, &calldata[4..])
+                }
+                None => None,
+            };
+            match result {
+                Some(Ok(output)) => results.push(output.into()),
+                failed => {
+                    return Err(StylusWorkshopNftError::CallFailed(CallFailed {
+                        index: U256::from(index),
+                        data: failed.and_then(|result| result.err()).unwrap_or_default(),
+                    }))
+                }
+            }
+        }
+        Ok(results)
+    }
+
+    /// Accepts NFTs sent via `safeTransferFrom`, so that this contract can hold them
+    #[selector(name = "onERC721Received")]
+    pub fn on_erc_721_received(
+        _operator: Address,
+        _from: Address,
+        _token_id: U256,
+        _data: Bytes,
+    ) -> Result<FixedBytes<4>> {
+        Ok(ERC721_TOKEN_RECEIVER_ID.to_be_bytes().into())
+    }
+
+    /// Mints an NFT, but does not call onErc712Received
+    /// Requires the caller supply MINT_VALUE
+    #[payable]
+    pub fn mint(&mut self) -> Result<()> {
+        self.check_mint_price()?;
+        self.enforce_mint_cooldown()?;
+        self.require_mintable()?;
+        let token_id = self.erc712.mint(msg::sender())?;
+        self.minted(token_id, self.refundable_proceeds(MINT_PRICE));
+        self.split_proceeds(msg::value());
+        Ok(())
+    }
+
+    /// Mints an NFT and calls onErc712Received with empty data
+    /// Requires the caller supply MINT_VALUE
+    #[payable]
+    pub fn safe_mint(&mut self) -> Result<()> {
+        self.check_mint_price()?;
+        self.enforce_mint_cooldown()?;
+        self.require_mintable()?;
+        let token_id = Erc712::safe_mint(self, msg::sender(), Vec::new())?;
+        self.minted(token_id, self.refundable_proceeds(MINT_PRICE));
+        self.split_proceeds(msg::value());
+        Ok(())
+    }
+
+    /// Mints an NFT and calls onErc712Received with the specified data
+    /// Requires the caller supply MINT_VALUE
+    #[payable]
+    #[selector(name = "safeMint")]
+    pub fn safe_mint_with_data(&mut self, data: Bytes) -> Result<()> {
+        self.check_mint_price()?;
+        self.enforce_mint_cooldown()?;
+        self.require_mintable()?;
+        let token_id = Erc712::safe_mint(self, msg::sender(), data.0)?;
+        self.minted(token_id, self.refundable_proceeds(MINT_PRICE));
+        self.split_proceeds(msg::value());
+        Ok(())
+    }
+
+    /// Mints `quantity` NFTs to the caller, but does not call onErc712Received
+    /// Requires the caller supply MINT_VALUE for each, `quantity` be from 1 to MAX_BATCH, and the supply cover them all
+    #[payable]
+    pub fn mint_batch(&mut self, quantity: U256) -> Result<()> {
+        // an empty batch would mint nothing, yet still start the caller's cooldown
+        if quantity == U256::ZERO || quantity > MAX_BATCH {
+            return Err(StylusWorkshopNftError::InvalidBatchSize(InvalidBatchSize {
+                quantity,
+                max_batch: MAX_BATCH,
+            }));
+        }
+        let expected = MINT_PRICE * quantity;
+        if msg::value() != expected {
+            return Err(StylusWorkshopNftError::IncorrectMintValue(
+                IncorrectMintValue {
+                    paid: msg::value(),
+                    expected,
+                },
+            ));
+        }
+        self.enforce_mint_cooldown()?;
+        if quantity > self.remaining_supply()? {
+            return Err(StylusWorkshopNftError::SoldOut(SoldOut {
+                max_supply: MAX_SUPPLY,
+            }));
+        }
+        for _ in 0..quantity.to::<u32>() {
+            let token_id = self.erc712.mint(msg::sender())?;
+            self.minted(token_id, self.refundable_proceeds(MINT_PRICE));
+        }
+        self.split_proceeds(msg::value());
+        Ok(())
+    }
+
+    /// Mints a specific NFT, like a collector's lucky number, but does not call onErc712Received
+    /// Requires the caller supply MINT_VALUE, and the id be below MAX_SUPPLY and not currently minted
+    #[payable]
+    pub fn mint_specific(&mut self, token_id: U256) -> Result<()> {
+        self.check_mint_price()?;
+        self.enforce_mint_cooldown()?;
+        self.require_mintable()?;
+        if token_id >= MAX_SUPPLY {
+            return Err(StylusWorkshopNftError::TokenIdOutOfRange(
+                TokenIdOutOfRange {
+                    token_id,
+                    max_supply: MAX_SUPPLY,
+                },
+            ));
+        }
+        if self.erc712.exists(token_id) {
+            return Err(StylusWorkshopNftError::TokenAlreadyMinted(
+                TokenAlreadyMinted { token_id },
+            ));
+        }
+        self.erc712.mint_id(msg::sender(), token_id)?;
+        self.minted(token_id, self.refundable_proceeds(MINT_PRICE));
+        self.split_proceeds(msg::value());
+        Ok(())
+    }
+
+    /// Mints an NFT, but does not call onErc712Received
+    /// Credits the referrer their cut of the mint price, as set by `set_referral_bps`, to collect via `withdraw_refund`
+    /// Burning the NFT refunds the mint price less that cut
+    /// Requires the caller supply MINT_VALUE and not refer themselves
+    #[payable]
+    pub fn mint_with_referral(&mut self, referrer: Address) -> Result<()> {
+        if referrer.is_zero() || referrer == msg::sender() {
+            return Err(StylusWorkshopNftError::InvalidReferrer(InvalidReferrer {
+                referrer,
+            }));
+        }
+        self.check_mint_price()?;
+        self.enforce_mint_cooldown()?;
+        self.require_mintable()?;
+        // the referrer's cut is owed to them, so burning the token only refunds the rest
+        let cut = Self::bps_of(MINT_PRICE, self.referral_bps.get());
+        let token_id = self.erc712.mint(msg::sender())?;
+        self.minted(token_id, self.refundable_proceeds(MINT_PRICE - cut));
+
+        if cut != U256::ZERO {
+            // credited rather than sent, so a referrer that rejects eth can't block the mint
+            self.credit_withdrawal(referrer, cut);
+        }
+        self.split_proceeds(MINT_PRICE - cut);
+        Ok(())
+    }
+
+    /// How long an account must wait between paid mints, in seconds; zero means no wait
+    pub fn mint_cooldown(&self) -> Result<U256> {
+        Ok(self.mint_cooldown.get())
+    }
+
+    /// Sets how long an account must wait between paid mints, to slow down bots
+    /// Setting it to zero disables the cooldown
+    /// Requires the caller be the contract owner
+    pub fn set_mint_cooldown(&mut self, seconds: U256) -> Result<()> {
+        self.only_owner()?;
+        self.mint_cooldown.set(seconds);
+        evm::log(MintCooldownUpdated { cooldown: seconds });
+        Ok(())
+    }
+
+    /// When the account last made a paid mint while the cooldown was on, as a unix timestamp
+    pub fn last_mint_at(&self, account: Address) -> Result<U256> {
+        Ok(self.last_mint_at.get(account))
+    }
+
+    /// Who mint proceeds are split between, alongside their shares in basis points
+    pub fn payees(&self) -> Result<(Vec<Address>, Vec<u16>)> {
+        let accounts = (0..self.payees.len())
+            .filter_map(|index| self.payees.get(index))
+            .collect();
+        let shares = (0..self.payee_bps.len())
+            .filter_map(|index| self.payee_bps.get(index))
+            .map(|bps| bps.to::<u16>())
+            .collect();
+        Ok((accounts, shares))
+    }
+
+    /// Splits the proceeds of each paid mint between `accounts`, crediting each their share in basis points
+    /// to collect via `withdraw_refund`
+    /// No payees leaves the proceeds in the contract; tokens minted while a split is set refund nothing when burned
+    /// Requires the caller be the contract owner, and a share for each payee, summing to 10000
+    pub fn set_payees(&mut self, accounts: Vec<Address>, shares: Vec<u16>) -> Result<()> {
+        self.only_owner()?;
+        let total_bps: U256 = shares.iter().map(|&bps| U256::from(bps)).sum();
+        let complete = accounts.is_empty() || total_bps == BPS_DENOMINATOR;
+        if accounts.len() != shares.len() || !complete {
+            return Err(StylusWorkshopNftError::InvalidSplit(InvalidSplit {
+                payees: U256::from(accounts.len()),
+                total_bps,
+            }));
+        }
+        self.payees.erase();
+        self.payee_bps.erase();
+        for (&account, &bps) in accounts.iter().zip(&shares) {
+            self.payees.push(account);
+            self.payee_bps.push(U16::from(bps));
+        }
+        evm::log(PayeesUpdated {
+            payees: accounts,
+            shares,
+        });
+        Ok(())
+    }
+
+    /// The referrer's cut of the mint price, in basis points
+    pub fn referral_bps(&self) -> Result<U256> {
+        Ok(self.referral_bps.get())
+    }
+
+    /// Sets the referrer's cut of the mint price, in basis points
+    /// Requires the caller be the contract owner
+    pub fn set_referral_bps(&mut self, bps: U256) -> Result<()> {
+        self.only_owner()?;
+        Self::check_bps(bps)?;
+        self.referral_bps.set(bps);
+        evm::log(ReferralBpsUpdated { bps });
+        Ok(())
+    }
+
+    /// The ERC-2981 royalty owed on a sale, and who it's owed to
+    /// Tokens with their own royalty use it instead of the default
+    pub fn royalty_info(&self, token_id: U256, sale_price: U256) -> Result<(Address, U256)> {
+        let mut receiver = self.token_royalty_receivers.get(token_id);
+        let mut bps = self.token_royalty_bps.get(token_id);
+        if receiver.is_zero() {
+            receiver = self.royalty_receiver.get();
+            bps = self.royalty_bps.get();
+        }
+        Ok((receiver, Self::bps_of(sale_price, bps)))
+    }
+
+    /// Sets the royalty for tokens without their own, in basis points of the sale price
+    /// Requires the caller be the contract owner
+    pub fn set_default_royalty(&mut self, receiver: Address, bps: U256) -> Result<()> {
+        self.only_owner()?;
+        Self::check_bps(bps)?;
+        self.royalty_receiver.set(receiver);
+        self.royalty_bps.set(bps);
+        evm::log(RoyaltyUpdated { receiver, bps });
+        Ok(())
+    }
+
+    /// Gives a token its own royalty, in basis points of the sale price
+    /// Setting the receiver to zero reverts the token to the default royalty
+    /// Requires the caller be the contract owner
+    pub fn set_token_royalty(
+        &mut self,
+        token_id: U256,
+        receiver: Address,
+        bps: U256,
+    ) -> Result<()> {
+        self.only_owner()?;
+        Self::check_bps(bps)?;
+        self.token_royalty_receivers.insert(token_id, receiver);
+        self.token_royalty_bps.insert(token_id, bps);
+        evm::log(TokenRoyaltyUpdated {
+            token_id,
+            receiver,
+            bps,
+        });
+        Ok(())
+    }
+
+    /// The account whose signatures authorize lazy mints
+    pub fn mint_signer(&self) -> Result<Address> {
+        Ok(self.mint_signer.get())
+    }
+
+    /// Sets the account whose signatures authorize lazy mints
+    /// Requires the caller be the contract owner
+    pub fn set_mint_signer(&mut self, signer: Address) -> Result<()> {
+        self.only_owner()?;
+        self.mint_signer.set(signer);
+        evm::log(MintSignerUpdated { signer });
+        Ok(())
+    }
+
+    /// The EIP-712 digest the mint signer signs to let `redeemer` mint `token_id`
+    /// Signs the struct `MintAuthorization(address redeemer,uint256 tokenId)`
+    pub fn mint_authorization_digest(redeemer: Address, token_id: U256) -> Result<B256> {
+        let mut authorization =
+            crypto::keccak(b"MintAuthorization(address redeemer,uint256 tokenId)").to_vec();
+        authorization.extend(redeemer.into_word());
+        authorization.extend(token_id.to_be_bytes::<32>());
+        Ok(Self::eip712_digest(crypto::keccak(authorization)))
+    }
+
+    /// Mints `token_id` to the caller, as authorized by the mint signer's `signature`
+    /// Each authorization can only be redeemed once, even if the token is later burned
+    /// The mint is free, so burning the token refunds nothing
+    pub fn redeem_mint(&mut self, token_id: U256, signature: Bytes) -> Result<()> {
+        let digest = Self::mint_authorization_digest(msg::sender(), token_id)?;
+        if self.redeemed_mints.get(digest) {
+            return Err(StylusWorkshopNftError::SignatureUsed(SignatureUsed {
+                digest: digest.0,
+            }));
+        }
+        let signer = self.recover_signer(digest, &signature);
+        if signer.is_none() || signer != Some(self.mint_signer.get()) {
+            return Err(StylusWorkshopNftError::InvalidSignature(
+                InvalidSignature {},
+            ));
+        }
+        self.require_mintable()?;
+        self.redeemed_mints.insert(digest, true);
+        self.erc712.mint_id(msg::sender(), token_id)?;
+        self.minted(token_id, U256::ZERO); // signed mints are free, so there's nothing to refund
+        Ok(())
+    }
+
+    /// The nonce `owner` must sign their next `permit_for_all` with
+    pub fn permit_nonce(&self, owner: Address) -> Result<U256> {
+        Ok(self.permit_nonces.get(owner))
+    }
+
+    /// The EIP-712 digest `owner` signs to set `operator`'s approval for all their NFTs
+    /// Signs the struct `PermitForAll(address owner,address operator,bool approved,uint256 nonce,uint256 deadline)`
+    pub fn permit_for_all_digest(
+        owner: Address,
+        operator: Address,
+        approved: bool,
+        nonce: U256,
+        deadline: U256,
+    ) -> Result<B256> {
+        let mut permit = crypto::keccak(
+            b"PermitForAll(address owner,address operator,bool approved,uint256 nonce,uint256 deadline)",
+        )
+        .to_vec();
+        permit.extend(owner.into_word());
+        permit.extend(operator.into_word());
+        permit.extend(U256::from(approved as u8).to_be_bytes::<32>());
+        permit.extend(nonce.to_be_bytes::<32>());
+        permit.extend(deadline.to_be_bytes::<32>());
+        Ok(Self::eip712_digest(crypto::keccak(permit)))
+    }
+
+    /// Sets `operator`'s approval for all of `owner`'s NFTs, as authorized by `owner`'s `signature`
+    /// Lets anyone relay the approval, so owners can list NFTs without paying gas
+    /// Requires the deadline, a unix timestamp, not have passed
+    pub fn permit_for_all(
+        &mut self,
+        owner: Address,
+        operator: Address,
+        approved: bool,
+        deadline: U256,
+        signature: Bytes,
+    ) -> Result<()> {
+        if U256::from(block::timestamp()) > deadline {
+            return Err(StylusWorkshopNftError::PermitExpired(PermitExpired {
+                deadline,
+            }));
+        }
+        let nonce = self.permit_nonces.get(owner);
+        let digest = Self::permit_for_all_digest(owner, operator, approved, nonce, deadline)?;
+        if self.recover_signer(digest, &signature) != Some(owner) {
+            return Err(StylusWorkshopNftError::InvalidSignature(
+                InvalidSignature {},
+            ));
+        }
+        // each signature works once
+        self.permit_nonces.insert(owner, nonce + U256::from(1));
+        self.erc712
+            .set_operator_approval(owner, operator, approved)?;
+        Ok(())
+    }
+
+    /// Whether all MAX_SUPPLY NFTs have been minted, so no more can be
+    pub fn is_sold_out(&self) -> Result<bool> {
+        Ok(self.erc712.total_minted()? >= MAX_SUPPLY)
+    }
+
+    /// The number of NFTs that can still be minted before the collection sells out
+    /// Burning a token doesn't free up supply
+    pub fn remaining_supply(&self) -> Result<U256> {
+        Ok(MAX_SUPPLY.saturating_sub(self.erc712.total_minted()?))
+    }
+
+    /// Burns an NFT and credits its refund to the caller, to collect via `withdraw_refund`
+    /// The refund is what the NFT paid into the contract when minted, as given by `refundable`
+    pub fn burn(&mut self, token_id: U256) -> Result<()> {
+        self.burn_to(token_id, msg::sender())
+    }
+
+    /// Burns an NFT and credits its refund to `refund_to` instead of the caller
+    /// Requires the caller own the NFT and the refund go to a nonzero address
+    pub fn burn_to(&mut self, token_id: U256, refund_to: Address) -> Result<()> {
+        if refund_to.is_zero() {
+            return Err(StylusWorkshopNftError::InvalidRefundRecipient(
+                InvalidRefundRecipient { refund_to },
+            ));
+        }
+        // This function checks that msg::sender() owns the specified token_id
+        self.erc712.burn(msg::sender(), token_id)?;
+        self.credit_refund(refund_to, token_id);
+        Ok(())
+    }
+
+    /// Burns an NFT on its owner's behalf, crediting its refund to the owner `from`
+    /// The owner paid for the mint, so the refund is theirs even when an operator burns it
+    /// Requires the caller be the owner or approved to spend the NFT
+    pub fn burn_from(&mut self, from: Address, token_id: U256) -> Result<()> {
+        self.erc712.require_authorized_to_spend(from, token_id)?;
+        self.erc712.burn(from, token_id)?;
+        self.credit_refund(from, token_id);
+        Ok(())
+    }
+
+    /// When the account's claim on a phase-2 token expires, or zero if it has none
+    /// The claim can be redeemed up to and including this timestamp
+    pub fn claim_deadline(&self, account: Address) -> Result<U256> {
+        Ok(self.claim_deadlines.get(account))
+    }
+
+    /// Burns an NFT in exchange for a claim on a phase-2 token, redeemable for `CLAIM_WINDOW`
+    /// Unlike `burn`, the caller isn't refunded: the claim takes the refund's place,
+    /// and the phase-2 token refunds what the burned NFT would have
+    /// Requires the caller own the NFT and have no unexpired claim
+    pub fn burn_for_claim(&mut self, token_id: U256) -> Result<()> {
+        let account = msg::sender();
+        let now = U256::from(block::timestamp());
+        let deadline = self.claim_deadlines.get(account);
+        if deadline >= now {
+            return Err(StylusWorkshopNftError::ClaimPending(ClaimPending {
+                account,
+                deadline,
+            }));
+        }
+        // This function checks that msg::sender() owns the specified token_id
+        self.erc712.burn(account, token_id)?;
+        self.claim_deadlines
+            .insert(account, now + U256::from(CLAIM_WINDOW));
+        let refundable = self.refundable.get(token_id);
+        self.refundable.delete(token_id);
+        self.claim_refundable.insert(account, refundable);
+        Ok(())
+    }
+
+    /// Mints the caller a phase-2 token for their claim, using it up
+    /// Works even once sold out, since the burned NFT already took up the claim's supply
+    /// Requires the caller have a claim that hasn't expired
+    pub fn redeem_claim(&mut self) -> Result<()> {
+        let account = msg::sender();
+        let deadline = self.claim_deadlines.get(account);
+        if deadline == U256::ZERO {
+            return Err(StylusWorkshopNftError::NoClaim(NoClaim { account }));
+        }
+        if U256::from(block::timestamp()) > deadline {
+            return Err(StylusWorkshopNftError::ClaimExpired(ClaimExpired {
+                account,
+                deadline,
+            }));
+        }
+        self.claim_deadlines.delete(account);
+        let refundable = self.claim_refundable.get(account);
+        self.claim_refundable.delete(account);
+        let token_id = self.erc712.mint(account)?;
+        self.minted(token_id, refundable);
+        Ok(())
+    }
+
+    /// What burning the NFT would refund: the part of its mint price the contract kept
+    /// Zero for NFTs that were free to mint, like signed mints
+    pub fn refundable(&self, token_id: U256) -> Result<U256> {
+        self.erc712.owner_of(token_id)?; // require NFT exist
+        Ok(self.refundable.get(token_id))
+    }
+
+    /// The refunds and mint proceeds an account has yet to withdraw
+    pub fn pending_withdrawal(&self, account: Address) -> Result<U256> {
+        Ok(self.pending_withdrawals.get(account))
+    }
+
+    /// Sends the caller every refund and share of mint proceeds credited to them
+    /// Requires the caller be able to receive eth with no calldata
+    pub fn withdraw_refund(&mut self) -> Result<()> {
+        let account = msg::sender();
+        let amount = self.pending_withdrawals.get(account);
+        if amount == U256::ZERO {
+            return Err(StylusWorkshopNftError::NothingToWithdraw(
+                NothingToWithdraw { account },
+            ));
+        }
+        // clear the balance before sending, so reentrant calls find nothing left
+        self.pending_withdrawals.delete(account);
+        call::call(Call::new_in(self).value(amount), account, &[])?;
+        Ok(())
+    }
+
+    /// Burns any NFT, for moderating abusive tokens
+    /// Unlike `burn`, the holder is not refunded: the mint price stays with the contract
+    /// Requires the caller be the contract owner
+    pub fn admin_burn(&mut self, token_id: U256) -> Result<()> {
+        self.only_owner()?;
+        let holder = self.erc712.owner_of(token_id)?;
+        // unstake first, so the token is unlocked before it's gone
+        self.erc712.set_locked(token_id, false);
+        self.erc712.burn(holder, token_id)?;
+        self.staked_since.delete(token_id);
+        self.refundable.delete(token_id);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_vm::{CallRecord, TestVm, CONTRACT};
+    use alloy_sol_types::{sol_data, SolCall, SolEvent, SolStruct, SolType};
+    use base64::Engine;
+    use erc712::Transfer;
+
+    sol! {
+        function onERC721Received(address operator, address from, uint256 token_id, bytes data) external returns (bytes4);
+        function name() external returns (string);
+        function symbol() external returns (string);
+        function ownerOf(uint256 token_id) external returns (address);
+        function traitsOf(uint256 token_id) external returns ((string,string)[]);
+
+        struct MintAuthorization {
+            address redeemer;
+            uint256 tokenId;
+        }
+
+        struct PermitForAll {
+            address owner;
+            address operator;
+            bool approved;
+            uint256 nonce;
+            uint256 deadline;
+        }
+    }
+
+    const OWNER: Address = Address::new([0x01; 20]);
+    const ALICE: Address = Address::new([0xa1; 20]);
+
+    fn deploy(vm: &TestVm) -> StylusWorkshopNft {
+        let mut nft: StylusWorkshopNft = vm.contract();
+        vm.set_sender(OWNER);
+        nft.init().ok().unwrap();
+        nft
+    }
+
+    fn mint(vm: &TestVm, nft: &mut StylusWorkshopNft, to: Address) {
+        vm.set_sender(to);
+        vm.set_value(MINT_PRICE);
+        nft.mint().ok().unwrap();
+        vm.set_value(U256::ZERO);
+    }
+
+    #[test]
+    fn test_init_once() {
+        let vm = TestVm::new();
+        let mut nft = deploy(&vm);
+        assert_eq!(nft.owner().ok(), Some(OWNER));
+
+        // nobody can init a second time, not even the owner
+        for account in [ALICE, OWNER] {
+            vm.set_sender(account);
+            assert!(matches!(
+                nft.init(),
+                Err(StylusWorkshopNftError::AlreadyInitialized(_))
+            ));
+        }
+        assert_eq!(nft.owner().ok(), Some(OWNER));
+    }
+
+    #[test]
+    fn test_placeholder_before_reveal() {
+        let vm = TestVm::new();
+        let mut nft = deploy(&vm);
+        nft.set_placeholder_uri("ipfs://placeholder".into())
+            .ok()
+            .unwrap();
+        mint(&vm, &mut nft, ALICE);
+
+        assert_eq!(nft.revealed().ok(), Some(false));
+        assert_eq!(
+            nft.token_uri(U256::ZERO).ok().as_deref(),
+            Some("ipfs://placeholder")
+        );
+        assert!(matches!(
+            nft.token_uri(U256::from(1)),
+            Err(StylusWorkshopNftError::Erc712Error(
+                Erc712Error::InvalidTokenId(_)
+            ))
+        ));
+    }
+
+    #[test]
+    fn test_art_after_reveal() {
+        let vm = TestVm::new();
+        let mut nft = deploy(&vm);
+        nft.set_placeholder_uri("ipfs://placeholder".into())
+            .ok()
+            .unwrap();
+        mint(&vm, &mut nft, ALICE);
+
+        vm.set_sender(OWNER);
+        nft.reveal().ok().unwrap();
+        assert_eq!(nft.revealed().ok(), Some(true));
+
+        let uri = nft.token_uri(U256::ZERO).ok().unwrap();
+        assert_eq!(uri, StylusWorkshopParams::token_uri(U256::ZERO));
+        assert!(uri.starts_with("data:application/json;base64,"));
+        assert!(matches!(
+            nft.token_uri(U256::from(1)),
+            Err(StylusWorkshopNftError::Erc712Error(
+                Erc712Error::InvalidTokenId(_)
+            ))
+        ));
+    }
+
+    #[test]
+    fn test_reveal_requires_owner() {
+        let vm = TestVm::new();
+        let mut nft = deploy(&vm);
+
+        vm.set_sender(ALICE);
+        assert!(matches!(
+            nft.reveal(),
+            Err(StylusWorkshopNftError::NotContractOwner(_))
+        ));
+        assert!(matches!(
+            nft.set_placeholder_uri("ipfs://other".into()),
+            Err(StylusWorkshopNftError::NotContractOwner(_))
+        ));
+        assert_eq!(nft.revealed().ok(), Some(false));
+    }
+
+    #[test]
+    fn test_provenance_hash() {
+        let vm = TestVm::new();
+        let mut nft = deploy(&vm);
+        let hash = B256::repeat_byte(0x42);
+
+        vm.set_sender(ALICE);
+        assert!(matches!(
+            nft.set_provenance_hash(hash),
+            Err(StylusWorkshopNftError::NotContractOwner(_))
+        ));
+
+        vm.set_sender(OWNER);
+        nft.set_provenance_hash(hash).ok().unwrap();
+        assert_eq!(nft.provenance_hash().ok(), Some(hash));
+        assert_eq!(
+            vm.take_logs(),
+            vec![(vec![ProvenanceHashSet::SIGNATURE_HASH], hash.to_vec())]
+        );
+
+        assert!(matches!(
+            nft.set_provenance_hash(B256::repeat_byte(0x43)),
+            Err(StylusWorkshopNftError::ProvenanceAlreadySet(_))
+        ));
+        assert_eq!(nft.provenance_hash().ok(), Some(hash));
+    }
+
+    #[test]
+    fn test_custom_style() {
+        struct Solid;
+        impl ArtStyle<4, 6> for Solid {
+            fn render(_version: u8, address: Address, token_id: U256) -> art::Image<4, 6> {
+                assert_eq!(address, CONTRACT);
+                let shade = token_id.to::<u8>();
+                art::Image::new(utils::Color::new(shade, 0x40, 0x80))
+            }
+        }
+
+        let uri = style_token_uri::<Solid, 4, 6>(1, U256::from(7), MetadataFormat::Image);
+        let (prefix, encoded) = uri.split_once(',').unwrap();
+        assert_eq!(prefix, "data:image/png;base64");
+        let png = base64::engine::general_purpose::STANDARD
+            .decode(encoded)
+            .unwrap();
+        let mut reader = png::Decoder::new(std::io::Cursor::new(png))
+            .read_info()
+            .unwrap();
+        let mut pixels = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut pixels).unwrap();
+        assert_eq!((info.width, info.height), (6, 4));
+        assert_eq!(&pixels[..info.buffer_size()], [7, 0x40, 0x80].repeat(24));
+
+        // the deployment's own style is the default one
+        assert_eq!(
+            StylusWorkshopParams::token_uri(U256::from(7)),
+            style_token_uri::<DefaultStyle, 32, 32>(
+                DefaultStyle::VERSION,
+                U256::from(7),
+                MetadataFormat::Json
+            )
+        );
+    }
+
+    #[test]
+    fn test_verify_token() {
+        let vm = TestVm::new();
+        let mut nft = deploy(&vm);
+        mint(&vm, &mut nft, ALICE);
+
+        let png = art::generate_nft(CONTRACT, U256::ZERO).make_png();
+        let hash = crypto::keccak(png);
+        assert_eq!(nft.verify_token(U256::ZERO, hash).ok(), Some(true));
+        assert_eq!(
+            nft.verify_token(U256::ZERO, B256::repeat_byte(0x42)).ok(),
+            Some(false)
+        );
+        assert!(nft.verify_token(U256::from(1), hash).is_err());
+    }
+
+    #[test]
+    fn test_token_rendered() {
+        let vm = TestVm::new();
+        let mut nft = deploy(&vm);
+        let rendered = |logs: Vec<(Vec<B256>, Vec<u8>)>| {
+            logs.into_iter()
+                .filter(|(topics, _)| topics[0] == TokenRendered::SIGNATURE_HASH)
+                .collect::<Vec<_>>()
+        };
+
+        // off by default, sparing mints the cost of rendering
+        vm.take_logs();
+        mint(&vm, &mut nft, ALICE);
+        assert!(rendered(vm.take_logs()).is_empty());
+
+        vm.set_sender(ALICE);
+        assert!(nft.set_emit_token_rendered(true).is_err());
+        vm.set_sender(OWNER);
+        nft.set_emit_token_rendered(true).ok().unwrap();
+        assert_eq!(nft.emit_token_rendered().ok(), Some(true));
+
+        mint(&vm, &mut nft, ALICE);
+        let token_id = U256::from(1);
+        let image_hash = crypto::keccak(art::generate_nft(CONTRACT, token_id).make_png());
+        let event = TokenRendered {
+            token_id,
+            image_hash: image_hash.0,
+        };
+        let topics = vec![TokenRendered::SIGNATURE_HASH, token_id.into()];
+        assert_eq!(
+            rendered(vm.take_logs()),
+            vec![(topics, event.encode_data())]
+        );
+        assert_eq!(nft.verify_token(token_id, image_hash).ok(), Some(true));
+    }
+
+    #[test]
+    fn test_safe_mint() {
+        let vm = TestVm::new();
+        let mut nft = deploy(&vm);
+        vm.set_sender(ALICE);
+        vm.set_value(MINT_PRICE);
+        nft.safe_mint().ok().unwrap();
+        assert_eq!(nft.erc712.owner_of(U256::ZERO).ok(), Some(ALICE));
+
+        // a contract minting for itself is asked first, and hears the data
+        let vault = Address::new([0xee; 20]);
+        vm.mock_contract(vault, |calldata, _| {
+            let call = onERC721ReceivedCall::decode(calldata, true).unwrap();
+            assert_eq!(call.data, vec![1, 2, 3]);
+            let selector = ERC721_TOKEN_RECEIVER_ID.to_be_bytes();
+            Ok(sol_data::FixedBytes::<4>::encode_single(&selector))
+        });
+        vm.set_sender(vault);
+        nft.safe_mint_with_data(vec![1, 2, 3].into()).ok().unwrap();
+        assert_eq!(nft.erc712.owner_of(U256::from(1)).ok(), Some(vault));
+
+        // one that refuses reverts the mint
+        let refuser = Address::new([0xef; 20]);
+        vm.mock_contract(refuser, |_, _| Err(b"no nfts".to_vec()));
+        vm.set_sender(refuser);
+        assert!(vm.call(|| nft.safe_mint()).is_err());
+        let nft: StylusWorkshopNft = vm.contract(); // reload what the revert undid
+        assert!(nft.erc712.owner_of(U256::from(2)).is_err());
+        assert_eq!(nft.erc712.total_minted().ok(), Some(U256::from(2)));
+    }
+
+    #[test]
+    fn test_receive_nft() {
+        let vm = TestVm::new();
+        let mut nft = deploy(&vm);
+        mint(&vm, &mut nft, ALICE);
+
+        // another deployment of this contract, which the token is sent to
+        let vault = Address::new([0xee; 20]);
+        vm.mock_contract(vault, |calldata, _| {
+            let call = onERC721ReceivedCall::decode(calldata, true).unwrap();
+            let returned = StylusWorkshopNft::on_erc_721_received(
+                call.operator,
+                call.from,
+                call.token_id,
+                Bytes(call.data),
+            )
+            .ok()
+            .unwrap();
+            Ok(sol_data::FixedBytes::<4>::encode_single(&returned.0))
+        });
+
+        vm.set_sender(ALICE);
+        Erc712::safe_transfer_from(&mut nft, ALICE, vault, U256::ZERO)
+            .ok()
+            .unwrap();
+        assert_eq!(nft.erc712.owner_of(U256::ZERO).ok(), Some(vault));
+        assert_eq!(
+            onERC721ReceivedCall::SELECTOR,
+            ERC721_TOKEN_RECEIVER_ID.to_be_bytes()
+        );
+    }
+
+    #[test]
+    fn test_freeze_metadata() {
+        let vm = TestVm::new();
+        let mut nft = deploy(&vm);
+        nft.set_placeholder_uri("ipfs://placeholder".into())
+            .ok()
+            .unwrap();
+
+        vm.set_sender(ALICE);
+        assert!(matches!(
+            nft.freeze_metadata(),
+            Err(StylusWorkshopNftError::NotContractOwner(_))
+        ));
+
+        vm.set_sender(OWNER);
+        vm.take_logs();
+        nft.freeze_metadata().ok().unwrap();
+        assert_eq!(nft.metadata_frozen().ok(), Some(true));
+        assert_eq!(
+            vm.take_logs(),
+            vec![(vec![MetadataFrozen::SIGNATURE_HASH], vec![])]
+        );
+
+        assert!(matches!(
+            nft.reveal(),
+            Err(StylusWorkshopNftError::MetadataIsFrozen(_))
+        ));
+        assert!(matches!(
+            nft.set_placeholder_uri("ipfs://other".into()),
+            Err(StylusWorkshopNftError::MetadataIsFrozen(_))
+        ));
+        assert!(matches!(
+            nft.freeze_metadata(),
+            Err(StylusWorkshopNftError::MetadataIsFrozen(_))
+        ));
+        assert_eq!(nft.revealed().ok(), Some(false));
+        assert_eq!(
+            nft.placeholder_uri().ok().as_deref(),
+            Some("ipfs://placeholder")
+        );
+    }
+
+    #[test]
+    fn test_reveal_updates_metadata() {
+        let vm = TestVm::new();
+        let mut nft = deploy(&vm);
+        for _ in 0..3 {
+            mint(&vm, &mut nft, ALICE);
+        }
+
+        vm.set_sender(OWNER);
+        vm.take_logs();
+        nft.reveal().ok().unwrap();
+
+        let update = BatchMetadataUpdate {
+            from_token_id: U256::ZERO,
+            to_token_id: U256::MAX,
+        };
+        assert_eq!(
+            vm.take_logs(),
+            vec![
+                (vec![Revealed::SIGNATURE_HASH], vec![]),
+                (
+                    vec![BatchMetadataUpdate::SIGNATURE_HASH],
+                    update.encode_data()
+                )
+            ]
+        );
+    }
+
+    #[test]
+    fn test_supports_interface() {
+        let supports = |id: u32| {
+            StylusWorkshopNft::supports_interface(id.to_be_bytes().into())
+                .ok()
+                .unwrap()
+        };
+        // every interface the contract claims, whose ids are checked in erc712's tests
+        assert_eq!(IERC4906, 0x49064906);
+        assert!(supports(IERC4906));
+        assert_eq!(
+            IERC2981.to_be_bytes(),
+            alloy_primitives::keccak256("royaltyInfo(uint256,uint256)")[..4]
+        );
+        assert!(supports(IERC2981));
+        assert!(supports(erc712::IERC721));
+        assert!(supports(erc712::IERC165));
+        assert!(supports(erc712::IERC5192));
+        assert!(!supports(0x780e9d63)); // ERC-721's enumerable extension
+        assert!(!supports(0xffffffff));
+    }
+
+    #[test]
+    fn test_base_uri() {
+        let vm = TestVm::new();
+        let mut nft = deploy(&vm);
+        for _ in 0..12 {
+            mint(&vm, &mut nft, ALICE);
+        }
+        let id = U256::from(11);
+
+        vm.set_sender(OWNER);
+        nft.reveal().ok().unwrap();
+        assert_eq!(
+            nft.token_uri(id).ok(),
+            Some(StylusWorkshopParams::token_uri(id))
+        );
+
+        nft.set_base_uri("ipfs://collection/".into()).ok().unwrap();
+        assert_eq!(
+            nft.token_uri(id).ok().as_deref(),
+            Some("ipfs://collection/11")
+        );
+        assert!(nft.token_uri(U256::from(12)).is_err());
+
+        nft.set_base_uri(String::new()).ok().unwrap();
+        assert_eq!(
+            nft.token_uri(id).ok(),
+            Some(StylusWorkshopParams::token_uri(id))
+        );
+
+        vm.set_sender(ALICE);
+        assert!(matches!(
+            nft.set_base_uri("ipfs://other/".into()),
+            Err(StylusWorkshopNftError::NotContractOwner(_))
+        ));
+
+        vm.set_sender(OWNER);
+        nft.freeze_metadata().ok().unwrap();
+        assert!(matches!(
+            nft.set_base_uri("ipfs://other/".into()),
+            Err(StylusWorkshopNftError::MetadataIsFrozen(_))
+        ));
+    }
+
+    #[test]
+    fn test_blocked_operator() {
+        let vm = TestVm::new();
+        let mut nft = deploy(&vm);
+        let market = Address::new([0x33; 20]);
+        let bob = Address::new([0xb0; 20]);
+        mint(&vm, &mut nft, ALICE);
+        mint(&vm, &mut nft, ALICE);
+
+        vm.set_sender(ALICE);
+        nft.erc712.set_approval_for_all(market, true).ok().unwrap();
+        nft.erc712.approve(market, U256::ZERO).ok().unwrap();
+
+        vm.set_sender(ALICE);
+        assert!(matches!(
+            nft.set_operator_blocked(market, true),
+            Err(StylusWorkshopNftError::NotContractOwner(_))
+        ));
+        vm.set_sender(OWNER);
+        nft.set_operator_blocked(market, true).ok().unwrap();
+        assert_eq!(nft.is_operator_blocked(market).ok(), Some(true));
+
+        // the operator can't move or approve tokens
+        vm.set_sender(market);
+        assert!(matches!(
+            nft.erc712.transfer_from(ALICE, bob, U256::ZERO),
+            Err(Erc712Error::OperatorBlocked(_))
+        ));
+        assert!(matches!(
+            Erc712::safe_transfer_from(&mut nft, ALICE, bob, U256::from(1)),
+            Err(Erc712Error::OperatorBlocked(_))
+        ));
+        assert!(matches!(
+            nft.erc712.approve(bob, U256::from(1)),
+            Err(Erc712Error::OperatorBlocked(_))
+        ));
+
+        // nor can it be newly approved
+        vm.set_sender(bob);
+        assert!(matches!(
+            nft.erc712.set_approval_for_all(market, true),
+            Err(Erc712Error::OperatorBlocked(_))
+        ));
+
+        // but the owner still can, and can revoke the operator
+        vm.set_sender(ALICE);
+        assert!(matches!(
+            nft.erc712.approve(market, U256::from(1)),
+            Err(Erc712Error::OperatorBlocked(_))
+        ));
+        nft.erc712.set_approval_for_all(market, false).ok().unwrap();
+        nft.erc712
+            .transfer_from(ALICE, bob, U256::ZERO)
+            .ok()
+            .unwrap();
+        assert_eq!(nft.erc712.owner_of(U256::ZERO).ok(), Some(bob));
+
+        // unblocking restores the operator
+        vm.set_sender(OWNER);
+        nft.set_operator_blocked(market, false).ok().unwrap();
+        vm.set_sender(ALICE);
+        nft.erc712.approve(market, U256::from(1)).ok().unwrap();
+        vm.set_sender(market);
+        nft.erc712
+            .transfer_from(ALICE, bob, U256::from(1))
+            .ok()
+            .unwrap();
+        assert_eq!(nft.erc712.owner_of(U256::from(1)).ok(), Some(bob));
+    }
+
+    #[test]
+    fn test_staking() {
+        let vm = TestVm::new();
+        let mut nft = deploy(&vm);
+        let bob = Address::new([0xb0; 20]);
+        mint(&vm, &mut nft, ALICE);
+
+        vm.set_sender(bob);
+        assert!(nft.stake(U256::ZERO).is_err());
+
+        vm.set_sender(ALICE);
+        vm.set_timestamp(1_700_000_000);
+        vm.take_logs();
+        nft.stake(U256::ZERO).ok().unwrap();
+        assert_eq!(
+            nft.staked_since(U256::ZERO).ok(),
+            Some(U256::from(1_700_000_000))
+        );
+        let topics = vec![Staked::SIGNATURE_HASH, ALICE.into_word(), U256::ZERO.into()];
+        let locked = (vec![erc712::Locked::SIGNATURE_HASH], vec![0; 32]);
+        assert_eq!(vm.take_logs(), vec![locked, (topics, vec![])]);
+        assert_eq!(nft.erc712.locked(U256::ZERO).ok(), Some(true));
+        assert!(matches!(
+            nft.stake(U256::ZERO),
+            Err(StylusWorkshopNftError::AlreadyStaked(_))
+        ));
+
+        // staked tokens can't move
+        assert!(matches!(
+            nft.erc712.transfer_from(ALICE, bob, U256::ZERO),
+            Err(Erc712Error::TokenStaked(_))
+        ));
+
+        // nor be burned, by any path, which would leave the id staked for whoever mints it next
+        let burns = [
+            nft.burn(U256::ZERO),
+            nft.burn_to(U256::ZERO, bob),
+            nft.burn_from(ALICE, U256::ZERO),
+            nft.burn_for_claim(U256::ZERO),
+        ];
+        for result in burns {
+            assert!(matches!(
+                result,
+                Err(StylusWorkshopNftError::Erc712Error(
+                    Erc712Error::TokenStaked(_)
+                ))
+            ));
+        }
+        assert_eq!(nft.erc712.owner_of(U256::ZERO).ok(), Some(ALICE));
+        assert!(matches!(
+            Erc712::safe_transfer_from(&mut nft, ALICE, bob, U256::ZERO),
+            Err(Erc712Error::TokenStaked(_))
+        ));
+
+        // only the holder can unstake
+        vm.set_sender(bob);
+        assert!(nft.unstake(U256::ZERO).is_err());
+
+        vm.set_sender(ALICE);
+        nft.unstake(U256::ZERO).ok().unwrap();
+        assert_eq!(nft.staked_since(U256::ZERO).ok(), Some(U256::ZERO));
+        let topics = vec![
+            Unstaked::SIGNATURE_HASH,
+            ALICE.into_word(),
+            U256::ZERO.into(),
+        ];
+        let unlocked = (vec![erc712::Unlocked::SIGNATURE_HASH], vec![0; 32]);
+        assert_eq!(vm.take_logs(), vec![unlocked, (topics, vec![])]);
+        assert_eq!(nft.erc712.locked(U256::ZERO).ok(), Some(false));
+        assert!(matches!(
+            nft.unstake(U256::ZERO),
+            Err(StylusWorkshopNftError::NotStaked(_))
+        ));
+
+        nft.erc712
+            .transfer_from(ALICE, bob, U256::ZERO)
+            .ok()
+            .unwrap();
+        assert_eq!(nft.erc712.owner_of(U256::ZERO).ok(), Some(bob));
+    }
+
+    #[test]
+    fn test_admin_burn() {
+        let vm = TestVm::new();
+        let mut nft = deploy(&vm);
+        mint(&vm, &mut nft, ALICE);
+        mint(&vm, &mut nft, ALICE);
+
+        vm.set_sender(ALICE);
+        nft.stake(U256::from(1)).ok().unwrap();
+        assert!(matches!(
+            nft.admin_burn(U256::ZERO),
+            Err(StylusWorkshopNftError::NotContractOwner(_))
+        ));
+
+        vm.set_sender(OWNER);
+        for id in [U256::ZERO, U256::from(1)] {
+            vm.take_logs();
+            nft.admin_burn(id).ok().unwrap();
+            assert!(nft.erc712.owner_of(id).is_err());
+
+            // only the transfer to zero is logged, after unlocking a staked token, and no refund is sent
+            let topics = vec![
+                Transfer::SIGNATURE_HASH,
+                ALICE.into_word(),
+                Address::ZERO.into_word(),
+                id.into(),
+            ];
+            let mut expected = vec![(topics, vec![])];
+            if id == U256::from(1) {
+                let unlocked = (vec![erc712::Unlocked::SIGNATURE_HASH], id.to_be_bytes_vec());
+                expected.insert(0, unlocked);
+            }
+            assert_eq!(vm.take_logs(), expected);
+        }
+        assert_eq!(nft.erc712.balance_of(ALICE).ok(), Some(U256::ZERO));
+        assert_eq!(nft.staked_since(U256::from(1)).ok(), Some(U256::ZERO));
+        assert!(nft.admin_burn(U256::ZERO).is_err());
+    }
+
+    #[test]
+    fn test_mint_with_referral() {
+        let vm = TestVm::new();
+        let mut nft = deploy(&vm);
+        // a contract that rejects plain eth transfers
+        let referrer = Address::new([0x44; 20]);
+        vm.mock_contract(referrer, |_, _| Err(b"no eth".to_vec()));
+
+        vm.set_sender(ALICE);
+        assert!(matches!(
+            nft.set_referral_bps(U256::from(1_000)),
+            Err(StylusWorkshopNftError::NotContractOwner(_))
+        ));
+        vm.set_sender(OWNER);
+        assert!(matches!(
+            nft.set_referral_bps(U256::from(10_001)),
+            Err(StylusWorkshopNftError::InvalidBps(_))
+        ));
+        nft.set_referral_bps(U256::from(1_000)).ok().unwrap();
+
+        vm.set_sender(ALICE);
+        vm.set_value(MINT_PRICE);
+        nft.mint_with_referral(referrer).ok().unwrap();
+        assert_eq!(nft.erc712.owner_of(U256::ZERO).ok(), Some(ALICE));
+        let cut = MINT_PRICE / U256::from(10);
+        assert!(vm.take_calls().is_empty());
+        assert_eq!(nft.pending_withdrawal(referrer).ok(), Some(cut));
+
+        for referrer in [ALICE, Address::ZERO] {
+            assert!(matches!(
+                nft.mint_with_referral(referrer),
+                Err(StylusWorkshopNftError::InvalidReferrer(_))
+            ));
+        }
+        assert!(vm.take_calls().is_empty());
+
+        vm.set_value(U256::ZERO);
+        assert!(matches!(
+            nft.mint_with_referral(referrer),
+            Err(StylusWorkshopNftError::IncorrectMintValue(_))
+        ));
+
+        // burning refunds only what the contract kept, even when the referrer takes it all
+        let kept = MINT_PRICE - MINT_PRICE / U256::from(10);
+        assert_eq!(nft.refundable(U256::ZERO).ok(), Some(kept));
+        nft.burn(U256::ZERO).ok().unwrap();
+        assert_eq!(nft.pending_withdrawal(ALICE).ok(), Some(kept));
+
+        vm.set_sender(OWNER);
+        nft.set_referral_bps(BPS_DENOMINATOR).ok().unwrap();
+        vm.set_sender(ALICE);
+        vm.set_value(MINT_PRICE);
+        nft.mint_with_referral(referrer).ok().unwrap();
+        vm.set_value(U256::ZERO);
+        nft.burn(U256::from(1)).ok().unwrap();
+        assert_eq!(nft.pending_withdrawal(ALICE).ok(), Some(kept));
+        assert_eq!(
+            nft.pending_withdrawal(referrer).ok(),
+            Some(cut + MINT_PRICE)
+        );
+    }
+
+    #[test]
+    fn test_token_royalty() {
+        let vm = TestVm::new();
+        let mut nft = deploy(&vm);
+        let artist = Address::new([0x55; 20]);
+        let collaborator = Address::new([0x66; 20]);
+        let price = U256::from(1_000_000);
+
+        nft.set_default_royalty(artist, U256::from(500))
+            .ok()
+            .unwrap();
+        nft.set_token_royalty(U256::from(7), collaborator, U256::from(1_250))
+            .ok()
+            .unwrap();
+
+        assert_eq!(
+            nft.royalty_info(U256::ZERO, price).ok(),
+            Some((artist, U256::from(50_000)))
+        );
+        assert_eq!(
+            nft.royalty_info(U256::from(7), price).ok(),
+            Some((collaborator, U256::from(125_000)))
+        );
+
+        // prices too big to multiply by the rate still get the exact royalty
+        assert_eq!(
+            nft.royalty_info(U256::ZERO, U256::MAX).ok(),
+            Some((artist, U256::MAX / U256::from(20)))
+        );
+        nft.set_default_royalty(artist, BPS_DENOMINATOR)
+            .ok()
+            .unwrap();
+        assert_eq!(
+            nft.royalty_info(U256::ZERO, U256::MAX).ok(),
+            Some((artist, U256::MAX))
+        );
+        nft.set_default_royalty(artist, U256::from(500))
+            .ok()
+            .unwrap();
+
+        // clearing the override falls back to the default
+        nft.set_token_royalty(U256::from(7), Address::ZERO, U256::ZERO)
+            .ok()
+            .unwrap();
+        assert_eq!(
+            nft.royalty_info(U256::from(7), price).ok(),
+            Some((artist, U256::from(50_000)))
+        );
+
+        assert!(matches!(
+            nft.set_token_royalty(U256::ZERO, collaborator, U256::from(10_001)),
+            Err(StylusWorkshopNftError::InvalidBps(_))
+        ));
+        vm.set_sender(ALICE);
+        assert!(matches!(
+            nft.set_token_royalty(U256::ZERO, ALICE, U256::from(100)),
+            Err(StylusWorkshopNftError::NotContractOwner(_))
+        ));
+        assert!(matches!(
+            nft.set_default_royalty(ALICE, U256::from(100)),
+            Err(StylusWorkshopNftError::NotContractOwner(_))
+        ));
+    }
+
+    /// The contract's EIP-712 domain as alloy builds it, to check the digests against
+    fn eip712_domain() -> alloy_sol_types::Eip712Domain {
+        alloy_sol_types::eip712_domain! {
+            name: StylusWorkshopParams::NAME,
+            version: "1",
+            chain_id: test_vm::CHAIN_ID,
+            verifying_contract: CONTRACT,
+        }
+    }
+
+    #[test]
+    fn test_mint_authorization_digest_vector() {
+        let _vm = TestVm::new();
+        let token_id = U256::from(500);
+        let digest = StylusWorkshopNft::mint_authorization_digest(ALICE, token_id)
+            .ok()
+            .unwrap();
+
+        // matches the digest alloy's own EIP-712 implementation gives for the same struct and domain
+        let authorization = MintAuthorization {
+            redeemer: ALICE,
+            tokenId: token_id,
+        };
+        assert_eq!(digest, authorization.eip712_signing_hash(&eip712_domain()));
+        let expected =
+            hex_literal::hex!("4af9140f4c67feb36e3e7a78d23de4d514015748c3ed81691613bcc26bdf6f31");
+        assert_eq!(digest, B256::new(expected));
+    }
+
+    #[test]
+    fn test_redeem_mint() {
+        let vm = TestVm::new();
+        vm.mock_ecrecover();
+        let mut nft = deploy(&vm);
+        let signer = Address::new([0x5e; 20]);
+        nft.set_mint_signer(signer).ok().unwrap();
+        assert_eq!(nft.mint_signer().ok(), Some(signer));
+
+        let token_id = U256::from(500);
+        let digest = StylusWorkshopNft::mint_authorization_digest(ALICE, token_id)
+            .ok()
+            .unwrap();
+        let signature = test_vm::sign(signer, digest);
+        for (redeemer, id) in [(OWNER, token_id), (ALICE, U256::from(501))] {
+            let other = StylusWorkshopNft::mint_authorization_digest(redeemer, id);
+            assert_ne!(other.ok(), Some(digest));
+        }
+
+        // only the redeemer can use the authorization
+        vm.set_sender(OWNER);
+        assert!(matches!(
+            nft.redeem_mint(token_id, signature.clone().into()),
+            Err(StylusWorkshopNftError::InvalidSignature(_))
+        ));
+
+        vm.set_sender(ALICE);
+        let forged = test_vm::sign(ALICE, digest);
+        assert!(matches!(
+            nft.redeem_mint(token_id, forged.into()),
+            Err(StylusWorkshopNftError::InvalidSignature(_))
+        ));
+        assert!(matches!(
+            nft.redeem_mint(token_id, signature[..64].to_vec().into()),
+            Err(StylusWorkshopNftError::InvalidSignature(_))
+        ));
+
+        nft.redeem_mint(token_id, signature.clone().into())
+            .ok()
+            .unwrap();
+        assert_eq!(nft.erc712.owner_of(token_id).ok(), Some(ALICE));
+
+        // authorizations can't be replayed, even once the token is burned
+        assert!(matches!(
+            nft.redeem_mint(token_id, signature.clone().into()),
+            Err(StylusWorkshopNftError::SignatureUsed(_))
+        ));
+
+        // the mint was free, so burning it credits nothing
+        assert_eq!(nft.refundable(token_id).ok(), Some(U256::ZERO));
+        nft.burn(token_id).ok().unwrap();
+        assert_eq!(nft.pending_withdrawal(ALICE).ok(), Some(U256::ZERO));
+        assert!(matches!(
+            nft.redeem_mint(token_id, signature.into()),
+            Err(StylusWorkshopNftError::SignatureUsed(_))
+        ));
+
+        // setting the signer is owner-only
+        assert!(matches!(
+            nft.set_mint_signer(ALICE),
+            Err(StylusWorkshopNftError::NotContractOwner(_))
+        ));
+    }
+
+    #[test]
+    fn test_multicall() {
+        let vm = TestVm::new();
+        let mut nft = deploy(&vm);
+        mint(&vm, &mut nft, ALICE);
+
+        let calls = vec![
+            nameCall {}.encode().into(),
+            symbolCall {}.encode().into(),
+            ownerOfCall {
+                token_id: U256::ZERO,
+            }
+            .encode()
+            .into(),
+        ];
+        let results = nft.multicall(calls).ok().unwrap();
+        assert_eq!(results.len(), 3);
+        let name = nameCall::decode_returns(&results[0], true).unwrap()._0;
+        let symbol = symbolCall::decode_returns(&results[1], true).unwrap()._0;
+        let owner = ownerOfCall::decode_returns(&results[2], true).unwrap()._0;
+        assert_eq!(name, StylusWorkshopParams::NAME);
+        assert_eq!(symbol, StylusWorkshopParams::SYMBOL);
+        assert_eq!(owner, ALICE);
+
+        // the first failure aborts the batch, reporting the call's revert data
+        let missing = ownerOfCall {
+            token_id: U256::from(9),
+        }
+        .encode();
+        let calls = vec![nameCall {}.encode().into(), missing.into()];
+        match nft.multicall(calls) {
+            Err(StylusWorkshopNftError::CallFailed(err)) => {
+                assert_eq!(err.index, U256::from(1));
+                assert!(!err.data.is_empty());
+            }
+            _ => panic!("expected the second call to fail"),
+        }
+        for unknown in [vec![0xde, 0xad, 0xbe, 0xef], vec![0x12]] {
+            let calls = vec![unknown.into()];
+            match nft.multicall(calls) {
+                Err(StylusWorkshopNftError::CallFailed(err)) => assert!(err.data.is_empty()),
+                _ => panic!("expected an unknown method to fail"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_withdraw_refund() {
+        let vm = TestVm::new();
+        let mut nft = deploy(&vm);
+        // a contract that rejects plain eth transfers, and so couldn't be refunded directly
+        let vault = Address::new([0x7a; 20]);
+        vm.mock_contract(vault, |_, value| match value == U256::ZERO {
+            true => Ok(Vec::new()),
+            false => Err(b"no eth".to_vec()),
+        });
+        mint(&vm, &mut nft, ALICE);
+        mint(&vm, &mut nft, ALICE);
+        mint(&vm, &mut nft, vault);
+
+        // burning only credits the refund
+        vm.set_sender(ALICE);
+        vm.take_calls();
+        nft.burn(U256::ZERO).ok().unwrap();
+        nft.burn(U256::from(1)).ok().unwrap();
+        assert!(vm.take_calls().is_empty());
+        let refund = MINT_PRICE * U256::from(2);
+        assert_eq!(nft.pending_withdrawal(ALICE).ok(), Some(refund));
+
+        nft.withdraw_refund().ok().unwrap();
+        let calls = vec![CallRecord {
+            to: ALICE,
+            calldata: vec![],
+            value: refund,
+        }];
+        assert_eq!(vm.take_calls(), calls);
+        assert_eq!(nft.pending_withdrawal(ALICE).ok(), Some(U256::ZERO));
+        assert!(matches!(
+            nft.withdraw_refund(),
+            Err(StylusWorkshopNftError::NothingToWithdraw(_))
+        ));
+
+        // the vault can still burn, even though it can't take the refund
+        vm.set_sender(vault);
+        nft.burn(U256::from(2)).ok().unwrap();
+        assert!(nft.erc712.owner_of(U256::from(2)).is_err());
+        assert_eq!(nft.pending_withdrawal(vault).ok(), Some(MINT_PRICE));
+        assert!(matches!(
+            nft.withdraw_refund(),
+            Err(StylusWorkshopNftError::ExternalCallError(_))
+        ));
+    }
+
+    /// Splits a base64 data URI into its MIME type and decoded contents.
+    fn decode_data_uri(uri: &str) -> (&str, Vec<u8>) {
+        let (prefix, encoded) = uri.split_once(";base64,").unwrap();
+        let mime_type = prefix.strip_prefix("data:").unwrap();
+        let data = base64::engine::general_purpose::STANDARD.decode(encoded);
+        (mime_type, data.unwrap())
+    }
+
+    #[test]
+    fn test_metadata_format() {
+        let vm = TestVm::new();
+        let mut nft = deploy(&vm);
+        mint(&vm, &mut nft, ALICE);
+        vm.set_sender(OWNER);
+        nft.reveal().ok().unwrap();
+        let png = art::generate_nft(CONTRACT, U256::ZERO).make_png();
+
+        // JSON wrapping the PNG by default
+        assert_eq!(nft.metadata_format().ok(), Some(0));
+        let uri = nft.token_uri(U256::ZERO).ok().unwrap();
+        let (mime_type, json) = decode_data_uri(&uri);
+        assert_eq!(mime_type, "application/json");
+        let json = String::from_utf8(json).unwrap();
+        assert!(json.starts_with(r#"{"name":"Stylus Workshop NFT #0","image":"data:image/png"#));
+        let image = json.split(r#""image":""#).nth(1).unwrap();
+        let image = image.split('"').next().unwrap();
+        assert_eq!(decode_data_uri(image), ("image/png", png.clone()));
+        let traits = art::nft_traits(CONTRACT, U256::ZERO);
+        let background = format!(
+            r#"{{"trait_type":"Background","value":"{}"}}"#,
+            traits.background.name()
+        );
+        assert!(json.contains(&background));
+        assert!(json.ends_with("}]}"));
+
+        vm.take_logs();
+        nft.set_metadata_format(1).ok().unwrap();
+        assert_eq!(vm.take_logs().len(), 1);
+        let uri = nft.token_uri(U256::ZERO).ok().unwrap();
+        assert_eq!(decode_data_uri(&uri), ("image/png", png));
+
+        nft.set_metadata_format(2).ok().unwrap();
+        let uri = nft.token_uri(U256::ZERO).ok().unwrap();
+        let (mime_type, svg) = decode_data_uri(&uri);
+        assert_eq!(mime_type, "image/svg+xml");
+        assert!(String::from_utf8(svg).unwrap().starts_with("<svg"));
+
+        assert!(matches!(
+            nft.set_metadata_format(4),
+            Err(StylusWorkshopNftError::InvalidMetadataFormat(_))
+        ));
+        assert_eq!(nft.metadata_format().ok(), Some(2));
+        nft.freeze_metadata().ok().unwrap();
+        assert!(matches!(
+            nft.set_metadata_format(0),
+            Err(StylusWorkshopNftError::MetadataIsFrozen(_))
+        ));
+        vm.set_sender(ALICE);
+        assert!(matches!(
+            nft.set_metadata_format(0),
+            Err(StylusWorkshopNftError::NotContractOwner(_))
+        ));
+    }
+
+    #[test]
+    fn test_burn_from() {
+        let vm = TestVm::new();
+        let mut nft = deploy(&vm);
+        let operator = Address::new([0x0e; 20]);
+        for _ in 0..3 {
+            mint(&vm, &mut nft, ALICE);
+        }
+
+        vm.set_sender(operator);
+        assert!(matches!(
+            nft.burn_from(ALICE, U256::ZERO),
+            Err(StylusWorkshopNftError::Erc712Error(
+                Erc712Error::NotApproved(_)
+            ))
+        ));
+
+        vm.set_sender(ALICE);
+        nft.erc712.approve(operator, U256::ZERO).ok().unwrap();
+        vm.set_sender(operator);
+        nft.burn_from(ALICE, U256::ZERO).ok().unwrap();
+        assert!(nft.erc712.owner_of(U256::ZERO).is_err());
+        // the refund goes to the owner, not the operator
+        assert_eq!(nft.pending_withdrawal(ALICE).ok(), Some(MINT_PRICE));
+        assert_eq!(nft.pending_withdrawal(operator).ok(), Some(U256::ZERO));
+
+        vm.set_sender(ALICE);
+        nft.erc712
+            .set_approval_for_all(operator, true)
+            .ok()
+            .unwrap();
+        vm.set_sender(operator);
+        nft.burn_from(ALICE, U256::from(1)).ok().unwrap();
+        assert!(matches!(
+            nft.burn_from(OWNER, U256::from(2)),
+            Err(StylusWorkshopNftError::Erc712Error(Erc712Error::NotOwner(
+                _
+            )))
+        ));
+
+        // owners can burn their own tokens this way too
+        vm.set_sender(ALICE);
+        nft.burn_from(ALICE, U256::from(2)).ok().unwrap();
+        let refund = MINT_PRICE * U256::from(3);
+        assert_eq!(nft.pending_withdrawal(ALICE).ok(), Some(refund));
+    }
+
+    #[test]
+    fn test_burn_to() {
+        let vm = TestVm::new();
+        let mut nft = deploy(&vm);
+        let seller = Address::new([0x5e; 20]);
+        mint(&vm, &mut nft, ALICE);
+        mint(&vm, &mut nft, ALICE);
+
+        vm.set_sender(ALICE);
+        assert!(matches!(
+            nft.burn_to(U256::ZERO, Address::ZERO),
+            Err(StylusWorkshopNftError::InvalidRefundRecipient(_))
+        ));
+        nft.burn_to(U256::ZERO, seller).ok().unwrap();
+        assert!(nft.erc712.owner_of(U256::ZERO).is_err());
+        assert_eq!(nft.pending_withdrawal(seller).ok(), Some(MINT_PRICE));
+        assert_eq!(nft.pending_withdrawal(ALICE).ok(), Some(U256::ZERO));
+
+        // only the owner can burn, wherever the refund goes
+        vm.set_sender(seller);
+        assert!(matches!(
+            nft.burn_to(U256::from(1), seller),
+            Err(StylusWorkshopNftError::Erc712Error(Erc712Error::NotOwner(
+                _
+            )))
+        ));
+
+        vm.set_sender(ALICE);
+        nft.burn(U256::from(1)).ok().unwrap();
+        assert_eq!(nft.pending_withdrawal(ALICE).ok(), Some(MINT_PRICE));
+    }
+
+    #[test]
+    fn test_config_events() {
+        let vm = TestVm::new();
+        let mut nft = deploy(&vm);
+        let artist = Address::new([0xa7; 20]);
+
+        vm.take_logs();
+        nft.reveal().ok().unwrap();
+        assert_eq!(vm.take_logs()[0], (vec![Revealed::SIGNATURE_HASH], vec![]));
+
+        nft.set_default_royalty(artist, U256::from(500))
+            .ok()
+            .unwrap();
+        let event = RoyaltyUpdated {
+            receiver: artist,
+            bps: U256::from(500),
+        };
+        let log = (vec![RoyaltyUpdated::SIGNATURE_HASH], event.encode_data());
+        assert_eq!(vm.take_logs(), vec![log]);
+
+        nft.set_token_royalty(U256::from(7), ALICE, U256::from(250))
+            .ok()
+            .unwrap();
+        let event = TokenRoyaltyUpdated {
+            token_id: U256::from(7),
+            receiver: ALICE,
+            bps: U256::from(250),
+        };
+        let topics = vec![TokenRoyaltyUpdated::SIGNATURE_HASH, U256::from(7).into()];
+        assert_eq!(vm.take_logs(), vec![(topics, event.encode_data())]);
+
+        nft.set_mint_signer(artist).ok().unwrap();
+        let event = MintSignerUpdated { signer: artist };
+        let log = (vec![MintSignerUpdated::SIGNATURE_HASH], event.encode_data());
+        assert_eq!(vm.take_logs(), vec![log]);
+
+        nft.set_payees(vec![artist, ALICE], vec![9_000, 1_000])
+            .ok()
+            .unwrap();
+        let event = PayeesUpdated {
+            payees: vec![artist, ALICE],
+            shares: vec![9_000, 1_000],
+        };
+        let log = (vec![PayeesUpdated::SIGNATURE_HASH], event.encode_data());
+        assert_eq!(vm.take_logs(), vec![log]);
+
+        nft.set_mint_cooldown(U256::from(60)).ok().unwrap();
+        let event = MintCooldownUpdated {
+            cooldown: U256::from(60),
+        };
+        let log = (
+            vec![MintCooldownUpdated::SIGNATURE_HASH],
+            event.encode_data(),
+        );
+        assert_eq!(vm.take_logs(), vec![log]);
+
+        nft.set_referral_bps(U256::from(250)).ok().unwrap();
+        let event = ReferralBpsUpdated {
+            bps: U256::from(250),
+        };
+        let log = (
+            vec![ReferralBpsUpdated::SIGNATURE_HASH],
+            event.encode_data(),
+        );
+        assert_eq!(vm.take_logs(), vec![log]);
+
+        nft.set_operator_blocked(artist, true).ok().unwrap();
+        let event = OperatorBlockedUpdated {
+            operator: artist,
+            blocked: true,
+        };
+        let topics = vec![OperatorBlockedUpdated::SIGNATURE_HASH, artist.into_word()];
+        assert_eq!(vm.take_logs(), vec![(topics, event.encode_data())]);
+
+        nft.set_emit_token_rendered(true).ok().unwrap();
+        let event = EmitTokenRenderedUpdated { enabled: true };
+        let log = (
+            vec![EmitTokenRenderedUpdated::SIGNATURE_HASH],
+            event.encode_data(),
+        );
+        assert_eq!(vm.take_logs(), vec![log]);
+
+        nft.set_pixel_place_open(true).ok().unwrap();
+        let event = PixelPlaceOpenUpdated { open: true };
+        let log = (
+            vec![PixelPlaceOpenUpdated::SIGNATURE_HASH],
+            event.encode_data(),
+        );
+        assert_eq!(vm.take_logs(), vec![log]);
+
+        // failed updates log nothing
+        assert!(nft.set_default_royalty(artist, U256::from(10_001)).is_err());
+        assert!(nft.set_referral_bps(U256::from(10_001)).is_err());
+        assert!(nft.set_payees(vec![artist], vec![1]).is_err());
+        assert!(vm.take_logs().is_empty());
+    }
+
+    #[test]
+    fn test_remaining_supply() {
+        let vm = TestVm::new();
+        let mut nft = deploy(&vm);
+        assert_eq!(nft.remaining_supply().ok(), Some(MAX_SUPPLY));
+
+        mint(&vm, &mut nft, ALICE);
+        let one_less = MAX_SUPPLY - U256::from(1);
+        assert_eq!(nft.remaining_supply().ok(), Some(one_less));
+
+        // burning doesn't free up supply
+        vm.set_sender(ALICE);
+        nft.burn(U256::ZERO).ok().unwrap();
+        assert_eq!(nft.remaining_supply().ok(), Some(one_less));
+
+        for _ in 1..MAX_SUPPLY.to::<u32>() {
+            mint(&vm, &mut nft, ALICE);
+        }
+        assert_eq!(nft.remaining_supply().ok(), Some(U256::ZERO));
+
+        vm.set_value(MINT_PRICE);
+        let err: Vec<u8> = nft.mint().err().unwrap().into();
+        let max_supply = MAX_SUPPLY;
+        assert_eq!(err, SoldOut { max_supply }.encode());
+        assert_eq!(nft.remaining_supply().ok(), Some(U256::ZERO));
+
+        // claims still redeem, taking the place of the NFT burned for them
+        vm.set_value(U256::ZERO);
+        vm.set_timestamp(1_000);
+        nft.burn_for_claim(U256::from(1)).ok().unwrap();
+        nft.redeem_claim().ok().unwrap();
+        assert_eq!(nft.erc712.owner_of(MAX_SUPPLY).ok(), Some(ALICE));
+        assert_eq!(nft.erc712.total_supply().ok(), Some(one_less));
+    }
+
+    #[test]
+    fn test_error_name() {
+        let name = error_name(IncorrectMintValue::SELECTOR);
+        assert_eq!(name, Some("IncorrectMintValue"));
+        assert_eq!(error_name(SoldOut::SELECTOR), Some("SoldOut"));
+        assert_eq!(error_name(NotOwner::SELECTOR), Some("NotOwner"));
+        assert_eq!(error_name([0; 4]), None);
+
+        // reverts can be named from their encoding
+        let vm = TestVm::new();
+        let mut nft = deploy(&vm);
+        vm.set_sender(ALICE);
+        let err: Vec<u8> = nft.mint().err().unwrap().into();
+        let selector = err[..4].try_into().unwrap();
+        assert_eq!(error_name(selector), Some("IncorrectMintValue"));
+    }
+
+    #[test]
+    fn test_error_encoding() {
+        // the selector, then each argument as a word
+        let paid = U256::from(1);
+        let err = StylusWorkshopNftError::from(IncorrectMintValue {
+            paid,
+            expected: MINT_PRICE,
+        });
+        let selector = &alloy_primitives::keccak256("IncorrectMintValue(uint256,uint256)")[..4];
+        let words: [B256; 2] = [paid.into(), MINT_PRICE.into()];
+        let expected = [selector, &words.concat()].concat();
+        assert_eq!(Vec::<u8>::from(err), expected);
+
+        let account = ALICE;
+        let bps = U256::from(10_001);
+        let digest = [0x5e; 32];
+        let encodings: Vec<(StylusWorkshopNftError, Vec<u8>)> = vec![
+            (AlreadyInitialized {}.into(), AlreadyInitialized {}.encode()),
+            (
+                NotContractOwner { account }.into(),
+                NotContractOwner { account }.encode(),
+            ),
+            (
+                ProvenanceAlreadySet {
+                    provenance_hash: digest,
+                }
+                .into(),
+                ProvenanceAlreadySet {
+                    provenance_hash: digest,
+                }
+                .encode(),
+            ),
+            (MetadataIsFrozen {}.into(), MetadataIsFrozen {}.encode()),
+            (
+                AlreadyStaked { token_id: paid }.into(),
+                AlreadyStaked { token_id: paid }.encode(),
+            ),
+            (
+                NotStaked { token_id: paid }.into(),
+                NotStaked { token_id: paid }.encode(),
+            ),
+            (
+                InvalidReferrer { referrer: account }.into(),
+                InvalidReferrer { referrer: account }.encode(),
+            ),
+            (InvalidBps { bps }.into(), InvalidBps { bps }.encode()),
+            (InvalidSignature {}.into(), InvalidSignature {}.encode()),
+            (
+                SignatureUsed { digest }.into(),
+                SignatureUsed { digest }.encode(),
+            ),
+            (
+                CallFailed {
+                    index: paid,
+                    data: b"nope".to_vec(),
+                }
+                .into(),
+                CallFailed {
+                    index: paid,
+                    data: b"nope".to_vec(),
+                }
+                .encode(),
+            ),
+            (
+                NothingToWithdraw { account }.into(),
+                NothingToWithdraw { account }.encode(),
+            ),
+            (
+                InvalidMetadataFormat { format: 3 }.into(),
+                InvalidMetadataFormat { format: 3 }.encode(),
+            ),
+            (
+                InvalidRefundRecipient { refund_to: account }.into(),
+                InvalidRefundRecipient { refund_to: account }.encode(),
+            ),
+            (
+                SoldOut {
+                    max_supply: MAX_SUPPLY,
+                }
+                .into(),
+                SoldOut {
+                    max_supply: MAX_SUPPLY,
+                }
+                .encode(),
+            ),
+            (
+                PermitExpired { deadline: paid }.into(),
+                PermitExpired { deadline: paid }.encode(),
+            ),
+            (
+                ClaimPending {
+                    account,
+                    deadline: paid,
+                }
+                .into(),
+                ClaimPending {
+                    account,
+                    deadline: paid,
+                }
+                .encode(),
+            ),
+            (NoClaim { account }.into(), NoClaim { account }.encode()),
+            (PixelPlaceClosed {}.into(), PixelPlaceClosed {}.encode()),
+            (InvalidPackedImage {}.into(), InvalidPackedImage {}.encode()),
+            (
+                ArtSeedAlreadyCommitted {
+                    commitment: [7; 32],
+                }
+                .into(),
+                ArtSeedAlreadyCommitted {
+                    commitment: [7; 32],
+                }
+                .encode(),
+            ),
+            (
+                ArtSeedAlreadyRevealed { seed: [7; 32] }.into(),
+                ArtSeedAlreadyRevealed { seed: [7; 32] }.encode(),
+            ),
+            (
+                ArtSeedMismatch {
+                    seed: [7; 32],
+                    commitment: [8; 32],
+                }
+                .into(),
+                ArtSeedMismatch {
+                    seed: [7; 32],
+                    commitment: [8; 32],
+                }
+                .encode(),
+            ),
+            (
+                ArtSeedTooLate { total_minted: paid }.into(),
+                ArtSeedTooLate { total_minted: paid }.encode(),
+            ),
+            (
+                ArtSeedUnrevealed {
+                    commitment: [8; 32],
+                }
+                .into(),
+                ArtSeedUnrevealed {
+                    commitment: [8; 32],
+                }
+                .encode(),
+            ),
+            (
+                TokenRenderedEnabled {}.into(),
+                TokenRenderedEnabled {}.encode(),
+            ),
+            (
+                OutOfBounds { x: 32, y: 0 }.into(),
+                OutOfBounds { x: 32, y: 0 }.encode(),
+            ),
+            (
+                MintCooldown {
+                    account,
+                    available_at: paid,
+                }
+                .into(),
+                MintCooldown {
+                    account,
+                    available_at: paid,
+                }
+                .encode(),
+            ),
+            (
+                InvalidSplit {
+                    payees: paid,
+                    total_bps: paid,
+                }
+                .into(),
+                InvalidSplit {
+                    payees: paid,
+                    total_bps: paid,
+                }
+                .encode(),
+            ),
+            (
+                InvalidBatchSize {
+                    quantity: paid,
+                    max_batch: MAX_BATCH,
+                }
+                .into(),
+                InvalidBatchSize {
+                    quantity: paid,
+                    max_batch: MAX_BATCH,
+                }
+                .encode(),
+            ),
+            (
+                ClaimExpired {
+                    account,
+                    deadline: paid,
+                }
+                .into(),
+                ClaimExpired {
+                    account,
+                    deadline: paid,
+                }
+                .encode(),
+            ),
+            (
+                TokenAlreadyMinted { token_id: paid }.into(),
+                TokenAlreadyMinted { token_id: paid }.encode(),
+            ),
+            (
+                TokenIdOutOfRange {
+                    token_id: paid,
+                    max_supply: MAX_SUPPLY,
+                }
+                .into(),
+                TokenIdOutOfRange {
+                    token_id: paid,
+                    max_supply: MAX_SUPPLY,
+                }
+                .encode(),
+            ),
+        ];
+        for (err, expected) in encodings {
+            let name = error_name(expected[..4].try_into().unwrap()).unwrap();
+            assert_eq!(Vec::<u8>::from(err), expected);
+            assert!(!name.is_empty());
+        }
+
+        // nested errors encode as themselves
+        let staked = erc712::TokenStaked { token_id: paid };
+        let err = StylusWorkshopNftError::from(Erc712Error::from(staked.clone()));
+        assert_eq!(Vec::<u8>::from(err), staked.encode());
+        let revert = call::Error::Revert(b"nope".to_vec());
+        assert_eq!(
+            Vec::<u8>::from(StylusWorkshopNftError::from(revert)),
+            b"nope"
+        );
+    }
+
+    #[test]
+    fn test_erc712_errors_bubble_up() {
+        let vm = TestVm::new();
+        let mut nft = deploy(&vm);
+        let token_id = U256::from(9);
+
+        // a missing token fails inside the ERC-721 implementation
+        vm.set_sender(ALICE);
+        let err = nft.stake(token_id).err().unwrap();
+        assert!(matches!(
+            err,
+            StylusWorkshopNftError::Erc712Error(Erc712Error::InvalidTokenId(_))
+        ));
+        let data: Vec<u8> = err.into();
+        assert_eq!(data, erc712::InvalidTokenId { token_id }.encode());
+
+        // as does spending a token without approval
+        mint(&vm, &mut nft, ALICE);
+        vm.set_sender(OWNER);
+        let data: Vec<u8> = nft.burn_from(ALICE, U256::ZERO).err().unwrap().into();
+        let not_approved = erc712::NotApproved {
+            token_id: U256::ZERO,
+            owner: ALICE,
+            spender: OWNER,
+        };
+        assert_eq!(data, not_approved.encode());
+    }
+
+    #[test]
+    fn test_token_accent_color() {
+        let vm = TestVm::new();
+        let mut nft = deploy(&vm);
+        assert!(nft.token_accent_color(U256::ZERO).is_err());
+
+        // tone filters recolor the face, so check tokens without one
+        let mut checked = 0;
+        for id in 0..16 {
+            mint(&vm, &mut nft, ALICE);
+            let token_id = U256::from(id);
+            if art::nft_traits(CONTRACT, token_id).tone != art::ToneFilter::None {
+                continue;
+            }
+            let accent = nft.token_accent_color(token_id).ok().unwrap();
+            let image = Style::render(Style::VERSION, CONTRACT, token_id);
+            assert_eq!(image.pixels[4][4].to_hex(), accent); // the left eye
+            checked += 1;
+        }
+        assert!(checked > 0);
+    }
+
+    #[test]
+    fn test_mint_specific() {
+        let vm = TestVm::new();
+        let mut nft = deploy(&vm);
+        let lucky = U256::from(1);
+
+        vm.set_sender(ALICE);
+        vm.set_value(MINT_PRICE);
+        nft.mint_specific(lucky).ok().unwrap();
+        assert_eq!(nft.erc712.owner_of(lucky).ok(), Some(ALICE));
+        assert_eq!(nft.erc712.total_minted().ok(), Some(U256::from(1)));
+
+        // taken and out of range ids can't be minted
+        vm.set_sender(OWNER);
+        let err: Vec<u8> = nft.mint_specific(lucky).err().unwrap().into();
+        assert_eq!(err, TokenAlreadyMinted { token_id: lucky }.encode());
+        let err: Vec<u8> = nft.mint_specific(MAX_SUPPLY).err().unwrap().into();
+        let out_of_range = TokenIdOutOfRange {
+            token_id: MAX_SUPPLY,
+            max_supply: MAX_SUPPLY,
+        };
+        assert_eq!(err, out_of_range.encode());
+
+        // sequential mints skip the claimed id
+        vm.set_value(U256::ZERO);
+        mint(&vm, &mut nft, OWNER);
+        mint(&vm, &mut nft, OWNER);
+        assert_eq!(nft.erc712.owner_of(U256::ZERO).ok(), Some(OWNER));
+        assert_eq!(nft.erc712.owner_of(lucky).ok(), Some(ALICE));
+        assert_eq!(nft.erc712.owner_of(U256::from(2)).ok(), Some(OWNER));
+    }
+
+    #[test]
+    fn test_sold_out() {
+        let vm = TestVm::new();
+        vm.mock_ecrecover();
+        let mut nft = deploy(&vm);
+        let signer = Address::new([0x5e; 20]);
+        nft.set_mint_signer(signer).ok().unwrap();
+
+        for _ in 0..MAX_SUPPLY.to::<u32>() {
+            assert_eq!(nft.is_sold_out().ok(), Some(false));
+            mint(&vm, &mut nft, ALICE);
+        }
+        assert_eq!(nft.is_sold_out().ok(), Some(true));
+
+        // every mint path refuses
+        vm.set_value(MINT_PRICE);
+        let token_id = MAX_SUPPLY + U256::from(1);
+        let digest = StylusWorkshopNft::mint_authorization_digest(ALICE, token_id)
+            .ok()
+            .unwrap();
+        let signature = test_vm::sign(signer, digest);
+        let results = [
+            nft.mint(),
+            nft.safe_mint(),
+            nft.safe_mint_with_data(vec![1, 2, 3].into()),
+            nft.mint_with_referral(OWNER),
+            nft.mint_specific(MAX_SUPPLY - U256::from(1)),
+            nft.redeem_mint(token_id, signature.into()),
+        ];
+        for result in results {
+            assert!(matches!(result, Err(StylusWorkshopNftError::SoldOut(_))));
+        }
+        assert_eq!(nft.erc712.total_minted().ok(), Some(MAX_SUPPLY));
+    }
+
+    #[test]
+    fn test_permit_for_all_digest_vector() {
+        let _vm = TestVm::new();
+        let operator = Address::new([0x0e; 20]);
+        let (nonce, deadline) = (U256::from(3), U256::from(1_000));
+        let digest =
+            StylusWorkshopNft::permit_for_all_digest(ALICE, operator, true, nonce, deadline)
+                .ok()
+                .unwrap();
+
+        // alloy derives the same type from the struct, and the same digest from it and the domain
+        let permit = PermitForAll {
+            owner: ALICE,
+            operator,
+            approved: true,
+            nonce,
+            deadline,
+        };
+        assert_eq!(
+            <PermitForAll as SolStruct>::eip712_encode_type(),
+            "PermitForAll(address owner,address operator,bool approved,uint256 nonce,uint256 deadline)"
+        );
+        assert_eq!(digest, permit.eip712_signing_hash(&eip712_domain()));
+        let expected =
+            hex_literal::hex!("881005fa631f1358017acb4664873bec8848eb0c94245344f5c283acd5c139de");
+        assert_eq!(digest, B256::new(expected));
+    }
+
+    #[test]
+    fn test_permit_for_all() {
+        let vm = TestVm::new();
+        vm.mock_ecrecover();
+        let mut nft = deploy(&vm);
+        let operator = Address::new([0x0e; 20]);
+        let deadline = U256::from(1_000);
+        let permit = |approved, nonce| {
+            let digest = StylusWorkshopNft::permit_for_all_digest(
+                ALICE, operator, approved, nonce, deadline,
+            );
+            test_vm::sign(ALICE, digest.ok().unwrap())
+        };
+        vm.set_timestamp(500);
+
+        // anyone can relay the owner's signature
+        vm.set_sender(OWNER);
+        let signature = permit(true, U256::ZERO);
+        nft.permit_for_all(ALICE, operator, true, deadline, signature.clone().into())
+            .ok()
+            .unwrap();
+        assert_eq!(
+            nft.erc712.is_approved_for_all(ALICE, operator).ok(),
+            Some(true)
+        );
+        assert_eq!(nft.permit_nonce(ALICE).ok(), Some(U256::from(1)));
+
+        // but not twice
+        assert!(matches!(
+            nft.permit_for_all(ALICE, operator, true, deadline, signature.into()),
+            Err(StylusWorkshopNftError::InvalidSignature(_))
+        ));
+
+        // nor by anyone but the owner, nor for a different approval
+        let digest = StylusWorkshopNft::permit_for_all_digest(
+            ALICE,
+            operator,
+            false,
+            U256::from(1),
+            deadline,
+        );
+        let forged = test_vm::sign(OWNER, digest.ok().unwrap());
+        assert!(matches!(
+            nft.permit_for_all(ALICE, operator, false, deadline, forged.into()),
+            Err(StylusWorkshopNftError::InvalidSignature(_))
+        ));
+        let revoke = permit(false, U256::from(1));
+        assert!(matches!(
+            nft.permit_for_all(ALICE, OWNER, false, deadline, revoke.clone().into()),
+            Err(StylusWorkshopNftError::InvalidSignature(_))
+        ));
+
+        // nor once the deadline passes
+        vm.set_timestamp(1_001);
+        assert!(matches!(
+            nft.permit_for_all(ALICE, operator, false, deadline, revoke.clone().into()),
+            Err(StylusWorkshopNftError::PermitExpired(_))
+        ));
+        vm.set_timestamp(1_000);
+        nft.permit_for_all(ALICE, operator, false, deadline, revoke.into())
+            .ok()
+            .unwrap();
+        assert_eq!(
+            nft.erc712.is_approved_for_all(ALICE, operator).ok(),
+            Some(false)
+        );
+    }
+
+    #[test]
+    fn test_token_uri_override() {
+        let vm = TestVm::new();
+        let mut nft = deploy(&vm);
+        mint(&vm, &mut nft, ALICE);
+        mint(&vm, &mut nft, ALICE);
+        let prize = U256::from(1);
+
+        // overrides wait for the reveal
+        vm.set_sender(OWNER);
+        nft.set_placeholder_uri("ipfs://placeholder".into())
+            .ok()
+            .unwrap();
+        nft.set_token_uri(prize, "ipfs://prize".into())
+            .ok()
+            .unwrap();
+        assert_eq!(
+            nft.token_uri(prize).ok().as_deref(),
+            Some("ipfs://placeholder")
+        );
+
+        vm.take_logs();
+        nft.reveal().ok().unwrap();
+        assert_eq!(nft.token_uri(prize).ok().as_deref(), Some("ipfs://prize"));
+        assert_eq!(
+            nft.token_uri(U256::ZERO).ok(),
+            Some(StylusWorkshopParams::token_uri(U256::ZERO))
+        );
+
+        // overrides beat the base URI too, and can be cleared
+        nft.set_base_uri("ipfs://collection/".into()).ok().unwrap();
+        assert_eq!(nft.token_uri(prize).ok().as_deref(), Some("ipfs://prize"));
+        vm.take_logs();
+        nft.set_token_uri(prize, String::new()).ok().unwrap();
+        assert_eq!(
+            nft.token_uri(prize).ok().as_deref(),
+            Some("ipfs://collection/1")
+        );
+        let update = MetadataUpdate { token_id: prize };
+        assert_eq!(
+            vm.take_logs(),
+            vec![(vec![MetadataUpdate::SIGNATURE_HASH], update.encode_data())]
+        );
+
+        vm.set_sender(ALICE);
+        assert!(matches!(
+            nft.set_token_uri(prize, "ipfs://mine".into()),
+            Err(StylusWorkshopNftError::NotContractOwner(_))
+        ));
+        vm.set_sender(OWNER);
+        nft.freeze_metadata().ok().unwrap();
+        assert!(matches!(
+            nft.set_token_uri(prize, "ipfs://other".into()),
+            Err(StylusWorkshopNftError::MetadataIsFrozen(_))
+        ));
+        assert_eq!(nft.token_uri_override(prize).ok().as_deref(), Some(""));
+    }
+
+    #[test]
+    fn test_render_fingerprint() {
+        let vm = TestVm::new();
+        let nft = deploy(&vm);
+        let ids: Vec<U256> = [0, 1, 7, 42].map(U256::from).to_vec();
+
+        // the same computation off-chain, straight from the art library
+        let pngs: Vec<u8> = ids
+            .iter()
+            .flat_map(|&id| art::generate_nft(CONTRACT, id).make_png())
+            .collect();
+        let expected = alloy_primitives::keccak256(pngs);
+        assert_eq!(nft.render_fingerprint(ids.clone()).ok(), Some(expected));
+
+        // order and membership both matter
+        let reversed = ids.iter().rev().copied().collect();
+        assert_ne!(nft.render_fingerprint(reversed).ok(), Some(expected));
+        assert_ne!(
+            nft.render_fingerprint(ids[1..].to_vec()).ok(),
+            Some(expected)
+        );
+    }
+
+    #[test]
+    fn test_token_image() {
+        let vm = TestVm::new();
+        let mut nft = deploy(&vm);
+        assert!(matches!(
+            nft.token_image(U256::ZERO),
+            Err(StylusWorkshopNftError::Erc712Error(
+                Erc712Error::InvalidTokenId(_)
+            ))
+        ));
+        mint(&vm, &mut nft, ALICE);
+
+        let png = nft.token_image(U256::ZERO).ok().unwrap().0;
+        assert!(png.starts_with(b"\x89PNG\r\n\x1a\n"));
+
+        let mut reader = png::Decoder::new(png.as_slice()).read_info().unwrap();
+        let mut pixels = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut pixels).unwrap();
+        assert_eq!((info.width, info.height), (32, 32));
+        let image = Style::render(Style::VERSION, CONTRACT, U256::ZERO);
+        let expected: Vec<u8> = image
+            .pixels
+            .iter()
+            .flatten()
+            .flat_map(|p| [p.red, p.green, p.blue])
+            .collect();
+        assert_eq!(&pixels[..info.buffer_size()], expected);
+    }
+
+    #[test]
+    fn test_art_versions() {
+        let vm = TestVm::new();
+        let mut nft = deploy(&vm);
+        assert!(nft.art_version(U256::ZERO).is_err());
+        mint(&vm, &mut nft, ALICE);
+        assert_eq!(nft.art_version(U256::ZERO).ok(), Some(Style::VERSION));
+
+        // a new version of the art leaves tokens minted under the old one untouched
+        struct Evolved;
+        impl ArtStyle<32, 32> for Evolved {
+            const VERSION: u8 = 2;
+
+            fn render(version: u8, address: Address, token_id: U256) -> art::Image<32, 32> {
+                let mut image = DefaultStyle::render(version, address, token_id);
+                if version >= 2 {
+                    image.draw_border(1, utils::Color::new(0x01, 0x02, 0x03));
+                }
+                image
+            }
+        }
+        for id in 0..4 {
+            let token_id = U256::from(id);
+            assert_eq!(
+                style_token_uri::<Evolved, 32, 32>(1, token_id, MetadataFormat::Image),
+                style_token_uri::<DefaultStyle, 32, 32>(1, token_id, MetadataFormat::Image)
+            );
+        }
+        assert_ne!(
+            style_token_uri::<Evolved, 32, 32>(2, U256::ZERO, MetadataFormat::Image),
+            style_token_uri::<Evolved, 32, 32>(1, U256::ZERO, MetadataFormat::Image)
+        );
+    }
+
+    #[test]
+    fn test_burn_for_claim() {
+        let vm = TestVm::new();
+        let mut nft = deploy(&vm);
+        let bob = Address::new([0xb0; 20]);
+        mint(&vm, &mut nft, ALICE);
+        mint(&vm, &mut nft, ALICE);
+        mint(&vm, &mut nft, bob);
+        vm.set_timestamp(1_000);
+
+        vm.set_sender(ALICE);
+        assert!(matches!(
+            nft.redeem_claim(),
+            Err(StylusWorkshopNftError::NoClaim(_))
+        ));
+        assert!(nft.burn_for_claim(U256::from(2)).is_err());
+        nft.burn_for_claim(U256::ZERO).ok().unwrap();
+        let deadline = U256::from(1_000 + CLAIM_WINDOW);
+        assert_eq!(nft.claim_deadline(ALICE).ok(), Some(deadline));
+        assert_eq!(nft.pending_withdrawal(ALICE).ok(), Some(U256::ZERO));
+        assert!(nft.erc712.owner_of(U256::ZERO).is_err());
+
+        // one claim at a time
+        assert!(matches!(
+            nft.burn_for_claim(U256::from(1)),
+            Err(StylusWorkshopNftError::ClaimPending(_))
+        ));
+
+        // redeeming on the deadline mints the next token
+        vm.set_timestamp(1_000 + CLAIM_WINDOW);
+        nft.redeem_claim().ok().unwrap();
+        assert_eq!(nft.erc712.owner_of(U256::from(3)).ok(), Some(ALICE));
+        assert_eq!(nft.refundable(U256::from(3)).ok(), Some(MINT_PRICE));
+        assert_eq!(nft.claim_deadline(ALICE).ok(), Some(U256::ZERO));
+        assert!(matches!(
+            nft.redeem_claim(),
+            Err(StylusWorkshopNftError::NoClaim(_))
+        ));
+    }
+
+    #[test]
+    fn test_expired_claim() {
+        let vm = TestVm::new();
+        let mut nft = deploy(&vm);
+        mint(&vm, &mut nft, ALICE);
+        mint(&vm, &mut nft, ALICE);
+        vm.set_timestamp(1_000);
+
+        vm.set_sender(ALICE);
+        nft.burn_for_claim(U256::ZERO).ok().unwrap();
+        vm.set_timestamp(1_001 + CLAIM_WINDOW);
+        assert!(matches!(
+            nft.redeem_claim(),
+            Err(StylusWorkshopNftError::ClaimExpired(_))
+        ));
+        assert_eq!(nft.erc712.total_minted().ok(), Some(U256::from(2)));
+
+        // an expired claim no longer blocks burning for a new one
+        nft.burn_for_claim(U256::from(1)).ok().unwrap();
+        nft.redeem_claim().ok().unwrap();
+        assert_eq!(nft.erc712.owner_of(U256::from(2)).ok(), Some(ALICE));
+    }
+
+    #[test]
+    fn test_set_pixel() {
+        let vm = TestVm::new();
+        let mut nft = deploy(&vm);
+        mint(&vm, &mut nft, ALICE);
+        vm.set_sender(OWNER);
+        nft.reveal().ok().unwrap();
+        nft.set_metadata_format(MetadataFormat::Image as u8)
+            .ok()
+            .unwrap();
+        let red = 0xff0000;
+        let art = art::generate_nft(CONTRACT, U256::ZERO);
+        let image_uri = |nft: &StylusWorkshopNft| {
+            let uri = nft.token_uri(U256::ZERO).ok().unwrap();
+            let (mime_type, png) = decode_data_uri(&uri);
+            assert_eq!(mime_type, "image/png");
+            assert_eq!(nft.token_image(U256::ZERO).ok().unwrap().0, png);
+            let image_hash = crypto::keccak(&png);
+            assert_eq!(nft.verify_token(U256::ZERO, image_hash).ok(), Some(true));
+            png
+        };
+        assert_eq!(image_uri(&nft), art.make_png());
+
+        // closed until the owner opens it
+        vm.set_sender(ALICE);
+        assert!(matches!(
+            nft.set_pixel(U256::ZERO, 3, 4, red),
+            Err(StylusWorkshopNftError::PixelPlaceClosed(_))
+        ));
+        assert!(nft.set_pixel_place_open(true).is_err());
+        vm.set_sender(OWNER);
+        nft.set_pixel_place_open(true).ok().unwrap();
+        assert_eq!(nft.pixel_place_open().ok(), Some(true));
+
+        // only the holder can draw, and only on the canvas
+        assert!(matches!(
+            nft.set_pixel(U256::ZERO, 3, 4, red),
+            Err(StylusWorkshopNftError::Erc712Error(Erc712Error::NotOwner(
+                _
+            )))
+        ));
+        vm.set_sender(ALICE);
+        assert!(matches!(
+            nft.set_pixel(U256::ZERO, 32, 0, red),
+            Err(StylusWorkshopNftError::OutOfBounds(_))
+        ));
+        assert!(matches!(
+            nft.set_pixel(U256::ZERO, 0, 255, red),
+            Err(StylusWorkshopNftError::OutOfBounds(_))
+        ));
+
+        vm.take_logs();
+        nft.set_pixel(U256::ZERO, 3, 4, red).ok().unwrap();
+        let event = PixelSet {
+            token_id: U256::ZERO,
+            x: 3,
+            y: 4,
+            color: red,
+        };
+        let topics = vec![PixelSet::SIGNATURE_HASH, U256::ZERO.into()];
+        let logs = vm.take_logs();
+        assert_eq!(logs[0], (topics, event.encode_data()));
+        assert_eq!(logs[1].0, vec![MetadataUpdate::SIGNATURE_HASH]);
+        nft.set_pixel(U256::ZERO, 31, 31, 0x0000ff).ok().unwrap();
+
+        // the rest of the generated art stays in place
+        let mut expected = art::generate_nft(CONTRACT, U256::ZERO);
+        expected.pixels[4][3] = utils::Color::from_hex(red);
+        expected.pixels[31][31] = utils::Color::from_hex(0x0000ff);
+        assert_eq!(image_uri(&nft), expected.make_png());
+
+        // minting a burned id again starts from fresh art
+        nft.burn(U256::ZERO).ok().unwrap();
+        vm.set_value(MINT_PRICE);
+        nft.mint_specific(U256::ZERO).ok().unwrap();
+        vm.set_value(U256::ZERO);
+        assert_eq!(image_uri(&nft), art.make_png());
+
+        vm.set_sender(OWNER);
+        nft.freeze_metadata().ok().unwrap();
+        vm.set_sender(ALICE);
+        assert!(matches!(
+            nft.set_pixel(U256::ZERO, 3, 4, red),
+            Err(StylusWorkshopNftError::MetadataIsFrozen(_))
+        ));
+    }
+
+    #[test]
+    fn test_living_art() {
+        let vm = TestVm::new();
+        let mut nft = deploy(&vm);
+        mint(&vm, &mut nft, ALICE);
+        vm.set_sender(OWNER);
+        nft.reveal().ok().unwrap();
+        nft.set_metadata_format(MetadataFormat::Living as u8)
+            .ok()
+            .unwrap();
+
+        // (image, animation_url) from the token's JSON metadata
+        let images = |nft: &StylusWorkshopNft| {
+            let uri = nft.token_uri(U256::ZERO).ok().unwrap();
+            let json = String::from_utf8(decode_data_uri(&uri).1).unwrap();
+            let field = |name: &str| {
+                let start = json.find(&format!(r#""{name}":""#)).unwrap() + name.len() + 4;
+                let uri = &json[start..start + json[start..].find('"').unwrap()];
+                let (mime_type, png) = decode_data_uri(uri);
+                assert_eq!(mime_type, "image/png");
+                png
+            };
+            (field("image"), field("animation_url"))
+        };
+        let art = art::generate_nft(CONTRACT, U256::ZERO).make_png();
+
+        vm.set_block_number(0);
+        let (image, start) = images(&nft);
+        assert_eq!(image, art);
+        assert_eq!(start, art);
+
+        // the frame drifts as blocks pass, while the static image stays put
+        vm.set_block_number(90 * BLOCKS_PER_HUE_DEGREE);
+        let (image, later) = images(&nft);
+        assert_eq!(image, art);
+        assert_ne!(later, start);
+        let mut expected = art::generate_nft(CONTRACT, U256::ZERO);
+        expected.shift_hue(90);
+        assert_eq!(later, expected.make_png());
+        vm.set_block_number(90 * BLOCKS_PER_HUE_DEGREE + 1);
+        assert_eq!(images(&nft).1, later);
+
+        // and comes full circle
+        vm.set_block_number(360 * BLOCKS_PER_HUE_DEGREE);
+        assert_eq!(images(&nft).1, start);
+    }
+
+    #[test]
+    fn test_large_token_ids() {
+        let vm = TestVm::new();
+        vm.mock_ecrecover();
+        let mut nft = deploy(&vm);
+        let signer = Address::new([0x5e; 20]);
+        nft.set_mint_signer(signer).ok().unwrap();
+        nft.reveal().ok().unwrap();
+        mint(&vm, &mut nft, ALICE);
+
+        // signed mints aren't bound by MAX_SUPPLY, so ids can use all 256 bits
+        let huge = [U256::MAX, U256::MAX - U256::from(1), U256::from(1) << 64];
+        vm.set_sender(ALICE);
+        for token_id in huge {
+            let digest = StylusWorkshopNft::mint_authorization_digest(ALICE, token_id);
+            let signature = test_vm::sign(signer, digest.ok().unwrap());
+            nft.redeem_mint(token_id, signature.into()).ok().unwrap();
+        }
+
+        // ids that agree in their low bits are still distinct tokens
+        assert!(nft.erc712.owner_of(U256::ZERO).is_ok());
+        assert!(nft.erc712.owner_of(U256::MAX - U256::from(2)).is_err());
+        for token_id in huge {
+            assert_eq!(nft.erc712.owner_of(token_id).ok(), Some(ALICE));
+            let uri = nft.token_uri(token_id).ok().unwrap();
+            let (_, json) = decode_data_uri(&uri);
+            let name = format!(r#"{{"name":"Stylus Workshop NFT #{token_id}""#);
+            assert!(String::from_utf8(json).unwrap().starts_with(&name));
+            let png = art::generate_nft(CONTRACT, token_id).make_png();
+            assert_eq!(nft.token_image(token_id).ok().map(|png| png.0), Some(png));
+        }
+        let near_max = art::generate_nft(CONTRACT, U256::MAX).make_png();
+        assert_ne!(
+            art::generate_nft(CONTRACT, U256::MAX - U256::from(1)).make_png(),
+            near_max
+        );
+
+        // enumeration indexes by position, so it never truncates ids
+        let all = [vec![U256::ZERO], huge.to_vec()].concat();
+        let count = U256::from(10);
+        assert_eq!(
+            nft.erc712.tokens_by_range(U256::ZERO, count).ok(),
+            Some(all.clone())
+        );
+        let owned = nft
+            .erc712
+            .tokens_of_owner_paginated(ALICE, U256::ZERO, count);
+        assert_eq!(owned.ok(), Some(all));
+
+        let bob = Address::new([0xb0; 20]);
+        nft.erc712
+            .transfer_from(ALICE, bob, U256::MAX)
+            .ok()
+            .unwrap();
+        nft.burn(U256::MAX - U256::from(1)).ok().unwrap();
+        assert_eq!(nft.pending_withdrawal(ALICE).ok(), Some(U256::ZERO));
+        let owned = nft
+            .erc712
+            .tokens_of_owner_paginated(ALICE, U256::ZERO, count);
+        assert_eq!(owned.ok(), Some(vec![U256::ZERO, huge[2]]));
+        let owned = nft.erc712.tokens_of_owner_paginated(bob, U256::ZERO, count);
+        assert_eq!(owned.ok(), Some(vec![U256::MAX]));
+        let all = nft.erc712.tokens_by_range(U256::ZERO, count).ok().unwrap();
+        assert_eq!(all, [U256::ZERO, U256::MAX, huge[2]]);
+
+        // sequential mints carry on from where they left off
+        mint(&vm, &mut nft, ALICE);
+        assert_eq!(nft.erc712.owner_of(U256::from(1)).ok(), Some(ALICE));
+    }
+
+    #[test]
+    fn test_mint_cooldown() {
+        let vm = TestVm::new();
+        let mut nft = deploy(&vm);
+        assert_eq!(nft.mint_cooldown().ok(), Some(U256::ZERO));
+        vm.set_sender(ALICE);
+        assert!(matches!(
+            nft.set_mint_cooldown(U256::from(60)),
+            Err(StylusWorkshopNftError::NotContractOwner(_))
+        ));
+        vm.set_sender(OWNER);
+        nft.set_mint_cooldown(U256::from(60)).ok().unwrap();
+
+        vm.set_timestamp(1_000);
+        mint(&vm, &mut nft, ALICE);
+        assert_eq!(nft.last_mint_at(ALICE).ok(), Some(U256::from(1_000)));
+
+        // an immediate second mint reverts, by any paid path
+        vm.set_value(MINT_PRICE);
+        assert!(matches!(
+            nft.mint(),
+            Err(StylusWorkshopNftError::MintCooldown(MintCooldown {
+                account: ALICE,
+                available_at,
+            })) if available_at == U256::from(1_060)
+        ));
+        assert!(matches!(
+            nft.safe_mint(),
+            Err(StylusWorkshopNftError::MintCooldown(_))
+        ));
+        vm.set_timestamp(1_059);
+        assert!(matches!(
+            nft.mint_specific(U256::from(42)),
+            Err(StylusWorkshopNftError::MintCooldown(_))
+        ));
+
+        // other accounts aren't held up
+        let bob = Address::new([0xb0; 20]);
+        mint(&vm, &mut nft, bob);
+
+        vm.set_timestamp(1_060);
+        mint(&vm, &mut nft, ALICE);
+        assert_eq!(nft.erc712.owner_of(U256::from(2)).ok(), Some(ALICE));
+
+        // cooldowns too long to add to the last mint never end
+        vm.set_sender(OWNER);
+        for cooldown in [U256::from(u64::MAX), U256::MAX] {
+            nft.set_mint_cooldown(cooldown).ok().unwrap();
+            vm.set_sender(ALICE);
+            vm.set_value(MINT_PRICE);
+            assert!(matches!(
+                nft.mint(),
+                Err(StylusWorkshopNftError::MintCooldown(MintCooldown { available_at, .. }))
+                    if available_at == U256::from(1_060).saturating_add(cooldown)
+            ));
+            vm.set_sender(OWNER);
+        }
+
+        // zero disables it
+        vm.set_sender(OWNER);
+        nft.set_mint_cooldown(U256::ZERO).ok().unwrap();
+        mint(&vm, &mut nft, ALICE);
+        mint(&vm, &mut nft, ALICE);
+        assert_eq!(nft.erc712.owner_of(U256::from(4)).ok(), Some(ALICE));
+    }
+
+    #[test]
+    fn test_mint_batch() {
+        let vm = TestVm::new();
+        let mut nft = deploy(&vm);
+        vm.set_sender(ALICE);
+        let max_batch = MAX_BATCH.to::<u64>();
+
+        // too large a batch reverts even when paid for and in supply
+        let too_many = MAX_BATCH + U256::from(1);
+        vm.set_value(MINT_PRICE * too_many);
+        assert!(matches!(
+            nft.mint_batch(too_many),
+            Err(StylusWorkshopNftError::InvalidBatchSize(InvalidBatchSize { quantity, .. }))
+                if quantity == too_many
+        ));
+
+        // as does an empty one, which would otherwise start the cooldown for nothing
+        vm.set_sender(OWNER);
+        nft.set_mint_cooldown(U256::from(60)).ok().unwrap();
+        vm.set_sender(ALICE);
+        vm.set_timestamp(1_000);
+        vm.set_value(U256::ZERO);
+        assert!(matches!(
+            nft.mint_batch(U256::ZERO),
+            Err(StylusWorkshopNftError::InvalidBatchSize(InvalidBatchSize { quantity, .. }))
+                if quantity == U256::ZERO
+        ));
+        assert_eq!(nft.last_mint_at(ALICE).ok(), Some(U256::ZERO));
+        vm.set_sender(OWNER);
+        nft.set_mint_cooldown(U256::ZERO).ok().unwrap();
+        vm.set_sender(ALICE);
+
+        vm.set_value(MINT_PRICE);
+        assert!(matches!(
+            nft.mint_batch(MAX_BATCH),
+            Err(StylusWorkshopNftError::IncorrectMintValue(_))
+        ));
+
+        vm.set_value(MINT_PRICE * MAX_BATCH);
+        nft.mint_batch(MAX_BATCH).ok().unwrap();
+        for id in 0..max_batch {
+            assert_eq!(nft.erc712.owner_of(U256::from(id)).ok(), Some(ALICE));
+            assert_eq!(nft.art_version(U256::from(id)).ok(), Some(Style::VERSION));
+        }
+        assert!(nft.erc712.owner_of(U256::from(max_batch)).is_err());
+        assert_eq!(nft.erc712.total_minted().ok(), Some(MAX_BATCH));
+    }
+
+    #[test]
+    fn test_token_uri_length() {
+        let vm = TestVm::new();
+        let mut nft = deploy(&vm);
+        assert!(nft.token_uri_length(U256::ZERO).is_err());
+        mint(&vm, &mut nft, ALICE);
+        let length = |nft: &StylusWorkshopNft| {
+            let uri = nft.token_uri(U256::ZERO).ok().unwrap();
+            assert_eq!(
+                nft.token_uri_length(U256::ZERO).ok(),
+                Some(U256::from(uri.len()))
+            );
+            uri.len()
+        };
+        length(&nft);
+
+        vm.set_sender(OWNER);
+        nft.reveal().ok().unwrap();
+        for format in [
+            MetadataFormat::Json,
+            MetadataFormat::Image,
+            MetadataFormat::Living,
+        ] {
+            nft.set_metadata_format(format as u8).ok().unwrap();
+            assert!(length(&nft) > 1_000);
+        }
+    }
+
+    #[test]
+    fn test_rarity_score() {
+        let vm = TestVm::new();
+        let mut nft = deploy(&vm);
+        assert!(nft.rarity_score(U256::ZERO).is_err());
+
+        let mut scores = Vec::new();
+        for id in 0..32 {
+            mint(&vm, &mut nft, ALICE);
+            let token_id = U256::from(id);
+            let traits = art::nft_traits(CONTRACT, token_id);
+            let score = nft.rarity_score(token_id).ok().unwrap();
+            assert_eq!(score, U256::from(traits.rarity_score()));
+            scores.push((traits, score));
+        }
+
+        // alike but for a rarer background, a token scores higher
+        let mut compared = 0;
+        for (a, a_score) in &scores {
+            for (b, b_score) in &scores {
+                if a.tone == b.tone
+                    && a.frame == b.frame
+                    && a.background.rarity() > b.background.rarity()
+                {
+                    assert!(a_score > b_score);
+                    compared += 1;
+                }
+            }
+        }
+        assert!(compared > 0);
+    }
+
+    #[test]
+    fn test_split_proceeds() {
+        let vm = TestVm::new();
+        let mut nft = deploy(&vm);
+        let treasury = Address::new([0x70; 20]);
+        // a contract that rejects plain eth transfers
+        let artist = Address::new([0x30; 20]);
+        vm.mock_contract(artist, |_, _| Err(b"no eth".to_vec()));
+
+        // shares must line up with payees and sum to 100%
+        for (accounts, shares) in [
+            (vec![treasury, artist], vec![7_000, 2_000]),
+            (vec![treasury, artist], vec![10_000]),
+            (vec![treasury], vec![7_000, 3_000]),
+        ] {
+            assert!(matches!(
+                nft.set_payees(accounts, shares),
+                Err(StylusWorkshopNftError::InvalidSplit(_))
+            ));
+        }
+        vm.set_sender(ALICE);
+        assert!(matches!(
+            nft.set_payees(vec![treasury], vec![10_000]),
+            Err(StylusWorkshopNftError::NotContractOwner(_))
+        ));
+        vm.set_sender(OWNER);
+        nft.set_payees(vec![treasury, artist], vec![7_000, 3_000])
+            .ok()
+            .unwrap();
+        assert_eq!(
+            nft.payees().ok(),
+            Some((vec![treasury, artist], vec![7_000, 3_000]))
+        );
+
+        // payees are credited rather than called, so even one that can't take eth doesn't block the mint
+        vm.take_calls();
+        mint(&vm, &mut nft, ALICE);
+        assert!(vm.take_calls().is_empty());
+        let treasury_share = MINT_PRICE * U256::from(7) / U256::from(10);
+        let artist_share = MINT_PRICE - treasury_share;
+        assert_eq!(nft.erc712.owner_of(U256::ZERO).ok(), Some(ALICE));
+        assert_eq!(nft.pending_withdrawal(treasury).ok(), Some(treasury_share));
+        assert_eq!(nft.pending_withdrawal(artist).ok(), Some(artist_share));
+
+        vm.set_sender(treasury);
+        nft.withdraw_refund().ok().unwrap();
+        assert_eq!(
+            vm.take_calls(),
+            vec![CallRecord {
+                to: treasury,
+                calldata: vec![],
+                value: treasury_share,
+            }]
+        );
+        assert_eq!(nft.pending_withdrawal(treasury).ok(), Some(U256::ZERO));
+        vm.set_sender(ALICE);
+
+        // the payees took the proceeds, so burning the token can't refund them
+        assert_eq!(nft.refundable(U256::ZERO).ok(), Some(U256::ZERO));
+        nft.burn(U256::ZERO).ok().unwrap();
+        assert_eq!(nft.pending_withdrawal(ALICE).ok(), Some(U256::ZERO));
+
+        // clearing the split leaves proceeds in the contract again, to back refunds
+        vm.set_sender(OWNER);
+        nft.set_payees(vec![], vec![]).ok().unwrap();
+        mint(&vm, &mut nft, ALICE);
+        assert!(vm.take_calls().is_empty());
+        nft.burn(U256::from(1)).ok().unwrap();
+        assert_eq!(nft.pending_withdrawal(ALICE).ok(), Some(MINT_PRICE));
+    }
+
+    #[test]
+    fn test_special_art() {
+        let vm = TestVm::new();
+        let mut nft = deploy(&vm);
+        let special = U256::from(1);
+        let mut art: Image<32, 32> = Image::new(utils::Color::from_hex(0x102030));
+        art.draw_border(2, utils::Color::from_hex(0xffd700));
+        let packed = Bytes(art.to_packed_bytes());
+
+        // only whole 32x32 images, set by the owner
+        let small: Image<8, 8> = Image::new(utils::Color::from_hex(0x102030));
+        for invalid in [small.to_packed_bytes(), vec![0, 1, 2, 3], vec![1]] {
+            assert!(matches!(
+                nft.set_special_art(special, Bytes(invalid)),
+                Err(StylusWorkshopNftError::InvalidPackedImage(_))
+            ));
+        }
+        vm.set_sender(ALICE);
+        assert!(matches!(
+            nft.set_special_art(special, packed.clone()),
+            Err(StylusWorkshopNftError::NotContractOwner(_))
+        ));
+        vm.set_sender(OWNER);
+        nft.set_special_art(special, packed.clone()).ok().unwrap();
+        assert_eq!(nft.special_art(special).ok(), Some(packed));
+
+        // the special id shows its own art, and its neighbors their generated art
+        nft.set_emit_token_rendered(true).ok().unwrap();
+        vm.take_logs();
+        for _ in 0..3 {
+            mint(&vm, &mut nft, ALICE);
+        }
+        let rendered: Vec<_> = vm
+            .take_logs()
+            .into_iter()
+            .filter(|(topics, _)| topics[0] == TokenRendered::SIGNATURE_HASH)
+            .collect();
+        let image_hash = crypto::keccak(art.make_png());
+        let event = TokenRendered {
+            token_id: special,
+            image_hash: image_hash.0,
+        };
+        assert_eq!(rendered[1].1, event.encode_data());
+        assert_eq!(nft.verify_token(special, image_hash).ok(), Some(true));
+        let fingerprint = nft.render_fingerprint(vec![special]).ok();
+        assert_eq!(fingerprint, Some(image_hash));
+        vm.set_sender(OWNER);
+        nft.reveal().ok().unwrap();
+        nft.set_metadata_format(MetadataFormat::Image as u8)
+            .ok()
+            .unwrap();
+        let image_uri = |nft: &StylusWorkshopNft, token_id| {
+            let uri = nft.token_uri(token_id).ok().unwrap();
+            decode_data_uri(&uri).1
+        };
+        assert_eq!(image_uri(&nft, special), art.make_png());
+        assert_eq!(nft.token_image(special).ok().unwrap().0, art.make_png());
+        for id in [0, 2] {
+            let token_id = U256::from(id);
+            let generated = art::generate_nft(CONTRACT, token_id).make_png();
+            assert_eq!(image_uri(&nft, token_id), generated);
+        }
+
+        // clearing it brings back the generated art
+        vm.take_logs();
+        nft.set_special_art(special, Bytes(vec![])).ok().unwrap();
+        assert_eq!(vm.take_logs().len(), 1);
+        let generated = art::generate_nft(CONTRACT, special).make_png();
+        assert_eq!(image_uri(&nft, special), generated);
+    }
+
+    #[test]
+    fn test_traits_of() {
+        let vm = TestVm::new();
+        let mut nft = deploy(&vm);
+        assert!(nft.traits_of(U256::ZERO).is_err());
+        mint(&vm, &mut nft, ALICE);
+
+        // call it as another contract would, through the ABI
+        let call = traitsOfCall {
+            token_id: U256::ZERO,
+        };
+        let results = nft.multicall(vec![call.encode().into()]).ok().unwrap();
+        let traits = traitsOfCall::decode_returns(&results[0], true).unwrap()._0;
+
+        let expected = art::nft_traits(CONTRACT, U256::ZERO);
+        let expected = vec![
+            ("Background", expected.background.name()),
+            ("Pattern", expected.pattern.name()),
+            ("Frame", expected.frame.name()),
+            ("Tone", expected.tone.name()),
+            ("Rarity", expected.rarity().name()),
+        ];
+        let expected: Vec<(String, String)> = expected
+            .into_iter()
+            .map(|(trait_type, value)| (trait_type.into(), value.into()))
+            .collect();
+        assert_eq!(traits, expected);
+        assert_eq!(nft.traits_of(U256::ZERO).ok(), Some(expected));
+    }
+
+    #[test]
+    fn test_art_seed() {
+        let vm = TestVm::new();
+        let mut nft = deploy(&vm);
+        let seed = B256::repeat_byte(0x5e);
+        let commitment = crypto::keccak(seed);
+        let art =
+            |nft: &StylusWorkshopNft, id: u64| nft.token_image(U256::from(id)).ok().unwrap().0;
+
+        // without a revealed seed, every token is drawn from its own id
+        assert!(matches!(
+            nft.reveal_art_seed(seed),
+            Err(StylusWorkshopNftError::ArtSeedMismatch(_))
+        ));
+        nft.commit_art_seed(commitment).ok().unwrap();
+        assert_eq!(nft.art_seed_commitment().ok(), Some(commitment));
+        assert!(matches!(
+            nft.commit_art_seed(B256::ZERO),
+            Err(StylusWorkshopNftError::ArtSeedAlreadyCommitted(_))
+        ));
+
+        // hashes of the art announced before the reveal would go stale
+        assert!(matches!(
+            nft.set_emit_token_rendered(true),
+            Err(StylusWorkshopNftError::ArtSeedUnrevealed(ArtSeedUnrevealed { commitment: c }))
+                if c == commitment.0
+        ));
+        nft.set_emit_token_rendered(false).ok().unwrap();
+        for _ in 0..8 {
+            mint(&vm, &mut nft, ALICE);
+        }
+        vm.set_sender(OWNER);
+        for id in 0..8 {
+            assert_eq!(nft.art_id(U256::from(id)).ok(), Some(U256::from(id)));
+            let generated = art::generate_nft(CONTRACT, U256::from(id)).make_png();
+            assert_eq!(art(&nft, id), generated);
+        }
+
+        // only the committed seed reveals
+        assert!(matches!(
+            nft.reveal_art_seed(B256::repeat_byte(0x5f)),
+            Err(StylusWorkshopNftError::ArtSeedMismatch(_))
+        ));
+        vm.set_sender(ALICE);
+        assert!(nft.reveal_art_seed(seed).is_err());
+        vm.set_sender(OWNER);
+        vm.take_logs();
+        nft.reveal_art_seed(seed).ok().unwrap();
+        let logs = vm.take_logs();
+        assert_eq!(logs[0].1, ArtSeedRevealed { seed: seed.0 }.encode_data());
+        assert_eq!(logs[1].0, vec![BatchMetadataUpdate::SIGNATURE_HASH]);
+        assert_eq!(nft.art_seed().ok(), Some(seed));
+        assert!(matches!(
+            nft.reveal_art_seed(seed),
+            Err(StylusWorkshopNftError::ArtSeedAlreadyRevealed(_))
+        ));
+        nft.set_emit_token_rendered(true).ok().unwrap();
+
+        // each token now shows, and lists the traits of, the art of its shuffled id
+        let mut moved = 0;
+        for id in 0..8 {
+            let token_id = U256::from(id);
+            let art_id = nft.art_id(token_id).ok().unwrap();
+            assert!(art_id < MAX_SUPPLY);
+            moved += usize::from(art_id != token_id);
+            let generated = art::generate_nft(CONTRACT, art_id);
+            assert_eq!(art(&nft, id), generated.make_png());
+            let traits = art::nft_traits(CONTRACT, art_id);
+            let listed = nft.traits_of(token_id).ok().unwrap();
+            assert_eq!(
+                listed[0],
+                ("Background".into(), traits.background.name().into())
+            );
+            let hash = crypto::keccak(generated.make_png());
+            assert_eq!(nft.verify_token(token_id, hash).ok(), Some(true));
+        }
+        assert!(moved > 0);
+        assert_eq!(nft.art_id(MAX_SUPPLY).ok(), Some(MAX_SUPPLY));
+    }
+
+    #[test]
+    fn test_art_seed_committed_before_mint() {
+        let vm = TestVm::new();
+        let mut nft = deploy(&vm);
+        mint(&vm, &mut nft, ALICE);
+
+        // once anyone has minted, a seed could be chosen to favor them
+        vm.set_sender(OWNER);
+        let commitment = crypto::keccak(B256::repeat_byte(0x5e));
+        assert!(matches!(
+            nft.commit_art_seed(commitment),
+            Err(StylusWorkshopNftError::ArtSeedTooLate(ArtSeedTooLate { total_minted }))
+                if total_minted == U256::from(1)
+        ));
+        assert_eq!(nft.art_seed_commitment().ok(), Some(B256::ZERO));
+
+        // burning doesn't reopen it
+        vm.set_sender(ALICE);
+        nft.burn(U256::ZERO).ok().unwrap();
+        vm.set_sender(OWNER);
+        assert!(nft.commit_art_seed(commitment).is_err());
+    }
+
+    #[test]
+    fn test_art_seed_with_token_rendered() {
+        let vm = TestVm::new();
+        let mut nft = deploy(&vm);
+        nft.set_emit_token_rendered(true).ok().unwrap();
+
+        // committing would let the reveal change art whose hash was announced
+        let commitment = crypto::keccak(B256::repeat_byte(0x5e));
+        assert!(matches!(
+            nft.commit_art_seed(commitment),
+            Err(StylusWorkshopNftError::TokenRenderedEnabled(_))
+        ));
+        nft.set_emit_token_rendered(false).ok().unwrap();
+        nft.commit_art_seed(commitment).ok().unwrap();
+    }
 }
@@ -0,0 +1,69 @@
+//! Declares error enums that encode to revert data.
+//!
+//! Stands in for the SDK's `#[derive(SolidityError)]`, which isn't in the version this crate uses.
+
+/// Declares an enum whose variants each wrap one error, and implements:
+/// - `From<Enum> for Vec<u8>`, encoding the wrapped error as revert data
+/// - `From<Error> for Enum` for each variant, so `?` converts into the enum
+/// - `Enum::error_name`, naming the Solidity error with a given selector, so the names can't drift from the variants
+///
+/// Variants wrap Solidity errors declared with `sol!` by default.
+/// Mark those wrapping other errors, which encode themselves via `Into<Vec<u8>>`, as `#[nested]`.
+macro_rules! solidity_error {
+    (
+        $(#[$meta:meta])*
+        $vis:vis enum $name:ident {
+            $($(#[$kind:ident])? $variant:ident($error:ty)),* $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        $vis enum $name {
+            $($variant($error)),*
+        }
+
+        impl From<$name> for alloc::vec::Vec<u8> {
+            fn from(err: $name) -> Self {
+                match err {
+                    $($name::$variant(err) => solidity_error!(@encode $($kind)? err)),*
+                }
+            }
+        }
+
+        $(
+            impl From<$error> for $name {
+                fn from(err: $error) -> Self {
+                    Self::$variant(err)
+                }
+            }
+        )*
+
+        #[cfg(any(test, feature = "std"))]
+        impl $name {
+            /// Names the Solidity error with the given selector, up to its arguments.
+            /// Nested errors aren't searched, so callers can name them however they name their own.
+            pub fn error_name(selector: [u8; 4]) -> Option<&'static str> {
+                $(
+                    if let Some(name) = solidity_error!(@name $($kind)? $error, selector) {
+                        return Some(name);
+                    }
+                )*
+                None
+            }
+        }
+    };
+    (@encode nested $err:ident) => {
+        $err.into()
+    };
+    (@encode $err:ident) => {
+        alloy_sol_types::SolError::encode(&$err)
+    };
+    (@name nested $error:ty, $selector:ident) => {
+        None::<&'static str>
+    };
+    (@name $error:ty, $selector:ident) => {
+        match <$error as alloy_sol_types::SolError>::SELECTOR == $selector {
+            true => <$error as alloy_sol_types::SolError>::SIGNATURE.split('(').next(),
+            false => None,
+        }
+    };
+}
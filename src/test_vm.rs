@@ -0,0 +1,372 @@
+//! A minimal host-side stand-in for the Stylus VM, used by the contract tests.
+//!
+//! On WASM the SDK's hostios are imported from `vm_hooks`; on the host they're ordinary
+//! symbols, so this module defines them against an in-memory world state.
+//! Storage, logs, and calls to other contracts are all tracked per test.
+//!
+//! The SDK caches the call context, like `msg::sender()` and `block::timestamp()`,
+//! for the life of the process, so the contract reads it through the [`msg`] and
+//! [`block`] shims in this module instead.
+
+use alloy_primitives::{Address, B256, U256};
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    rc::Rc,
+    sync::{Mutex, MutexGuard},
+};
+use stylus_sdk::storage::StorageType;
+use tiny_keccak::{Hasher, Keccak};
+
+/// The address the contract under test is deployed at.
+pub const CONTRACT: Address = Address::new([0xc0; 20]);
+
+/// The chain the contract under test is deployed on.
+pub const CHAIN_ID: u64 = 23011913;
+
+/// The address of the `ecrecover` precompile.
+const ECRECOVER: Address = Address::with_last_byte(1);
+
+/// Handles a call to a mocked contract, given its calldata and value.
+/// Returning `Err` makes the call revert with the given data.
+type Handler = Rc<dyn Fn(&[u8], U256) -> Result<Vec<u8>, Vec<u8>>>;
+
+/// A call made by the contract under test.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CallRecord {
+    pub to: Address,
+    pub calldata: Vec<u8>,
+    pub value: U256,
+}
+
+#[derive(Default)]
+struct World {
+    storage: HashMap<B256, B256>,
+    storage_loads: usize,
+    logs: Vec<(Vec<B256>, Vec<u8>)>,
+    contracts: HashMap<Address, Handler>,
+    calls: Vec<CallRecord>,
+    return_data: Vec<u8>,
+    sender: Address,
+    value: U256,
+    timestamp: u64,
+    block_number: u64,
+}
+
+thread_local! {
+    static WORLD: RefCell<World> = RefCell::default();
+}
+
+/// Serializes tests that touch the VM, since the SDK keeps some of its state in statics.
+static VM_LOCK: Mutex<()> = Mutex::new(());
+
+/// Exclusive access to a fresh VM, held for the duration of a test.
+pub struct TestVm {
+    _lock: MutexGuard<'static, ()>,
+}
+
+impl TestVm {
+    pub fn new() -> Self {
+        let lock = VM_LOCK
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        WORLD.with(|world| *world.borrow_mut() = World::default());
+        Self { _lock: lock }
+    }
+
+    pub fn set_timestamp(&self, timestamp: u64) {
+        WORLD.with(|world| world.borrow_mut().timestamp = timestamp);
+    }
+
+    pub fn set_block_number(&self, number: u64) {
+        WORLD.with(|world| world.borrow_mut().block_number = number);
+    }
+
+    /// Deploys a mock contract whose calls are answered by `handler`.
+    pub fn mock_contract(
+        &self,
+        address: Address,
+        handler: impl Fn(&[u8], U256) -> Result<Vec<u8>, Vec<u8>> + 'static,
+    ) {
+        WORLD.with(|world| {
+            world
+                .borrow_mut()
+                .contracts
+                .insert(address, Rc::new(handler))
+        });
+    }
+
+    /// Installs a stand-in for the `ecrecover` precompile that accepts signatures made by [`sign`].
+    pub fn mock_ecrecover(&self) {
+        self.mock_contract(ECRECOVER, |calldata, _| {
+            let (digest, rest) = calldata.split_at(32);
+            let (v, rs) = rest.split_at(32);
+            let (r, s) = rs.split_at(32);
+            let valid = matches!(v[31], 27 | 28) && s == signature_check(digest, r).as_slice();
+            // like the precompile, return nothing for an invalid signature
+            Ok(if valid { r.to_vec() } else { Vec::new() })
+        });
+    }
+
+    /// Takes the calls made so far.
+    pub fn take_calls(&self) -> Vec<CallRecord> {
+        WORLD.with(|world| core::mem::take(&mut world.borrow_mut().calls))
+    }
+
+    /// Takes the number of storage slots read so far.
+    pub fn take_storage_loads(&self) -> usize {
+        WORLD.with(|world| core::mem::take(&mut world.borrow_mut().storage_loads))
+    }
+
+    /// Takes the logs emitted so far as (topics, data) pairs.
+    pub fn take_logs(&self) -> Vec<(Vec<B256>, Vec<u8>)> {
+        WORLD.with(|world| core::mem::take(&mut world.borrow_mut().logs))
+    }
+
+    /// Runs a contract call, rolling back its storage writes and logs if it fails, as a revert would.
+    /// Storage fields cache the values they've read, so reload the contract with [`TestVm::contract`]
+    /// to read what the rollback restored.
+    pub fn call<T, E>(&self, call: impl FnOnce() -> Result<T, E>) -> Result<T, E> {
+        let (storage, logs) = WORLD.with(|world| {
+            let world = world.borrow();
+            (world.storage.clone(), world.logs.len())
+        });
+        let result = call();
+        if result.is_err() {
+            WORLD.with(|world| {
+                let mut world = world.borrow_mut();
+                world.storage = storage;
+                world.logs.truncate(logs);
+            });
+        }
+        result
+    }
+
+    /// Creates the contract's storage, rooted at slot zero like the entrypoint.
+    pub fn contract<T: StorageType>(&self) -> T {
+        unsafe { T::new(U256::ZERO, 0) }
+    }
+
+    /// Sets the caller for subsequent contract calls.
+    pub fn set_sender(&self, sender: Address) {
+        WORLD.with(|world| world.borrow_mut().sender = sender);
+    }
+
+    /// Sets the value sent with subsequent contract calls.
+    pub fn set_value(&self, value: U256) {
+        WORLD.with(|world| world.borrow_mut().value = value);
+    }
+}
+
+/// Signs `digest` as `signer` under the stand-in scheme of [`TestVm::mock_ecrecover`].
+///
+/// There's no secp256k1 here, so the signature's `r` is simply the signer's address,
+/// and its `s` a hash binding that address to the digest.
+pub fn sign(signer: Address, digest: B256) -> Vec<u8> {
+    let r = signer.into_word();
+    let s = signature_check(digest.as_slice(), r.as_slice());
+    [r.as_slice(), s.as_slice(), &[27]].concat()
+}
+
+fn signature_check(digest: &[u8], r: &[u8]) -> B256 {
+    alloy_primitives::keccak256([digest, r].concat())
+}
+
+/// Stands in for [`stylus_sdk::msg`], whose values the SDK caches process-wide.
+pub mod msg {
+    use super::WORLD;
+    use alloy_primitives::{Address, U256};
+
+    pub fn sender() -> Address {
+        WORLD.with(|world| world.borrow().sender)
+    }
+
+    pub fn value() -> U256 {
+        WORLD.with(|world| world.borrow().value)
+    }
+}
+
+/// Stands in for [`stylus_sdk::block`], whose values the SDK caches process-wide.
+pub mod block {
+    use super::WORLD;
+
+    pub fn timestamp() -> u64 {
+        WORLD.with(|world| world.borrow().timestamp)
+    }
+
+    pub fn number() -> u64 {
+        WORLD.with(|world| world.borrow().block_number)
+    }
+
+    pub fn chainid() -> u64 {
+        super::CHAIN_ID
+    }
+}
+
+unsafe fn read<const N: usize>(ptr: *const u8) -> [u8; N] {
+    let mut out = [0; N];
+    out.copy_from_slice(core::slice::from_raw_parts(ptr, N));
+    out
+}
+
+unsafe fn write(dest: *mut u8, data: &[u8]) {
+    core::ptr::copy_nonoverlapping(data.as_ptr(), dest, data.len());
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn native_keccak256(bytes: *const u8, len: usize, output: *mut u8) {
+    let mut hasher = Keccak::v256();
+    hasher.update(core::slice::from_raw_parts(bytes, len));
+    let mut hash = [0; 32];
+    hasher.finalize(&mut hash);
+    write(output, &hash);
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn storage_load_bytes32(key: *const u8, dest: *mut u8) {
+    let key = B256::from(read::<32>(key));
+    let value = WORLD.with(|world| {
+        let mut world = world.borrow_mut();
+        world.storage_loads += 1;
+        world.storage.get(&key).copied()
+    });
+    write(dest, value.unwrap_or_default().as_slice());
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn storage_store_bytes32(key: *const u8, value: *const u8) {
+    let key = B256::from(read::<32>(key));
+    let value = B256::from(read::<32>(value));
+    WORLD.with(|world| world.borrow_mut().storage.insert(key, value));
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn emit_log(data: *const u8, len: usize, topics: usize) {
+    let data = core::slice::from_raw_parts(data, len);
+    let (topic_bytes, data) = data.split_at(32 * topics);
+    let topics = topic_bytes.chunks(32).map(B256::from_slice).collect();
+    WORLD.with(|world| world.borrow_mut().logs.push((topics, data.to_vec())));
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn account_codehash(address: *const u8, dest: *mut u8) {
+    let address = Address::from(read::<20>(address));
+    let has_code = WORLD.with(|world| world.borrow().contracts.contains_key(&address));
+    let hash = match has_code {
+        true => alloy_primitives::keccak256(address),
+        false => B256::ZERO,
+    };
+    write(dest, hash.as_slice());
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn call_contract(
+    contract: *const u8,
+    calldata: *const u8,
+    calldata_len: usize,
+    value: *const u8,
+    _gas: u64,
+    return_data_len: *mut usize,
+) -> u8 {
+    let to = Address::from(read::<20>(contract));
+    let calldata = core::slice::from_raw_parts(calldata, calldata_len).to_vec();
+    let value = U256::from_be_bytes(read::<32>(value));
+    let handler = WORLD.with(|world| {
+        let mut world = world.borrow_mut();
+        world.calls.push(CallRecord {
+            to,
+            calldata: calldata.clone(),
+            value,
+        });
+        world.contracts.get(&to).cloned()
+    });
+
+    // calls to accounts without code always succeed
+    let result = match handler {
+        Some(handler) => handler(&calldata, value),
+        None => Ok(Vec::new()),
+    };
+    let (status, data) = match result {
+        Ok(data) => (0, data),
+        Err(data) => (1, data),
+    };
+    *return_data_len = data.len();
+    WORLD.with(|world| world.borrow_mut().return_data = data);
+    status
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn static_call_contract(
+    contract: *const u8,
+    calldata: *const u8,
+    calldata_len: usize,
+    gas: u64,
+    return_data_len: *mut usize,
+) -> u8 {
+    let value = B256::ZERO;
+    call_contract(
+        contract,
+        calldata,
+        calldata_len,
+        value.as_ptr(),
+        gas,
+        return_data_len,
+    )
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn delegate_call_contract(
+    contract: *const u8,
+    calldata: *const u8,
+    calldata_len: usize,
+    gas: u64,
+    return_data_len: *mut usize,
+) -> u8 {
+    static_call_contract(contract, calldata, calldata_len, gas, return_data_len)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn read_return_data(dest: *mut u8, offset: usize, size: usize) -> usize {
+    WORLD.with(|world| {
+        let world = world.borrow();
+        let data = world.return_data.get(offset..).unwrap_or_default();
+        let data = &data[..size.min(data.len())];
+        write(dest, data);
+        data.len()
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn return_data_size() -> usize {
+    WORLD.with(|world| world.borrow().return_data.len())
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn contract_address(address: *mut u8) {
+    write(address, CONTRACT.as_slice());
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn msg_sender(sender: *mut u8) {
+    write(sender, msg::sender().as_slice());
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn msg_value(value: *mut u8) {
+    write(value, &msg::value().to_be_bytes::<32>());
+}
+
+#[no_mangle]
+pub extern "C" fn block_timestamp() -> u64 {
+    block::timestamp()
+}
+
+#[no_mangle]
+pub extern "C" fn block_number() -> u64 {
+    block::number()
+}
+
+#[no_mangle]
+pub extern "C" fn evm_gas_left() -> u64 {
+    u64::MAX
+}
@@ -1,11 +1,25 @@
 //! Utilities.
 
-use alloc::{boxed::Box, vec, vec::Vec};
+pub mod json;
+
+use alloc::{
+    boxed::Box,
+    collections::{BTreeMap, BinaryHeap},
+    string::String,
+    vec,
+    vec::Vec,
+};
+use core::{
+    cmp::Reverse,
+    fmt::{self, Write},
+};
+use fastrand::Rng;
 use hex_literal::hex;
+use stylus_sdk::storage::StorageBytes;
 
 use crate::art::Image;
 
-#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Default, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct Color {
     pub red: u8,
     pub green: u8,
@@ -13,7 +27,12 @@ pub struct Color {
 }
 
 impl Color {
-    pub const fn from_hex(value: usize) -> Self {
+    pub const fn new(red: u8, green: u8, blue: u8) -> Self {
+        Self { red, green, blue }
+    }
+
+    /// Unpacks a `0xrrggbb` value, ignoring any bits above the low 24.
+    pub const fn from_hex(value: u32) -> Self {
         Self {
             red: (value >> 16) as u8,
             green: (value >> 8) as u8,
@@ -21,14 +40,198 @@ impl Color {
         }
     }
 
-    pub const fn to_hex(&self) -> usize {
-        (self.red as usize) << 16 | (self.green as usize) << 8 | self.blue as usize
+    /// Packs the color as `0xrrggbb`.
+    pub const fn to_hex(&self) -> u32 {
+        (self.red as u32) << 16 | (self.green as u32) << 8 | self.blue as u32
+    }
+
+    /// Converts from HSV, where `hue` is in degrees (mod 360),
+    /// and `saturation` and `value` range from 0 to 255.
+    pub fn from_hsv(hue: u16, saturation: u8, value: u8) -> Self {
+        let (s, v) = (saturation as u32, value as u32);
+        let hue = hue as u32 % 360;
+        let rem = (hue % 60) * 255 / 60;
+        let p = (v * (255 - s) / 255) as u8;
+        let q = (v * (255 - s * rem / 255) / 255) as u8;
+        let t = (v * (255 - s * (255 - rem) / 255) / 255) as u8;
+        match hue / 60 {
+            0 => Self::new(value, t, p),
+            1 => Self::new(q, value, p),
+            2 => Self::new(p, value, t),
+            3 => Self::new(p, q, value),
+            4 => Self::new(t, p, value),
+            _ => Self::new(value, p, q),
+        }
+    }
+
+    /// Converts to HSV as `(hue, saturation, value)`, the inverse of [`Color::from_hsv`].
+    pub fn to_hsv(self) -> (u16, u8, u8) {
+        let (r, g, b) = (self.red as i32, self.green as i32, self.blue as i32);
+        let max = r.max(g).max(b);
+        let delta = max - r.min(g).min(b);
+        if delta == 0 {
+            return (0, 0, max as u8);
+        }
+        let saturation = 255 * delta / max;
+        let hue = if max == r {
+            60 * (g - b) / delta
+        } else if max == g {
+            120 + 60 * (b - r) / delta
+        } else {
+            240 + 60 * (r - g) / delta
+        };
+        (hue.rem_euclid(360) as u16, saturation as u8, max as u8)
+    }
+
+    /// Perceived brightness, from 0 to 255.
+    pub const fn luminance(&self) -> u8 {
+        ((77 * self.red as u32 + 150 * self.green as u32 + 29 * self.blue as u32) >> 8) as u8
+    }
+
+    /// Applies `f` to each channel.
+    fn map(self, f: impl Fn(u8) -> u8) -> Self {
+        Self::new(f(self.red), f(self.green), f(self.blue))
+    }
+
+    /// Blends `a` into `b`, where `t` ranges from 0 (all `a`) to 255 (all `b`).
+    pub fn lerp(a: Color, b: Color, t: u8) -> Self {
+        Self::lerp_ratio(a, b, t as u32, 255)
+    }
+
+    /// Blends `a` into `b` by the fraction `t / span`, rounding down, where `t` is at most `span`.
+    pub fn lerp_ratio(a: Color, b: Color, t: u32, span: u32) -> Self {
+        let lerp = |x: u8, y: u8| ((x as u32 * (span - t) + y as u32 * t) / span) as u8;
+        Self::new(
+            lerp(a.red, b.red),
+            lerp(a.green, b.green),
+            lerp(a.blue, b.blue),
+        )
+    }
+
+    /// Multiplies each channel by `factor / 255`, so 255 leaves the color unchanged.
+    pub fn scale(self, factor: u8) -> Self {
+        self.map(|x| (x as u32 * factor as u32 / 255) as u8)
+    }
+
+    /// Returns the complementary color.
+    pub fn invert(self) -> Self {
+        self.map(|x| !x)
+    }
+
+    /// Whether each channel is within `tolerance` of `other`'s.
+    /// Lets tests of blending and scaling ignore off-by-one rounding.
+    #[cfg(test)]
+    pub fn approx_eq(self, other: Color, tolerance: u8) -> bool {
+        self.red.abs_diff(other.red) <= tolerance
+            && self.green.abs_diff(other.green) <= tolerance
+            && self.blue.abs_diff(other.blue) <= tolerance
     }
 }
 
+/// Formats the color as `#rrggbb`.
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#{:06x}", self.to_hex())
+    }
+}
+
+/// Prints hex rather than the channels, so failing tests show colors at a glance.
+impl fmt::Debug for Color {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+/// Width of a glyph in [`glyph`]'s font, in pixels.
+pub const GLYPH_WIDTH: usize = 3;
+
+/// Height of a glyph in [`glyph`]'s font, in pixels.
+pub const GLYPH_HEIGHT: usize = 5;
+
+/// Looks up a character in a tiny 3x5 bitmap font covering digits, letters, and a few symbols.
+///
+/// Each row is a bitmask whose most significant of the 3 bits is the leftmost pixel.
+/// Lowercase letters render as uppercase.
+pub fn glyph(c: char) -> Option<[u8; GLYPH_HEIGHT]> {
+    Some(match c.to_ascii_uppercase() {
+        '0' => [0b111, 0b101, 0b101, 0b101, 0b111],
+        '1' => [0b010, 0b110, 0b010, 0b010, 0b111],
+        '2' => [0b111, 0b001, 0b111, 0b100, 0b111],
+        '3' => [0b111, 0b001, 0b111, 0b001, 0b111],
+        '4' => [0b101, 0b101, 0b111, 0b001, 0b001],
+        '5' => [0b111, 0b100, 0b111, 0b001, 0b111],
+        '6' => [0b111, 0b100, 0b111, 0b101, 0b111],
+        '7' => [0b111, 0b001, 0b001, 0b001, 0b001],
+        '8' => [0b111, 0b101, 0b111, 0b101, 0b111],
+        '9' => [0b111, 0b101, 0b111, 0b001, 0b111],
+        'A' => [0b010, 0b101, 0b111, 0b101, 0b101],
+        'B' => [0b110, 0b101, 0b110, 0b101, 0b110],
+        'C' => [0b011, 0b100, 0b100, 0b100, 0b011],
+        'D' => [0b110, 0b101, 0b101, 0b101, 0b110],
+        'E' => [0b111, 0b100, 0b110, 0b100, 0b111],
+        'F' => [0b111, 0b100, 0b110, 0b100, 0b100],
+        'G' => [0b011, 0b100, 0b101, 0b101, 0b011],
+        'H' => [0b101, 0b101, 0b111, 0b101, 0b101],
+        'I' => [0b111, 0b010, 0b010, 0b010, 0b111],
+        'J' => [0b001, 0b001, 0b001, 0b101, 0b010],
+        'K' => [0b101, 0b101, 0b110, 0b101, 0b101],
+        'L' => [0b100, 0b100, 0b100, 0b100, 0b111],
+        'M' => [0b101, 0b111, 0b111, 0b101, 0b101],
+        'N' => [0b110, 0b101, 0b101, 0b101, 0b101],
+        'O' => [0b010, 0b101, 0b101, 0b101, 0b010],
+        'P' => [0b110, 0b101, 0b110, 0b100, 0b100],
+        'Q' => [0b010, 0b101, 0b101, 0b110, 0b011],
+        'R' => [0b110, 0b101, 0b110, 0b101, 0b101],
+        'S' => [0b011, 0b100, 0b010, 0b001, 0b110],
+        'T' => [0b111, 0b010, 0b010, 0b010, 0b010],
+        'U' => [0b101, 0b101, 0b101, 0b101, 0b111],
+        'V' => [0b101, 0b101, 0b101, 0b101, 0b010],
+        'W' => [0b101, 0b101, 0b111, 0b111, 0b101],
+        'X' => [0b101, 0b101, 0b010, 0b101, 0b101],
+        'Y' => [0b101, 0b101, 0b010, 0b010, 0b010],
+        'Z' => [0b111, 0b001, 0b010, 0b100, 0b111],
+        '#' => [0b101, 0b111, 0b101, 0b111, 0b101],
+        '-' => [0b000, 0b000, 0b111, 0b000, 0b000],
+        '.' => [0b000, 0b000, 0b000, 0b000, 0b010],
+        ' ' => [0b000, 0b000, 0b000, 0b000, 0b000],
+        _ => return None,
+    })
+}
+
 /// A grid of pixels `R` rows by `C` columns.
 pub type Pixels<const R: usize, const C: usize> = Box<[[Color; C]; R]>;
 
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Encodes `data` as standard, padded base64.
+pub fn base64_encode(data: &[u8]) -> String {
+    let mut out = String::new();
+    base64_encode_into(data, &mut out);
+    out
+}
+
+/// Appends `data` to `out` as standard, padded base64, reserving the space up front.
+pub fn base64_encode_into(data: &[u8], out: &mut String) {
+    out.reserve(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let bytes = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let word = u32::from_be_bytes([0, bytes[0], bytes[1], bytes[2]]);
+        for i in 0..4 {
+            if i <= chunk.len() {
+                let index = (word >> (18 - 6 * i)) & 0x3f;
+                out.push(BASE64_ALPHABET[index as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+}
+
 /// Doesn't actually compress, just changes formats.
 ///
 /// Equivalent to zlib compression level 0.
@@ -60,32 +263,236 @@ pub fn zlib_format(mut data: &[u8]) -> Vec<u8> {
     out
 }
 
+/// The PNG per-row filter types.
+///
+/// See https://www.w3.org/TR/png/#9Filter-types
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum PngFilter {
+    None = 0,
+    Sub = 1,
+    Up = 2,
+    Paeth = 4,
+}
+
+impl PngFilter {
+    pub const ALL: [PngFilter; 4] = [Self::None, Self::Sub, Self::Up, Self::Paeth];
+
+    /// Filters a single scanline given the previous (unfiltered) one.
+    /// `bpp` is the number of bytes per complete pixel.
+    fn apply(self, row: &[u8], prev: &[u8], bpp: usize, out: &mut Vec<u8>) {
+        for i in 0..row.len() {
+            let left = if i >= bpp { row[i - bpp] } else { 0 };
+            let up = prev[i];
+            let up_left = if i >= bpp { prev[i - bpp] } else { 0 };
+            let predicted = match self {
+                Self::None => 0,
+                Self::Sub => left,
+                Self::Up => up,
+                Self::Paeth => paeth_predictor(left, up, up_left),
+            };
+            out.push(row[i].wrapping_sub(predicted));
+        }
+    }
+}
+
+/// Picks whichever of `left`, `up`, or `up_left` is closest to `left + up - up_left`.
+fn paeth_predictor(left: u8, up: u8, up_left: u8) -> u8 {
+    let p = left as i16 + up as i16 - up_left as i16;
+    let pa = (p - left as i16).abs();
+    let pb = (p - up as i16).abs();
+    let pc = (p - up_left as i16).abs();
+    if pa <= pb && pa <= pc {
+        left
+    } else if pb <= pc {
+        up
+    } else {
+        up_left
+    }
+}
+
+/// The minimum-sum-of-absolute-differences heuristic, treating bytes as signed.
+fn filter_cost(filtered: &[u8]) -> u32 {
+    filtered
+        .iter()
+        .map(|&b| (b as i8).unsigned_abs() as u32)
+        .sum()
+}
+
 impl<const R: usize, const C: usize> Image<R, C> {
-    fn uncompressed_pixel_data(&self) -> Vec<u8> {
-        let mut out = Vec::with_capacity(R * (1 + C * 3));
-        for row in &*self.pixels {
-            out.push(0); // Filter type: none
+    /// Builds an image from row-major RGB bytes, as produced by [`Image::make_png`] before filtering.
+    /// Returns `None` unless there are exactly `R * C * 3` bytes.
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn from_rgb_bytes(data: &[u8]) -> Option<Image<R, C>> {
+        if data.len() != R * C * 3 {
+            return None;
+        }
+        let mut image = Image::new(Color::default());
+        for (pixel, rgb) in image.pixels.iter_mut().flatten().zip(data.chunks_exact(3)) {
+            *pixel = Color::new(rgb[0], rgb[1], rgb[2]);
+        }
+        Some(image)
+    }
+
+    /// Packs the image compactly for contract storage, as runs of identical pixels in row-major order.
+    /// Each run is 4 bytes: its length from 1 to 255, then the pixel's red, green, and blue.
+    /// Flat art packs far smaller than its `R * C * 3` bytes of RGB.
+    pub fn to_packed_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        let mut pixels = self.pixels.iter().flatten().peekable();
+        while let Some(&color) = pixels.next() {
+            let mut run = 1;
+            while run < u8::MAX && pixels.next_if_eq(&&color).is_some() {
+                run += 1;
+            }
+            out.extend([run, color.red, color.green, color.blue]);
+        }
+        out
+    }
+
+    /// Unpacks an image packed with [`Image::to_packed_bytes`].
+    /// Returns `None` if the runs are malformed or don't cover exactly `R * C` pixels.
+    pub fn from_packed_bytes(data: &[u8]) -> Option<Image<R, C>> {
+        if !data.len().is_multiple_of(4) {
+            return None;
+        }
+        let mut image = Image::new(Color::default());
+        let mut pixels = image.pixels.iter_mut().flatten();
+        for run in data.chunks_exact(4) {
+            if run[0] == 0 {
+                return None;
+            }
+            let color = Color::new(run[1], run[2], run[3]);
+            for _ in 0..run[0] {
+                *pixels.next()? = color;
+            }
+        }
+        if pixels.next().is_some() {
+            return None;
+        }
+        Some(image)
+    }
+
+    /// Persists the image to `storage` in its packed form.
+    pub fn store_packed(&self, storage: &mut StorageBytes) {
+        storage.set_bytes(self.to_packed_bytes());
+    }
+
+    /// Loads an image persisted with [`Image::store_packed`].
+    /// Returns `None` if nothing, or something other than an `R` by `C` image, is stored.
+    pub fn load_packed(storage: &StorageBytes) -> Option<Image<R, C>> {
+        Self::from_packed_bytes(&storage.get_bytes())
+    }
+
+    fn raw_rows(&self) -> impl Iterator<Item = Vec<u8>> + '_ {
+        self.pixels.iter().map(|row| {
+            let mut raw = Vec::with_capacity(C * 3);
             for pixel in row {
-                out.push(pixel.red);
-                out.push(pixel.green);
-                out.push(pixel.blue);
+                raw.push(pixel.red);
+                raw.push(pixel.green);
+                raw.push(pixel.blue);
             }
+            raw
+        })
+    }
+
+    /// Produces the scanlines, each prefixed by its filter type.
+    /// If `filter` is `None`, picks the best filter for each row heuristically.
+    fn filtered_pixel_data(&self, filter: Option<PngFilter>) -> Vec<u8> {
+        let mut out = Vec::with_capacity(R * (1 + C * 3));
+        let mut prev = vec![0; C * 3];
+        let mut candidate = Vec::with_capacity(C * 3);
+        let mut best = Vec::with_capacity(C * 3);
+        let filters = match &filter {
+            Some(filter) => core::slice::from_ref(filter),
+            None => &PngFilter::ALL[..],
+        };
+        for row in self.raw_rows() {
+            let mut best_filter = PngFilter::None;
+            let mut best_cost = u32::MAX;
+            for ty in filters {
+                candidate.clear();
+                ty.apply(&row, &prev, 3, &mut candidate);
+                let cost = filter_cost(&candidate);
+                if cost < best_cost {
+                    best_cost = cost;
+                    best_filter = *ty;
+                    core::mem::swap(&mut best, &mut candidate);
+                }
+            }
+            out.push(best_filter as u8);
+            out.extend_from_slice(&best);
+            prev = row;
         }
         out
     }
 
     pub fn make_png(&self) -> Vec<u8> {
-        let idat = zlib_format(&self.uncompressed_pixel_data());
+        self.make_png_with_filter(None)
+    }
+
+    /// Encodes the image as a PNG using the given row filter,
+    /// or a per-row heuristic if `filter` is `None`.
+    pub fn make_png_with_filter(&self, filter: Option<PngFilter>) -> Vec<u8> {
+        let idat = zlib_format(&self.filtered_pixel_data(filter));
+        let mut out = Self::png_header();
+        append_png_chunk(&mut out, b"IDAT", &idat);
+        append_png_chunk(&mut out, b"IEND", &[]);
+        out
+    }
+
+    /// Encodes `frames` as an animated PNG that loops forever,
+    /// showing each frame for `delay_ms` milliseconds.
+    ///
+    /// The first frame doubles as the static image for viewers without APNG support.
+    /// Note that every frame adds roughly the size of a full PNG to the output.
+    /// Returns an empty vector if `frames` is empty.
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn make_apng(frames: &[Image<R, C>], delay_ms: u16) -> Vec<u8> {
+        if frames.is_empty() {
+            return Vec::new();
+        }
+        let mut out = Self::png_header();
+
+        let mut actl = Vec::new();
+        actl.extend((frames.len() as u32).to_be_bytes()); // number of frames
+        actl.extend(0_u32.to_be_bytes()); // number of plays: infinite
+        append_png_chunk(&mut out, b"acTL", &actl);
+
+        let mut sequence = 0_u32;
+        for (i, frame) in frames.iter().enumerate() {
+            let mut fctl = Vec::new();
+            fctl.extend(sequence.to_be_bytes());
+            fctl.extend((C as u32).to_be_bytes());
+            fctl.extend((R as u32).to_be_bytes());
+            fctl.extend(0_u32.to_be_bytes()); // x offset
+            fctl.extend(0_u32.to_be_bytes()); // y offset
+            fctl.extend(delay_ms.to_be_bytes()); // delay numerator
+            fctl.extend(1000_u16.to_be_bytes()); // delay denominator
+            fctl.push(0); // dispose op: none
+            fctl.push(0); // blend op: source
+            append_png_chunk(&mut out, b"fcTL", &fctl);
+            sequence += 1;
+
+            let data = zlib_format(&frame.filtered_pixel_data(None));
+            if i == 0 {
+                append_png_chunk(&mut out, b"IDAT", &data);
+            } else {
+                let mut fdat = Vec::with_capacity(4 + data.len());
+                fdat.extend(sequence.to_be_bytes());
+                fdat.extend(data);
+                append_png_chunk(&mut out, b"fdAT", &fdat);
+                sequence += 1;
+            }
+        }
+        append_png_chunk(&mut out, b"IEND", &[]);
+        out
+    }
+
+    /// The PNG signature followed by the IHDR chunk.
+    fn png_header() -> Vec<u8> {
         let mut out = Vec::new();
         out.extend(hex!("89504E470D0A1A0A")); // PNG signature
-        let mut append_chunk = |name: &[u8; 4], chunk: &[u8]| {
-            out.extend((chunk.len() as u32).to_be_bytes());
-            let start = out.len();
-            out.extend(name);
-            out.extend(chunk);
-            let crc = crc::Crc::<u32>::new(&crc::CRC_32_ISO_HDLC);
-            out.extend(crc.checksum(&out[start..]).to_be_bytes());
-        };
         let mut ihdr = Vec::new();
         ihdr.extend((C as u32).to_be_bytes());
         ihdr.extend((R as u32).to_be_bytes());
@@ -94,14 +501,525 @@ impl<const R: usize, const C: usize> Image<R, C> {
         ihdr.push(0); // compression: deflate
         ihdr.push(0); // filter method: adapative
         ihdr.push(0); // interlace: no interlace
-        append_chunk(b"IHDR", &ihdr);
-        drop(ihdr);
-        append_chunk(b"IDAT", &idat);
-        append_chunk(b"IEND", &[]);
+        append_png_chunk(&mut out, b"IHDR", &ihdr);
+        out
+    }
+}
+
+/// Appends a PNG chunk with its length and CRC.
+fn append_png_chunk(out: &mut Vec<u8>, name: &[u8; 4], chunk: &[u8]) {
+    out.extend((chunk.len() as u32).to_be_bytes());
+    let start = out.len();
+    out.extend(name);
+    out.extend(chunk);
+    let crc = crc::Crc::<u32>::new(&crc::CRC_32_ISO_HDLC);
+    out.extend(crc.checksum(&out[start..]).to_be_bytes());
+}
+
+/// Packs variable-width codes into bytes, least significant bit first.
+#[derive(Default)]
+struct BitWriter {
+    out: Vec<u8>,
+    buffer: u32,
+    bits: u8,
+}
+
+impl BitWriter {
+    fn write(&mut self, value: impl Into<u32>, width: u8) {
+        self.buffer |= value.into() << self.bits;
+        self.bits += width;
+        while self.bits >= 8 {
+            self.out.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.bits -= 8;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.bits > 0 {
+            self.out.push(self.buffer as u8);
+        }
+        self.out
+    }
+}
+
+/// Compresses palette indices with GIF-flavored LZW.
+fn lzw_encode(min_code_size: u8, indices: &[u8]) -> Vec<u8> {
+    const MAX_CODES: u16 = 4096;
+    let clear = 1_u16 << min_code_size;
+    let end = clear + 1;
+
+    let mut writer = BitWriter::default();
+    let mut dict = BTreeMap::new();
+    let mut next_code = end + 1;
+    let mut width = min_code_size + 1;
+    writer.write(clear, width);
+
+    let Some((&first, rest)) = indices.split_first() else {
+        writer.write(end, width);
+        return writer.finish();
+    };
+    let mut prefix = first as u16;
+    for &index in rest {
+        if let Some(&code) = dict.get(&(prefix, index)) {
+            prefix = code;
+            continue;
+        }
+        writer.write(prefix, width);
+        if next_code < MAX_CODES {
+            dict.insert((prefix, index), next_code);
+            next_code += 1;
+            if next_code > 1 << width && width < 12 {
+                width += 1;
+            }
+        } else {
+            // The table is full, so start over
+            writer.write(clear, width);
+            dict.clear();
+            next_code = end + 1;
+            width = min_code_size + 1;
+        }
+        prefix = index as u16;
+    }
+    writer.write(prefix, width);
+    writer.write(end, width);
+    writer.finish()
+}
+
+/// Maps colors to palette indices for GIF encoding.
+///
+/// Uses the exact colors when there are at most 256 of them,
+/// and otherwise falls back to a lossy 3-3-2 bit palette.
+struct GifPalette {
+    colors: Vec<Color>,
+    exact: Option<BTreeMap<u32, u8>>,
+}
+
+impl GifPalette {
+    fn new<'a>(pixels: impl Iterator<Item = &'a Color>) -> Self {
+        let mut indices = BTreeMap::new();
+        let mut colors = Vec::new();
+        for color in pixels {
+            if indices.contains_key(&color.to_hex()) {
+                continue;
+            }
+            if colors.len() == 256 {
+                let colors = (0..=255_u8)
+                    .map(|i| {
+                        let expand = |bits: u8, max: u8| (bits as u32 * 255 / max as u32) as u8;
+                        Color::new(expand(i >> 5, 7), expand(i >> 2 & 7, 7), expand(i & 3, 3))
+                    })
+                    .collect();
+                return Self {
+                    colors,
+                    exact: None,
+                };
+            }
+            indices.insert(color.to_hex(), colors.len() as u8);
+            colors.push(*color);
+        }
+        Self {
+            colors,
+            exact: Some(indices),
+        }
+    }
+
+    fn index(&self, color: Color) -> u8 {
+        match &self.exact {
+            Some(indices) => indices[&color.to_hex()],
+            None => (color.red & 0xe0) | (color.green >> 5) << 2 | color.blue >> 6,
+        }
+    }
+
+    /// The number of bits needed to index the palette, which is always at least 1.
+    fn depth(&self) -> u8 {
+        let mut depth = 1;
+        while 1 << depth < self.colors.len() {
+            depth += 1;
+        }
+        depth
+    }
+}
+
+impl<const R: usize, const C: usize> Image<R, C> {
+    pub fn make_gif(&self) -> Vec<u8> {
+        Self::make_animated_gif(core::slice::from_ref(self), 0)
+    }
+
+    /// Encodes `frames` as a GIF sharing a single global color table.
+    /// When there's more than one frame, the animation loops forever,
+    /// showing each frame for `delay_cs` hundredths of a second.
+    /// Returns an empty vector if `frames` is empty.
+    pub fn make_animated_gif(frames: &[Image<R, C>], delay_cs: u16) -> Vec<u8> {
+        if frames.is_empty() {
+            return Vec::new();
+        }
+        let palette = GifPalette::new(frames.iter().flat_map(|f| f.pixels.iter().flatten()));
+        let depth = palette.depth();
+
+        let mut out = Vec::new();
+        out.extend(b"GIF89a");
+        out.extend((C as u16).to_le_bytes());
+        out.extend((R as u16).to_le_bytes());
+        out.push(0x80 | 0x70 | (depth - 1)); // global color table, 8-bit color resolution
+        out.push(0); // background color index
+        out.push(0); // pixel aspect ratio: unspecified
+        for i in 0..1 << depth {
+            let color = palette.colors.get(i).copied().unwrap_or_default();
+            out.extend([color.red, color.green, color.blue]);
+        }
+
+        if frames.len() > 1 {
+            out.extend(hex!("21FF0B")); // application extension
+            out.extend(b"NETSCAPE2.0");
+            out.extend(hex!("03010000")); // loop forever
+            out.push(0);
+        }
+
+        let min_code_size = depth.max(2);
+        for frame in frames {
+            if frames.len() > 1 {
+                out.extend(hex!("21F904")); // graphic control extension
+                out.push(0); // no disposal or transparency
+                out.extend(delay_cs.to_le_bytes());
+                out.push(0); // transparent color index
+                out.push(0);
+            }
+            out.push(0x2c); // image descriptor
+            out.extend(0_u16.to_le_bytes()); // left
+            out.extend(0_u16.to_le_bytes()); // top
+            out.extend((C as u16).to_le_bytes());
+            out.extend((R as u16).to_le_bytes());
+            out.push(0); // no local color table, not interlaced
+
+            let indices: Vec<u8> = frame
+                .pixels
+                .iter()
+                .flatten()
+                .map(|&color| palette.index(color))
+                .collect();
+            out.push(min_code_size);
+            for block in lzw_encode(min_code_size, &indices).chunks(255) {
+                out.push(block.len() as u8);
+                out.extend(block);
+            }
+            out.push(0); // block terminator
+        }
+        out.push(0x3b); // trailer
         out
     }
 }
 
+/// Computes Huffman code lengths for symbols occurring `counts` times, limited to `max_len` bits.
+/// Unused symbols get a length of 0, and a lone used symbol gets a length of 1.
+fn huffman_lengths(counts: &[u32], max_len: u8) -> Vec<u8> {
+    let mut lengths = vec![0; counts.len()];
+    let used: Vec<usize> = (0..counts.len()).filter(|&i| counts[i] > 0).collect();
+    if used.len() == 1 {
+        lengths[used[0]] = 1;
+    }
+    if used.len() < 2 {
+        return lengths;
+    }
+
+    // Flattening the counts shortens the deepest codes until they fit
+    let mut min_count = 1;
+    loop {
+        let mut heap = BinaryHeap::new();
+        let mut parents = vec![usize::MAX; used.len()];
+        for (node, &symbol) in used.iter().enumerate() {
+            heap.push(Reverse((counts[symbol].max(min_count), node)));
+        }
+        while let (Some(Reverse((a, left))), Some(Reverse((b, right)))) = (heap.pop(), heap.pop()) {
+            let node = parents.len();
+            parents.push(usize::MAX);
+            parents[left] = node;
+            parents[right] = node;
+            heap.push(Reverse((a + b, node)));
+        }
+
+        let mut max_depth = 0;
+        for (node, &symbol) in used.iter().enumerate() {
+            let mut depth = 0;
+            let mut current = node;
+            while parents[current] != usize::MAX {
+                current = parents[current];
+                depth += 1;
+            }
+            lengths[symbol] = depth;
+            max_depth = max_depth.max(depth);
+        }
+        if max_depth <= max_len {
+            return lengths;
+        }
+        min_count *= 2;
+    }
+}
+
+/// A canonical prefix code, ready to be written least significant bit first.
+struct PrefixCode {
+    codes: Vec<u16>,
+    widths: Vec<u8>,
+}
+
+impl PrefixCode {
+    fn new(lengths: &[u8]) -> Self {
+        let mut codes = vec![0; lengths.len()];
+        let mut next = 0_u16;
+        for len in 1..=15 {
+            for (symbol, _) in lengths.iter().enumerate().filter(|(_, &l)| l == len) {
+                // Codes are read starting from their most significant bit
+                codes[symbol] = next.reverse_bits() >> (16 - len);
+                next += 1;
+            }
+            next <<= 1;
+        }
+        // A code with a single symbol takes no bits to write
+        let mut widths = lengths.to_vec();
+        if lengths.iter().filter(|&&l| l > 0).count() == 1 {
+            widths.fill(0);
+        }
+        Self { codes, widths }
+    }
+
+    fn write(&self, writer: &mut BitWriter, symbol: usize) {
+        writer.write(self.codes[symbol], self.widths[symbol]);
+    }
+}
+
+/// The order in which VP8L transmits code length code lengths.
+const CODE_LENGTH_ORDER: [usize; 19] = [
+    17, 18, 0, 1, 2, 3, 4, 5, 16, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15,
+];
+
+/// Writes a VP8L prefix code for symbols occurring `counts` times, returning the code.
+fn write_vp8l_code(writer: &mut BitWriter, counts: &[u32]) -> PrefixCode {
+    let used: Vec<usize> = (0..counts.len()).filter(|&i| counts[i] > 0).collect();
+    let lengths = huffman_lengths(counts, 15);
+    if let [symbol] = used[..] {
+        if symbol < 256 {
+            writer.write(1_u8, 1); // simple code
+            writer.write(0_u8, 1); // one symbol
+            let wide = symbol > 1;
+            writer.write(wide, 1);
+            writer.write(symbol as u32, if wide { 8 } else { 1 });
+            return PrefixCode::new(&lengths);
+        }
+    }
+    if used.is_empty() {
+        // Unused, so any symbol will do
+        writer.write(0b0001_u8, 4); // simple code of just symbol 0
+        return PrefixCode::new(&lengths);
+    }
+
+    // Tokenize the lengths, using run codes 17 and 18 for zeros
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < lengths.len() {
+        let zeros = lengths[i..]
+            .iter()
+            .take_while(|&&l| l == 0)
+            .count()
+            .min(138);
+        match zeros {
+            11.. => tokens.push((18, zeros - 11, 7)),
+            3.. => tokens.push((17, zeros - 3, 3)),
+            _ => {
+                tokens.push((lengths[i] as usize, 0, 0));
+                i += 1;
+                continue;
+            }
+        }
+        i += zeros;
+    }
+    let mut token_counts = [0; 19];
+    for &(token, _, _) in &tokens {
+        token_counts[token] += 1;
+    }
+    let token_lengths = huffman_lengths(&token_counts, 7);
+    let token_code = PrefixCode::new(&token_lengths);
+
+    let num_lengths = CODE_LENGTH_ORDER
+        .iter()
+        .rposition(|&token| token_lengths[token] > 0)
+        .map_or(4, |last| (last + 1).max(4));
+    writer.write(0_u8, 1); // normal code
+    writer.write(num_lengths as u32 - 4, 4);
+    for &token in &CODE_LENGTH_ORDER[..num_lengths] {
+        writer.write(token_lengths[token], 3);
+    }
+    writer.write(0_u8, 1); // lengths are given for the whole alphabet
+    for (token, extra, extra_bits) in tokens {
+        token_code.write(writer, token);
+        writer.write(extra as u32, extra_bits);
+    }
+    PrefixCode::new(&lengths)
+}
+
+/// VP8L transform types.
+const SUBTRACT_GREEN: u8 = 2;
+const PREDICTOR: u8 = 0;
+
+/// Predictor transform block size, as a power of two.
+const PREDICTOR_BITS: u8 = 9;
+
+/// Predictor transform mode that predicts each pixel from its left neighbor.
+const PREDICT_LEFT: u8 = 1;
+
+impl<const R: usize, const C: usize> Image<R, C> {
+    /// Encodes the image as a lossless WebP.
+    ///
+    /// Applies the subtract-green and left-predictor transforms,
+    /// then Huffman codes the residuals without backward references.
+    pub fn make_webp(&self) -> Vec<u8> {
+        // Subtract green, then predict each pixel from its neighbors
+        let decorrelated: Vec<[u8; 3]> = self
+            .pixels
+            .iter()
+            .flatten()
+            .map(|p| {
+                [
+                    p.red.wrapping_sub(p.green),
+                    p.green,
+                    p.blue.wrapping_sub(p.green),
+                ]
+            })
+            .collect();
+        let residuals: Vec<[u8; 3]> = (0..R * C)
+            .map(|i| {
+                let predicted = match (i % C, i / C) {
+                    (0, 0) => [0; 3],
+                    (0, _) => decorrelated[i - C],
+                    _ => decorrelated[i - 1],
+                };
+                let [r, g, b] = decorrelated[i];
+                [
+                    r.wrapping_sub(predicted[0]),
+                    g.wrapping_sub(predicted[1]),
+                    b.wrapping_sub(predicted[2]),
+                ]
+            })
+            .collect();
+
+        let mut writer = BitWriter::default();
+        writer.write(0x2f_u8, 8); // signature
+        writer.write(C as u32 - 1, 14);
+        writer.write(R as u32 - 1, 14);
+        writer.write(0_u8, 1); // alpha is unused
+        writer.write(0_u8, 3); // version
+
+        writer.write(1_u8, 1);
+        writer.write(SUBTRACT_GREEN, 2);
+
+        writer.write(1_u8, 1);
+        writer.write(PREDICTOR, 2);
+        writer.write(PREDICTOR_BITS - 2, 3);
+        // The sub-image of predictor modes, which is uniform and so takes no pixel data
+        writer.write(0_u8, 1); // no color cache
+        for symbol in [PREDICT_LEFT, 0, 0, 255] {
+            let mut counts = [0; 256];
+            counts[symbol as usize] = 1;
+            write_vp8l_code(&mut writer, &counts);
+        }
+        write_vp8l_code(&mut writer, &[0; 40]);
+        writer.write(0_u8, 1); // no more transforms
+
+        writer.write(0_u8, 1); // no color cache
+        writer.write(0_u8, 1); // no meta prefix codes
+
+        let mut green = [0; 280];
+        let mut red = [0; 256];
+        let mut blue = [0; 256];
+        for &[r, g, b] in &residuals {
+            green[g as usize] += 1;
+            red[r as usize] += 1;
+            blue[b as usize] += 1;
+        }
+        let green = write_vp8l_code(&mut writer, &green);
+        let red = write_vp8l_code(&mut writer, &red);
+        let blue = write_vp8l_code(&mut writer, &blue);
+        let mut alpha = [0; 256];
+        alpha[0] = 1; // the residual of an opaque pixel
+        let alpha = write_vp8l_code(&mut writer, &alpha);
+        write_vp8l_code(&mut writer, &[0; 40]); // distances are unused
+
+        for [r, g, b] in residuals {
+            green.write(&mut writer, g as usize);
+            red.write(&mut writer, r as usize);
+            blue.write(&mut writer, b as usize);
+            alpha.write(&mut writer, 0);
+        }
+        let vp8l = writer.finish();
+
+        let padding = vp8l.len() % 2;
+        let mut out = Vec::with_capacity(20 + vp8l.len() + padding);
+        out.extend(b"RIFF");
+        out.extend((12 + vp8l.len() as u32 + padding as u32).to_le_bytes());
+        out.extend(b"WEBP");
+        out.extend(b"VP8L");
+        out.extend((vp8l.len() as u32).to_le_bytes());
+        out.extend(vp8l);
+        out.extend(vec![0; padding]);
+        out
+    }
+}
+
+/// The encodings an image can be rendered in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageFormat {
+    Png,
+    Gif,
+    Webp,
+    Svg,
+}
+
+impl ImageFormat {
+    /// The MIME type, as used in data URIs.
+    pub const fn mime_type(self) -> &'static str {
+        match self {
+            Self::Png => "image/png",
+            Self::Gif => "image/gif",
+            Self::Webp => "image/webp",
+            Self::Svg => "image/svg+xml",
+        }
+    }
+}
+
+impl<const R: usize, const C: usize> Image<R, C> {
+    pub fn encode(&self, format: ImageFormat) -> Vec<u8> {
+        match format {
+            ImageFormat::Png => self.make_png(),
+            ImageFormat::Gif => self.make_gif(),
+            ImageFormat::Webp => self.make_webp(),
+            ImageFormat::Svg => self.make_svg(),
+        }
+    }
+
+    /// Encodes the image as an SVG of pixel-sized rectangles,
+    /// merging horizontal runs of the same color into one.
+    pub fn make_svg(&self) -> Vec<u8> {
+        let mut out = String::new();
+        let _ = write!(
+            out,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 {C} {R}" shape-rendering="crispEdges">"#
+        );
+        for (y, row) in self.pixels.iter().enumerate() {
+            let mut x = 0;
+            while x < C {
+                let color = row[x];
+                let run = row[x..].iter().take_while(|&&p| p == color).count();
+                let _ = write!(
+                    out,
+                    r#"<rect x="{x}" y="{y}" width="{run}" height="1" fill="{color}"/>"#
+                );
+                x += run;
+            }
+        }
+        out.push_str("</svg>");
+        out.into_bytes()
+    }
+}
+
 const FNV_PRIME: u64 = 1099511628211;
 
 /// Implements FNV-1a hashing (not cryptographically secure)
@@ -131,13 +1049,219 @@ impl FnvHasher {
     }
 }
 
+/// The rounds of the Feistel network in [`shuffle_index`].
+const FEISTEL_ROUNDS: u8 = 4;
+
+/// Moves `index` to its place in a shuffle of `0..len` chosen by `seed`.
+/// Distinct indexes in range always land on distinct indexes in range; those outside it stay put.
+///
+/// A Feistel network permutes the smallest square power of two covering `len`, and is reapplied
+/// until the index lands back in range. Not cryptographically secure.
+pub fn shuffle_index(index: u64, len: u64, seed: &[u8]) -> u64 {
+    if index >= len {
+        return index;
+    }
+    let bits = (u64::BITS - (len - 1).leading_zeros()).max(1);
+    let half = bits.div_ceil(2);
+    let mask = (1 << half) - 1;
+    let mut shuffled = index;
+    loop {
+        let (mut left, mut right) = (shuffled >> half, shuffled & mask);
+        for round in 0..FEISTEL_ROUNDS {
+            let mut hasher = FnvHasher::new();
+            hasher.update(seed);
+            hasher.update(&[round]);
+            hasher.update(&right.to_le_bytes());
+            // FNV mixes its high bits best
+            let mixed = hasher.output() >> (u64::BITS - half);
+            (left, right) = (right, left ^ mixed);
+        }
+        shuffled = left << half | right;
+        if shuffled < len {
+            return shuffled;
+        }
+    }
+}
+
+/// Makes the random choices that determine an NFT's traits.
+pub struct TraitSelector {
+    rng: Rng,
+}
+
+impl TraitSelector {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            rng: Rng::with_seed(seed),
+        }
+    }
+
+    /// Picks one of `options` uniformly. Panics if `options` is empty.
+    pub fn pick<'a, T>(&mut self, options: &'a [T]) -> &'a T {
+        &options[self.rng.usize(..options.len())]
+    }
+
+    /// Picks an index with probability proportional to its weight.
+    /// Returns 0 if all the weights are zero.
+    pub fn weighted_pick(&mut self, weights: &[u32]) -> usize {
+        let total: u64 = weights.iter().map(|&w| w as u64).sum();
+        if total == 0 {
+            return 0;
+        }
+        let mut roll = self.rng.u64(..total);
+        for (i, &weight) in weights.iter().enumerate() {
+            if roll < weight as u64 {
+                return i;
+            }
+            roll -= weight as u64;
+        }
+        unreachable!("roll is less than the total weight")
+    }
+
+    /// The underlying random stream, for choices that aren't traits.
+    pub fn rng(&mut self) -> &mut Rng {
+        &mut self.rng
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::art::Image;
+    use alloy_primitives::{Address, U256};
 
-    use super::{zlib_format, Color};
+    use super::{
+        base64_encode, base64_encode_into, shuffle_index, zlib_format, Color, PngFilter,
+        StorageBytes, TraitSelector,
+    };
+    use base64::Engine;
     use std::io::Read;
 
+    #[test]
+    fn test_color_lerp() {
+        let a = Color::new(10, 200, 0);
+        let b = Color::new(250, 0, 255);
+        assert_eq!(Color::lerp(a, b, 0), a);
+        assert_eq!(Color::lerp(a, b, 255), b);
+        assert_eq!(Color::lerp(a, a, 77), a);
+
+        let mid = Color::lerp(Color::new(0, 0, 0), Color::new(255, 255, 255), 128);
+        assert_eq!(mid, Color::new(128, 128, 128));
+
+        // other spans blend by the same fraction
+        assert_eq!(Color::lerp_ratio(a, b, 0, 100), a);
+        assert_eq!(Color::lerp_ratio(a, b, 100, 100), b);
+        assert_eq!(Color::lerp_ratio(a, b, 1, 4), Color::new(70, 150, 63));
+    }
+
+    #[test]
+    fn test_color_approx_eq() {
+        let black = Color::from_hex(0x000000);
+        let near = Color::from_hex(0x010101);
+        assert!(black.approx_eq(near, 1));
+        assert!(near.approx_eq(black, 1));
+        assert!(!black.approx_eq(near, 0));
+        assert!(black.approx_eq(black, 0));
+
+        // every channel must be close, not just on average
+        let off = Color::new(0, 0, 3);
+        assert!(!black.approx_eq(off, 2));
+        assert!(Color::from_hex(0xffffff).approx_eq(Color::new(254, 255, 253), 2));
+    }
+
+    #[test]
+    fn test_hex_round_trip() {
+        for hex in [0x000000, 0xffffff, 0xe3066e, 0x123456, 0x00ff00, 0x0000ff] {
+            let color = Color::from_hex(hex);
+            assert_eq!(color.to_hex(), hex);
+            assert_eq!(Color::from_hex(color.to_hex()), color);
+        }
+        for color in [Color::new(1, 2, 3), Color::new(255, 0, 128)] {
+            assert_eq!(Color::from_hex(color.to_hex()), color);
+        }
+        assert_eq!(Color::from_hex(0xe3066e), Color::new(0xe3, 0x06, 0x6e));
+    }
+
+    #[test]
+    fn test_color_format() {
+        assert_eq!(format!("{}", Color::from_hex(0xe3066e)), "#e3066e");
+        assert_eq!(format!("{:?}", Color::new(0, 1, 255)), "#0001ff");
+        assert_eq!(format!("{}", Color::default()), "#000000");
+    }
+
+    #[test]
+    fn test_color_scale_and_invert() {
+        let color = Color::from_hex(0x40c0ff);
+        assert_eq!(color.scale(255), color);
+        assert_eq!(color.scale(0), Color::default());
+        assert_eq!(color.invert(), Color::from_hex(0xbf3f00));
+        assert_eq!(color.invert().invert(), color);
+    }
+
+    #[test]
+    fn test_hsv() {
+        assert_eq!(Color::from_hsv(0, 255, 255), Color::from_hex(0xff0000));
+        assert_eq!(Color::from_hsv(120, 255, 255), Color::from_hex(0x00ff00));
+        assert_eq!(Color::from_hsv(240, 255, 255), Color::from_hex(0x0000ff));
+        assert_eq!(Color::from_hsv(360, 255, 255), Color::from_hex(0xff0000));
+        assert_eq!(Color::from_hsv(77, 0, 90), Color::new(90, 90, 90));
+
+        assert_eq!(Color::from_hex(0xff0000).to_hsv(), (0, 255, 255));
+        assert_eq!(Color::from_hex(0x00ff00).to_hsv(), (120, 255, 255));
+        assert_eq!(Color::from_hex(0x0000ff).to_hsv(), (240, 255, 255));
+
+        for hue in 0..360 {
+            for (saturation, value) in [(255, 255), (200, 180), (128, 255)] {
+                let color = Color::from_hsv(hue, saturation, value);
+                let (h, s, v) = color.to_hsv();
+                let round_trip = Color::from_hsv(h, s, v);
+                assert!(
+                    color.approx_eq(round_trip, 4),
+                    "{color:?} vs {round_trip:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_trait_selector() {
+        let options = ["a", "b", "c", "d", "e", "f", "g", "h"];
+        let picks = |seed| {
+            let mut selector = TraitSelector::new(seed);
+            (0..16)
+                .map(|_| *selector.pick(&options))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(picks(42), picks(42));
+        assert_ne!(picks(42), picks(43));
+
+        let mut selector = TraitSelector::new(7);
+        for _ in 0..1000 {
+            assert_eq!(selector.weighted_pick(&[0, 1]), 1);
+            assert_eq!(selector.weighted_pick(&[0, 5, 0]), 1);
+            assert!(selector.weighted_pick(&[3, 0, 2]) != 1);
+        }
+        assert_eq!(selector.weighted_pick(&[0, 0]), 0);
+    }
+
+    #[test]
+    fn test_base64() {
+        let reference = |data: &[u8]| base64::engine::general_purpose::STANDARD.encode(data);
+        assert_eq!(base64_encode(b""), "");
+        assert_eq!(base64_encode(b"f"), "Zg==");
+        assert_eq!(base64_encode(b"fo"), "Zm8=");
+        assert_eq!(base64_encode(b"foo"), "Zm9v");
+
+        let mut rng = fastrand::Rng::with_seed(0xba5e);
+        for len in (0..64).chain([255, 256, 257, 1000, 1001, 1002]) {
+            let data: Vec<u8> = (0..len).map(|_| rng.u8(..)).collect();
+            assert_eq!(base64_encode(&data), reference(&data), "length {len}");
+        }
+
+        // appending leaves what's there
+        let mut out = String::from("data:,");
+        base64_encode_into(&[0xff; 4], &mut out);
+        assert_eq!(out, "data:,/////w==");
+    }
+
     #[test]
     fn test_deflate() {
         for len in [0, 1, 10, 100_000] {
@@ -151,6 +1275,31 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_deflate_fuzz() {
+        let inflate = |data: Vec<u8>| {
+            let mut reader = flate2::read::ZlibDecoder::new(std::io::Cursor::new(data));
+            let mut inflated = Vec::new();
+            reader.read_to_end(&mut inflated).unwrap();
+            inflated
+        };
+        let mut rng = fastrand::Rng::with_seed(0x7a11b);
+
+        // either side of the 65535-byte block size, then random lengths
+        let anchors = [0, 1, 65534, 65535, 65536, 2 * 65535, 2 * 65535 + 1];
+        let random: Vec<usize> = (0..64)
+            .map(|_| match rng.u8(..4) {
+                0 => rng.usize(..16),
+                1 => rng.usize(..1024),
+                _ => rng.usize(..200_000),
+            })
+            .collect();
+        for len in anchors.into_iter().chain(random) {
+            let input: Vec<u8> = core::iter::repeat_with(|| rng.u8(..)).take(len).collect();
+            assert_eq!(inflate(zlib_format(&input)), input, "length {len}");
+        }
+    }
+
     #[test]
     fn test_png() {
         let color = Color {
@@ -169,4 +1318,301 @@ mod tests {
             .is_some()
         {}
     }
+
+    #[test]
+    fn test_png_filters() {
+        let mut image: Image<24, 40> = Image::new(Color::default());
+        image.draw_gradient(Color::from_hex(0xff0000), Color::from_hex(0x0000ff));
+        image.pixels[5][7] = Color::from_hex(0x123456);
+
+        let filters = PngFilter::ALL.map(Some);
+        for filter in filters.into_iter().chain([None]) {
+            let encoded = image.make_png_with_filter(filter);
+            let decoder = png::Decoder::new(std::io::Cursor::new(encoded));
+            let mut reader = decoder.read_info().expect("Failed to read PNG info");
+            let mut buf = vec![0; reader.output_buffer_size()];
+            let info = reader
+                .next_frame(&mut buf)
+                .expect("Failed to read PNG data");
+            assert_eq!((info.width, info.height), (40, 24));
+
+            let expected: Vec<u8> = image
+                .pixels
+                .iter()
+                .flatten()
+                .flat_map(|p| [p.red, p.green, p.blue])
+                .collect();
+            assert_eq!(&buf[..info.buffer_size()], expected, "filter {filter:?}");
+        }
+    }
+
+    #[test]
+    fn test_from_rgb_bytes() {
+        let mut image: Image<5, 7> = Image::new(Color::default());
+        image.draw_gradient(Color::from_hex(0xff8000), Color::from_hex(0x0040ff));
+        image.pixels[3][6] = Color::from_hex(0x123456);
+
+        // strip the filter byte from the start of each unfiltered scanline
+        let scanlines = image.filtered_pixel_data(Some(PngFilter::None));
+        let rgb: Vec<u8> = scanlines
+            .chunks(1 + 7 * 3)
+            .flat_map(|line| &line[1..])
+            .copied()
+            .collect();
+        let decoded = Image::<5, 7>::from_rgb_bytes(&rgb).expect("decodes");
+        assert_eq!(decoded.pixels, image.pixels);
+
+        assert!(Image::<5, 7>::from_rgb_bytes(&rgb[1..]).is_none());
+        assert!(Image::<7, 5>::from_rgb_bytes(&rgb).is_some());
+        assert!(Image::<5, 8>::from_rgb_bytes(&rgb).is_none());
+    }
+
+    #[test]
+    fn test_packed_bytes() {
+        let flat: Image<32, 32> = Image::new(Color::from_hex(0x336699));
+        let mut framed = Image::<32, 32>::new(Color::from_hex(0xffffff));
+        framed.draw_border(2, Color::from_hex(0x000000));
+        let mut noisy: Image<7, 5> = Image::new(Color::default());
+        noisy.draw_gradient(Color::from_hex(0xff8000), Color::from_hex(0x0040ff));
+        let art = crate::art::generate_nft(Address::ZERO, U256::from(7));
+
+        // 1024 identical pixels take 5 runs of at most 255
+        assert_eq!(flat.to_packed_bytes().len(), 5 * 4);
+        assert!(framed.to_packed_bytes().len() < 32 * 32 * 3 / 4);
+        for image in [&flat, &framed, &art] {
+            let decoded = Image::<32, 32>::from_packed_bytes(&image.to_packed_bytes());
+            assert_eq!(
+                decoded.map(|image| image.pixels),
+                Some(image.pixels.clone())
+            );
+        }
+        let decoded = Image::<7, 5>::from_packed_bytes(&noisy.to_packed_bytes());
+        assert_eq!(
+            decoded.map(|image| image.pixels),
+            Some(noisy.pixels.clone())
+        );
+
+        // truncated, overlong, or zero-length runs are rejected
+        let packed = framed.to_packed_bytes();
+        assert!(Image::<32, 32>::from_packed_bytes(&packed[..packed.len() - 4]).is_none());
+        assert!(Image::<32, 32>::from_packed_bytes(&packed[..packed.len() - 1]).is_none());
+        assert!(
+            Image::<32, 32>::from_packed_bytes(&[&packed[..], &[1, 0, 0, 0]].concat()).is_none()
+        );
+        assert!(
+            Image::<32, 32>::from_packed_bytes(&[&[0, 1, 2, 3], &packed[..]].concat()).is_none()
+        );
+        assert!(Image::<32, 31>::from_packed_bytes(&packed).is_none());
+    }
+
+    #[test]
+    fn test_store_packed() {
+        let vm = crate::test_vm::TestVm::new();
+        let mut storage: StorageBytes = vm.contract();
+        assert!(Image::<32, 32>::load_packed(&storage).is_none());
+
+        let mut image: Image<32, 32> = Image::new(Color::from_hex(0x101010));
+        image.draw_checkerboard(8, Color::from_hex(0xe3066e), Color::from_hex(0x000000));
+        image.store_packed(&mut storage);
+        let loaded = Image::<32, 32>::load_packed(&storage);
+        assert_eq!(loaded.map(|image| image.pixels), Some(image.pixels));
+        assert!(Image::<16, 16>::load_packed(&storage).is_none());
+    }
+
+    #[test]
+    fn test_svg() {
+        let mut image: Image<2, 3> = Image::new(Color::from_hex(0xe3066e));
+        image.pixels[1][1] = Color::from_hex(0x00ff00);
+        let svg = String::from_utf8(image.make_svg()).unwrap();
+        assert_eq!(
+            svg,
+            concat!(
+                r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 3 2" shape-rendering="crispEdges">"#,
+                r##"<rect x="0" y="0" width="3" height="1" fill="#e3066e"/>"##,
+                r##"<rect x="0" y="1" width="1" height="1" fill="#e3066e"/>"##,
+                r##"<rect x="1" y="1" width="1" height="1" fill="#00ff00"/>"##,
+                r##"<rect x="2" y="1" width="1" height="1" fill="#e3066e"/>"##,
+                "</svg>",
+            )
+        );
+    }
+
+    /// Lists the names of the chunks in a PNG.
+    fn chunk_names(png: &[u8]) -> Vec<String> {
+        let mut names = Vec::new();
+        let mut rest = &png[8..];
+        while !rest.is_empty() {
+            let len = u32::from_be_bytes(rest[..4].try_into().unwrap()) as usize;
+            names.push(String::from_utf8(rest[4..8].to_vec()).unwrap());
+            rest = &rest[12 + len..];
+        }
+        names
+    }
+
+    #[test]
+    fn test_apng() {
+        let mut frames: Vec<Image<16, 12>> = Vec::new();
+        for i in 0..3 {
+            let mut frame = Image::new(Color::new(40 * i, 0, 0));
+            frame.pixels[i as usize][i as usize] = Color::from_hex(0xffffff);
+            frames.push(frame);
+        }
+        let apng = Image::make_apng(&frames, 250);
+
+        assert_eq!(
+            chunk_names(&apng),
+            ["IHDR", "acTL", "fcTL", "IDAT", "fcTL", "fdAT", "fcTL", "fdAT", "IEND"]
+        );
+
+        let decode = |encoded: Vec<u8>| {
+            let decoder = png::Decoder::new(std::io::Cursor::new(encoded));
+            let mut reader = decoder.read_info().expect("Failed to read PNG info");
+            let animation = reader.info().animation_control;
+            let mut buf = vec![0; reader.output_buffer_size()];
+            reader
+                .next_frame(&mut buf)
+                .expect("Failed to read PNG data");
+            (buf, animation.map(|a| (a.num_frames, a.num_plays)))
+        };
+        let (first_frame, animation) = decode(apng);
+        let (still, no_animation) = decode(frames[0].make_png());
+        assert_eq!(first_frame, still);
+        assert_eq!(animation, Some((3, 0)));
+        assert_eq!(no_animation, None);
+
+        assert!(Image::<4, 4>::make_apng(&[], 100).is_empty());
+    }
+
+    /// Decodes a GIF into RGB frames.
+    fn decode_gif(encoded: Vec<u8>) -> Vec<Vec<u8>> {
+        let mut options = gif::DecodeOptions::new();
+        options.set_color_output(gif::ColorOutput::RGBA);
+        let mut decoder = options
+            .read_info(std::io::Cursor::new(encoded))
+            .expect("Failed to read GIF info");
+        let mut frames = Vec::new();
+        while let Some(frame) = decoder.read_next_frame().expect("Failed to read GIF data") {
+            let rgb = frame.buffer.chunks(4).flat_map(|p| [p[0], p[1], p[2]]);
+            frames.push(rgb.collect());
+        }
+        frames
+    }
+
+    fn rgb<const R: usize, const C: usize>(image: &Image<R, C>) -> Vec<u8> {
+        image
+            .pixels
+            .iter()
+            .flatten()
+            .flat_map(|p| [p.red, p.green, p.blue])
+            .collect()
+    }
+
+    #[test]
+    fn test_gif() {
+        let mut image: Image<20, 30> = Image::new(Color::from_hex(0x102030));
+        for x in 0..30 {
+            image.pixels[x % 20][x] = Color::from_hsv(x as u16 * 12, 255, 255);
+        }
+        assert_eq!(decode_gif(image.make_gif()), [rgb(&image)]);
+
+        // a single color still needs a valid palette
+        let flat: Image<3, 3> = Image::new(Color::from_hex(0xabcdef));
+        assert_eq!(decode_gif(flat.make_gif()), [rgb(&flat)]);
+
+        // enough data to fill the LZW table several times
+        let mut noisy: Image<128, 128> = Image::new(Color::default());
+        let mut rng = fastrand::Rng::with_seed(1);
+        for pixel in noisy.pixels.iter_mut().flatten() {
+            *pixel = Color::from_hex(rng.u32(..200) * 0x010101);
+        }
+        assert_eq!(decode_gif(noisy.make_gif()), [rgb(&noisy)]);
+    }
+
+    #[test]
+    fn test_gif_quantized() {
+        let mut image: Image<32, 32> = Image::new(Color::default());
+        image.draw_gradient(Color::from_hex(0xff8000), Color::from_hex(0x0080ff));
+        let frames = decode_gif(image.make_gif());
+        for (decoded, source) in frames[0].iter().zip(rgb(&image)) {
+            assert!(decoded.abs_diff(source) <= 64);
+        }
+    }
+
+    #[test]
+    fn test_animated_gif() {
+        let frames: Vec<Image<8, 8>> = (0..3)
+            .map(|i| Image::new(Color::new(0, 80 * i, 0)))
+            .collect();
+        let decoded = decode_gif(Image::make_animated_gif(&frames, 20));
+        assert_eq!(decoded, frames.iter().map(rgb).collect::<Vec<_>>());
+    }
+
+    /// Decodes a WebP into RGB.
+    fn decode_webp(encoded: Vec<u8>) -> ((u32, u32), Vec<u8>) {
+        let mut decoder = image_webp::WebPDecoder::new(std::io::Cursor::new(encoded))
+            .expect("Failed to read WebP header");
+        let mut buf = vec![0; decoder.output_buffer_size().unwrap()];
+        decoder
+            .read_image(&mut buf)
+            .expect("Failed to read WebP data");
+        if decoder.has_alpha() {
+            buf = buf.chunks(4).flat_map(|p| [p[0], p[1], p[2]]).collect();
+        }
+        (decoder.dimensions(), buf)
+    }
+
+    #[test]
+    fn test_webp() {
+        let mut image: Image<24, 40> = Image::new(Color::default());
+        image.draw_gradient(Color::from_hex(0xff0000), Color::from_hex(0x0000ff));
+        image.pixels[5][7] = Color::from_hex(0x123456);
+        assert_eq!(decode_webp(image.make_webp()), ((40, 24), rgb(&image)));
+
+        let flat: Image<3, 5> = Image::new(Color::from_hex(0xabcdef));
+        assert_eq!(decode_webp(flat.make_webp()), ((5, 3), rgb(&flat)));
+
+        let mut noisy: Image<64, 64> = Image::new(Color::default());
+        let mut rng = fastrand::Rng::with_seed(1);
+        for pixel in noisy.pixels.iter_mut().flatten() {
+            *pixel = Color::from_hex(rng.u32(..1 << 24));
+        }
+        assert_eq!(decode_webp(noisy.make_webp()), ((64, 64), rgb(&noisy)));
+    }
+
+    #[test]
+    fn test_huffman_lengths() {
+        // a skewed distribution must still respect the length limit
+        let counts: Vec<u32> = (0..30).map(|i| 1 << i).collect();
+        let lengths = super::huffman_lengths(&counts, 15);
+        assert!(lengths.iter().all(|&l| (1..=15).contains(&l)));
+        let kraft: f64 = lengths.iter().map(|&l| 0.5_f64.powi(l as i32)).sum();
+        assert_eq!(kraft, 1.0);
+    }
+
+    #[test]
+    fn test_shuffle_index() {
+        for len in [1, 2, 3, 10, 100, 10_000] {
+            let mut seen = vec![false; len as usize];
+            for index in 0..len {
+                let shuffled = shuffle_index(index, len, b"seed");
+                assert!(shuffled < len);
+                assert!(!seen[shuffled as usize], "{index} collides in 0..{len}");
+                seen[shuffled as usize] = true;
+            }
+        }
+
+        // the seed picks the shuffle
+        let shuffle = |seed: &[u8]| -> Vec<u64> {
+            (0..10_000)
+                .map(|index| shuffle_index(index, 10_000, seed))
+                .collect()
+        };
+        let identity: Vec<u64> = (0..10_000).collect();
+        assert_ne!(shuffle(b"seed"), identity);
+        assert_ne!(shuffle(b"seed"), shuffle(b"other seed"));
+        assert_eq!(shuffle(b"seed"), shuffle(b"seed"));
+
+        assert_eq!(shuffle_index(10_000, 10_000, b"seed"), 10_000);
+        assert!(shuffle_index(u64::MAX - 1, u64::MAX, b"seed") < u64::MAX);
+    }
 }
@@ -0,0 +1,158 @@
+//! A minimal JSON builder for on-chain metadata.
+//!
+//! Values are built as a tree and written out with [`Value::to_string`], which handles
+//! escaping and separators so callers can't produce malformed JSON.
+
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::fmt::{self, Write};
+
+/// A JSON value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Value {
+    String(String),
+    Number(u64),
+    Array(Vec<Value>),
+    /// Fields are written in the order given.
+    Object(Vec<(String, Value)>),
+}
+
+impl Value {
+    pub fn string(value: impl Into<String>) -> Self {
+        Self::String(value.into())
+    }
+
+    pub fn array(values: impl IntoIterator<Item = Value>) -> Self {
+        Self::Array(values.into_iter().collect())
+    }
+
+    pub fn object<K: Into<String>>(fields: impl IntoIterator<Item = (K, Value)>) -> Self {
+        Self::Object(fields.into_iter().map(|(k, v)| (k.into(), v)).collect())
+    }
+}
+
+/// Writes `value` as a quoted JSON string, escaping quotes, backslashes, and control characters.
+fn write_string(f: &mut fmt::Formatter, value: &str) -> fmt::Result {
+    f.write_char('"')?;
+    for c in value.chars() {
+        match c {
+            '"' => f.write_str("\\\"")?,
+            '\\' => f.write_str("\\\\")?,
+            '\n' => f.write_str("\\n")?,
+            '\r' => f.write_str("\\r")?,
+            '\t' => f.write_str("\\t")?,
+            c if c < ' ' => write!(f, "\\u{:04x}", c as u32)?,
+            c => f.write_char(c)?,
+        }
+    }
+    f.write_char('"')
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::String(value) => write_string(f, value),
+            Self::Number(value) => write!(f, "{value}"),
+            Self::Array(values) => {
+                f.write_char('[')?;
+                for (i, value) in values.iter().enumerate() {
+                    if i > 0 {
+                        f.write_char(',')?;
+                    }
+                    value.fmt(f)?;
+                }
+                f.write_char(']')
+            }
+            Self::Object(fields) => {
+                f.write_char('{')?;
+                for (i, (key, value)) in fields.iter().enumerate() {
+                    if i > 0 {
+                        f.write_char(',')?;
+                    }
+                    write_string(f, key)?;
+                    f.write_char(':')?;
+                    value.fmt(f)?;
+                }
+                f.write_char('}')
+            }
+        }
+    }
+}
+
+impl From<&str> for Value {
+    fn from(value: &str) -> Self {
+        Self::String(value.to_string())
+    }
+}
+
+impl From<u64> for Value {
+    fn from(value: u64) -> Self {
+        Self::Number(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Parses `json` with `serde_json`, failing on anything malformed.
+    fn parse(json: &str) -> Option<serde_json::Value> {
+        serde_json::from_str(json).ok()
+    }
+
+    /// The `serde_json` value a [`Value`] should parse back into.
+    fn expected(value: &Value) -> serde_json::Value {
+        match value {
+            Value::String(value) => value.as_str().into(),
+            Value::Number(value) => (*value).into(),
+            Value::Array(values) => values.iter().map(expected).collect(),
+            Value::Object(fields) => fields
+                .iter()
+                .map(|(key, value)| (key.clone(), expected(value)))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_escaping() {
+        let name = r#"The "Best" NFT \o/"#;
+        let value = Value::object([("name", name.into()), ("id", 7.into())]);
+        let json = value.to_string();
+        assert_eq!(json, r#"{"name":"The \"Best\" NFT \\o/","id":7}"#);
+        assert_eq!(parse(&json), Some(expected(&value)));
+
+        let control = Value::string("line\nbreak\ttab\u{1}bell");
+        let json = control.to_string();
+        assert_eq!(json, r#""line\nbreak\ttab\u0001bell""#);
+        assert_eq!(parse(&json), Some("line\nbreak\ttab\u{1}bell".into()));
+    }
+
+    #[test]
+    fn test_nesting() {
+        let value = Value::object([
+            ("name", "Stylus \u{1f980}".into()),
+            ("empty", Value::array([])),
+            (
+                "attributes",
+                Value::array([
+                    Value::object([("trait_type", "Tone".into()), ("value", "None".into())]),
+                    Value::object::<&str>([]),
+                ]),
+            ),
+        ]);
+        let json = value.to_string();
+        assert_eq!(
+            json,
+            r#"{"name":"Stylus 🦀","empty":[],"attributes":[{"trait_type":"Tone","value":"None"},{}]}"#
+        );
+        assert_eq!(parse(&json), Some(expected(&value)));
+        assert_eq!(parse(&json).unwrap()["name"], "Stylus \u{1f980}");
+
+        // the parser catches what escaping prevents
+        assert_eq!(parse(r#"{"name":"a"b"}"#), None);
+        assert_eq!(parse("\"a\nb\""), None);
+        assert_eq!(parse(r#"[1,]"#), None);
+    }
+}